@@ -0,0 +1,61 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use data_cache::api::cache::Cache;
+use uuid::Uuid;
+
+const ROW_COUNTS: [usize; 3] = [1_000, 10_000, 100_000];
+
+fn create_cache(row_count: usize) -> (Cache, Vec<Uuid>) {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "unknown").unwrap();
+    cache.add_f64_column("age", "Age", "0").unwrap();
+    cache
+        .add_time_date_column("start_time", "Start Time", "%Y-%m-%d %H:%M:%S", "")
+        .unwrap();
+
+    let guids = (0..row_count)
+        .map(|index| {
+            cache
+                .create_row(format!("name {},{},2020-01-01 00:00:00", index, index).as_str())
+                .unwrap()
+        })
+        .collect();
+
+    (cache, guids)
+}
+
+fn bench_update_row(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("update_row");
+    for row_count in ROW_COUNTS {
+        let (mut cache, guids) = create_cache(row_count);
+        let guid = guids[row_count / 2];
+        group.bench_with_input(
+            BenchmarkId::from_parameter(row_count),
+            &row_count,
+            |bencher, _| {
+                bencher.iter(|| {
+                    cache
+                        .update_row(&guid, "fred,42,2021-01-01 00:00:00")
+                        .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_csv_for_guid(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("csv_for_guid");
+    for row_count in ROW_COUNTS {
+        let (cache, guids) = create_cache(row_count);
+        let guid = guids[row_count / 2];
+        group.bench_with_input(
+            BenchmarkId::from_parameter(row_count),
+            &row_count,
+            |bencher, _| bencher.iter(|| cache.csv_for_guid(&guid).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_update_row, bench_csv_for_guid);
+criterion_main!(benches);
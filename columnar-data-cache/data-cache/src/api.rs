@@ -1,6 +1,24 @@
+pub mod aggregation;
+pub mod arrow_conversion;
 pub mod cache;
 mod cache_error;
+pub mod chunked;
 pub mod column;
 mod column_metadata;
 pub mod column_storage;
+pub mod concurrent_cache;
+pub mod csv_loader;
+pub mod derived;
+pub mod events;
+mod json;
+pub mod memory_usage;
+pub mod migration;
 pub mod parsers;
+pub mod query;
+pub mod retention;
+pub mod row;
+pub mod search;
+pub mod snapshot;
+pub mod transaction;
+pub mod validation;
+pub mod value;
@@ -0,0 +1,185 @@
+use crate::api::cache_error::CacheError;
+use crate::api::chunked::Chunked;
+use crate::api::column_storage::ColumnStorage;
+use crate::api::value::Value;
+use std::cmp::Ordering;
+
+/// Summary statistics of a F64 column. Nulls are skipped the way SQL does, so count is the
+/// number of non null values and sum, min, max and mean are None when there are no values.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Aggregate {
+    pub row_count: usize,
+    pub count: usize,
+    pub sum: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+}
+
+impl Aggregate {
+    fn add(&mut self, value: &Option<f64>) {
+        self.row_count += 1;
+        if let Some(value) = value {
+            self.count += 1;
+            self.sum = Some(self.sum.unwrap_or(0.0) + value);
+            self.min = Some(self.min.map_or(*value, |min| min.min(*value)));
+            self.max = Some(self.max.map_or(*value, |max| max.max(*value)));
+            self.mean = self.sum.map(|sum| sum / self.count as f64);
+        }
+    }
+}
+
+/// One group of an aggregation, a None group holds the rows where the group column is null.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AggregateRow {
+    pub group: Option<Value>,
+    pub aggregate: Aggregate,
+}
+
+/// The result of a group by, with one row per group that has at least one row, ordered by group.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AggregateTable {
+    pub column: String,
+    pub group_column: String,
+    pub rows: Vec<AggregateRow>,
+}
+
+impl AggregateTable {
+    pub fn get(&self, group: Option<&Value>) -> Option<&Aggregate> {
+        self.rows
+            .iter()
+            .find(|row| row.group.as_ref() == group)
+            .map(|row| &row.aggregate)
+    }
+}
+
+fn get_f64_data(column_store: &ColumnStorage) -> Result<&Chunked<Option<f64>>, CacheError> {
+    match column_store {
+        ColumnStorage::F64Storage { data, .. } => Ok(data),
+        _ => Err(CacheError::UnsupportedType(format!(
+            "{:?}",
+            column_store.get_data_type()
+        ))),
+    }
+}
+
+// returns the possible groups and the index of the group for each row
+fn get_groups(
+    column_store: &ColumnStorage,
+) -> Result<(Vec<Option<Value>>, Vec<usize>), CacheError> {
+    match column_store {
+        ColumnStorage::BooleanStorage { data, .. } => {
+            let groups = vec![
+                None,
+                Some(Value::Boolean(false)),
+                Some(Value::Boolean(true)),
+            ];
+            let group_indices = data
+                .iter()
+                .map(|value| match value {
+                    None => 0,
+                    Some(false) => 1,
+                    Some(true) => 2,
+                })
+                .collect();
+            Ok((groups, group_indices))
+        }
+        ColumnStorage::EnumeratedStorage {
+            data,
+            allowed_values,
+            ..
+        } => {
+            let mut groups = vec![None];
+            groups.extend(
+                allowed_values
+                    .iter()
+                    .map(|allowed_value| Some(Value::Enumerated(allowed_value.clone()))),
+            );
+            // the group of a value is its code moved up one to make room for nulls
+            let group_indices = data
+                .iter()
+                .map(|code| code.map_or(0, |code| code as usize + 1))
+                .collect();
+            Ok((groups, group_indices))
+        }
+        _ => Err(CacheError::UnsupportedType(format!(
+            "{:?}",
+            column_store.get_data_type()
+        ))),
+    }
+}
+
+pub(crate) fn aggregate(column_store: &ColumnStorage) -> Result<Aggregate, CacheError> {
+    let mut aggregate = Aggregate::default();
+    for value in get_f64_data(column_store)? {
+        aggregate.add(value);
+    }
+    Ok(aggregate)
+}
+
+pub(crate) fn aggregate_by(
+    column_store: &ColumnStorage,
+    group_column_store: &ColumnStorage,
+) -> Result<AggregateTable, CacheError> {
+    let data = get_f64_data(column_store)?;
+    let (groups, group_indices) = get_groups(group_column_store)?;
+
+    let mut aggregates = vec![Aggregate::default(); groups.len()];
+    for (value, group_index) in data.iter().zip(group_indices) {
+        aggregates[group_index].add(value);
+    }
+
+    let mut rows: Vec<AggregateRow> = groups
+        .into_iter()
+        .zip(aggregates)
+        .filter(|(_, aggregate)| aggregate.row_count > 0)
+        .map(|(group, aggregate)| AggregateRow { group, aggregate })
+        .collect();
+    rows.sort_by(|left, right| {
+        left.group
+            .partial_cmp(&right.group)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    Ok(AggregateTable {
+        column: column_store.get_column().name.clone(),
+        group_column: group_column_store.get_column().name.clone(),
+        rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate_skips_nulls() {
+        let mut aggregate = Aggregate::default();
+        aggregate.add(&Some(2.0));
+        aggregate.add(&None);
+        aggregate.add(&Some(-1.0));
+        aggregate.add(&Some(5.0));
+
+        assert_eq!(
+            aggregate,
+            Aggregate {
+                row_count: 4,
+                count: 3,
+                sum: Some(6.0),
+                min: Some(-1.0),
+                max: Some(5.0),
+                mean: Some(2.0),
+            }
+        );
+    }
+
+    #[test]
+    fn test_aggregate_only_nulls() {
+        let mut aggregate = Aggregate::default();
+        aggregate.add(&None);
+        assert_eq!(aggregate.row_count, 1);
+        assert_eq!(aggregate.count, 0);
+        assert_eq!(aggregate.sum, None);
+        assert_eq!(aggregate.mean, None);
+    }
+}
@@ -0,0 +1,731 @@
+//! Conversion between a cache and Arrow record batches, and reading and writing Parquet files.
+//!
+//! Each column becomes a field with the same name and the closest Arrow type. Enumerated columns
+//! are dictionary encoded using the allowed values. The display name, default value and format are
+//! kept in the field metadata so a cache can be rebuilt without losing its column definitions.
+//! Decimals use Decimal128 with the column scale, durations are stored in microseconds and
+//! uuids as 16 byte fixed size binaries.
+//! Allowed values are kept in the metadata too since Parquet drops unused dictionary values.
+//! The guids are written as an extra string field named _guid.
+//! Importing fails with the column name when a value cannot be held by the column type.
+
+use crate::api::cache_error::CacheError;
+use crate::api::chunked::Chunked;
+use crate::api::column::Column;
+use crate::api::column_storage::{
+    check_allowed_values, find_code, ColumnStorage, ColumnStorageDataType,
+};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Date32Type, Decimal128Type, DurationMicrosecondType, DurationMillisecondType,
+    DurationNanosecondType, DurationSecondType, Float32Type, Float64Type, Int32Type, Int64Type,
+    Time64NanosecondType, TimestampMicrosecondType, TimestampMillisecondType,
+    TimestampNanosecondType, TimestampSecondType,
+};
+use arrow_array::{
+    Array, ArrayRef, BooleanArray, Date32Array, Decimal128Array, DictionaryArray,
+    DurationMicrosecondArray, FixedSizeBinaryArray, Float64Array, Int64Array, RecordBatch,
+    StringArray, Time64NanosecondArray, TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{Duration, NaiveDate, NaiveTime, Timelike};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const GUID_FIELD_NAME: &str = "_guid";
+const DISPLAY_NAME_KEY: &str = "display_name";
+const DEFAULT_VALUE_KEY: &str = "default_value";
+const FORMAT_KEY: &str = "format";
+const ALLOWED_VALUES_KEY: &str = "allowed_values";
+const DEFAULT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H:%M:%S";
+const DECIMAL_PRECISION: u8 = 38;
+const UUID_SIZE: i32 = 16;
+
+fn to_arrow_data_type(data_type: &ColumnStorageDataType, scale: u32) -> DataType {
+    match data_type {
+        ColumnStorageDataType::String => DataType::Utf8,
+        ColumnStorageDataType::Boolean => DataType::Boolean,
+        ColumnStorageDataType::F64 => DataType::Float64,
+        ColumnStorageDataType::TimeDate => DataType::Timestamp(TimeUnit::Microsecond, None),
+        ColumnStorageDataType::Enumerated => {
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+        }
+        ColumnStorageDataType::I64 => DataType::Int64,
+        ColumnStorageDataType::Decimal => DataType::Decimal128(DECIMAL_PRECISION, scale as i8),
+        ColumnStorageDataType::Date => DataType::Date32,
+        ColumnStorageDataType::Time => DataType::Time64(TimeUnit::Nanosecond),
+        ColumnStorageDataType::Duration => DataType::Duration(TimeUnit::Microsecond),
+        ColumnStorageDataType::Uuid => DataType::FixedSizeBinary(UUID_SIZE),
+    }
+}
+
+fn unix_epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+}
+
+// allowed values are stored as a single csv record so values may contain commas
+fn encode_allowed_values(allowed_values: &[String]) -> String {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_writer(vec![]);
+    writer.write_record(allowed_values).unwrap();
+    let bytes = writer.into_inner().unwrap();
+    String::from_utf8(bytes).unwrap().trim_end().to_string()
+}
+
+fn decode_allowed_values(encoded: &str) -> Vec<String> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(encoded.as_bytes())
+        .records()
+        .flatten()
+        .flat_map(|record| {
+            record
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+        })
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+fn to_field(column_store: &ColumnStorage) -> Field {
+    let column = column_store.get_column();
+    let mut metadata = HashMap::new();
+    metadata.insert(DISPLAY_NAME_KEY.to_string(), column.display_name.clone());
+    metadata.insert(DEFAULT_VALUE_KEY.to_string(), column.default_value.clone());
+    if matches!(
+        column_store.get_data_type(),
+        ColumnStorageDataType::TimeDate | ColumnStorageDataType::Date | ColumnStorageDataType::Time
+    ) {
+        metadata.insert(FORMAT_KEY.to_string(), column_store.get_format());
+    }
+    if column_store.get_data_type() == ColumnStorageDataType::Enumerated {
+        metadata.insert(
+            ALLOWED_VALUES_KEY.to_string(),
+            encode_allowed_values(&column_store.get_allowed_values()),
+        );
+    }
+
+    Field::new(
+        &column.name,
+        to_arrow_data_type(&column_store.get_data_type(), column_store.get_scale()),
+        true,
+    )
+    .with_metadata(metadata)
+}
+
+fn to_array(column_store: &ColumnStorage) -> Result<ArrayRef, CacheError> {
+    let array: ArrayRef = match column_store {
+        ColumnStorage::StringStorage { data, .. } => Arc::new(StringArray::from(data.to_vec())),
+        ColumnStorage::BooleanStorage { data, .. } => Arc::new(BooleanArray::from(data.to_vec())),
+        ColumnStorage::F64Storage { data, .. } => Arc::new(Float64Array::from(data.to_vec())),
+        ColumnStorage::TimeDateStorage { data, .. } => Arc::new(TimestampMicrosecondArray::from(
+            data.iter()
+                .map(|value| value.map(|value| value.and_utc().timestamp_micros()))
+                .collect::<Vec<_>>(),
+        )),
+        ColumnStorage::EnumeratedStorage {
+            data,
+            allowed_values,
+            ..
+        } => {
+            // the codes are the positions in allowed values so they can be used as keys
+            let keys: Vec<Option<i32>> = data
+                .iter()
+                .map(|code| code.map(|code| code as i32))
+                .collect();
+            let values = Arc::new(StringArray::from(allowed_values.clone()));
+            Arc::new(DictionaryArray::<Int32Type>::try_new(keys.into(), values)?)
+        }
+        ColumnStorage::I64Storage { data, .. } => Arc::new(Int64Array::from(data.to_vec())),
+        ColumnStorage::DecimalStorage { data, scale, .. } => {
+            // the mantissa is only the value when the decimal has the column scale
+            let mut mantissas = vec![];
+            for value in data {
+                mantissas.push(match value {
+                    Some(mut value) => {
+                        value.rescale(*scale);
+                        if value.scale() != *scale {
+                            return Err(CacheError::UnsupportedType(format!(
+                                "decimal {} with scale {}",
+                                value, scale
+                            )));
+                        }
+                        Some(value.mantissa())
+                    }
+                    None => None,
+                });
+            }
+            Arc::new(
+                Decimal128Array::from(mantissas)
+                    .with_precision_and_scale(DECIMAL_PRECISION, *scale as i8)?,
+            )
+        }
+        ColumnStorage::DateStorage { data, .. } => Arc::new(Date32Array::from(
+            data.iter()
+                .map(|value| value.map(|value| (value - unix_epoch()).num_days() as i32))
+                .collect::<Vec<_>>(),
+        )),
+        ColumnStorage::TimeStorage { data, .. } => Arc::new(Time64NanosecondArray::from(
+            data.iter()
+                .map(|value| {
+                    value.map(|value| {
+                        value.num_seconds_from_midnight() as i64 * 1_000_000_000
+                            + value.nanosecond() as i64
+                    })
+                })
+                .collect::<Vec<_>>(),
+        )),
+        ColumnStorage::DurationStorage { data, .. } => {
+            let mut microseconds = vec![];
+            for value in data {
+                microseconds.push(match value {
+                    Some(value) => Some(value.num_microseconds().ok_or_else(|| {
+                        CacheError::UnsupportedType(format!("duration {}", value))
+                    })?),
+                    None => None,
+                });
+            }
+            Arc::new(DurationMicrosecondArray::from(microseconds))
+        }
+        ColumnStorage::UuidStorage { data, .. } => {
+            Arc::new(FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                data.iter()
+                    .map(|value| value.map(|value| value.into_bytes())),
+                UUID_SIZE,
+            )?)
+        }
+    };
+
+    Ok(array)
+}
+
+pub(crate) fn to_record_batch(
+    column_stores: &[ColumnStorage],
+    guids: &Chunked<Uuid>,
+) -> Result<RecordBatch, CacheError> {
+    let mut fields = vec![Field::new(GUID_FIELD_NAME, DataType::Utf8, false)];
+    let mut arrays: Vec<ArrayRef> = vec![Arc::new(StringArray::from(
+        guids
+            .iter()
+            .map(|guid| guid.to_string())
+            .collect::<Vec<_>>(),
+    ))];
+
+    for column_store in column_stores {
+        fields.push(to_field(column_store));
+        arrays.push(to_array(column_store)?);
+    }
+
+    Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?)
+}
+
+fn get_metadata(field: &Field, key: &str, default_value: &str) -> String {
+    field
+        .metadata()
+        .get(key)
+        .cloned()
+        .unwrap_or_else(|| default_value.to_string())
+}
+
+fn strings(array: &dyn Array) -> Vec<Option<String>> {
+    let array = array.as_string::<i32>();
+    array
+        .iter()
+        .map(|value| value.map(|value| value.to_string()))
+        .collect()
+}
+
+fn invalid_data(field: &Field, reason: impl ToString) -> CacheError {
+    CacheError::InvalidColumnData {
+        column: field.name().to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+fn from_field(field: &Field, array: &dyn Array) -> Result<ColumnStorage, CacheError> {
+    let column = Column::new(
+        field.name(),
+        &get_metadata(field, DISPLAY_NAME_KEY, field.name()),
+        &get_metadata(field, DEFAULT_VALUE_KEY, ""),
+    );
+
+    let column_store = match field.data_type() {
+        DataType::Utf8 => ColumnStorage::StringStorage {
+            column,
+            data: strings(array).into(),
+            column_type: ColumnStorageDataType::String,
+        },
+        DataType::Boolean => ColumnStorage::BooleanStorage {
+            column,
+            data: array.as_boolean().iter().collect(),
+            column_type: ColumnStorageDataType::Boolean,
+        },
+        DataType::Float64 => ColumnStorage::F64Storage {
+            column,
+            data: array.as_primitive::<Float64Type>().iter().collect(),
+            column_type: ColumnStorageDataType::F64,
+        },
+        DataType::Float32 => ColumnStorage::F64Storage {
+            column,
+            data: array
+                .as_primitive::<Float32Type>()
+                .iter()
+                .map(|value| value.map(f64::from))
+                .collect(),
+            column_type: ColumnStorageDataType::F64,
+        },
+        DataType::Int64 => ColumnStorage::I64Storage {
+            column,
+            data: array.as_primitive::<Int64Type>().iter().collect(),
+            column_type: ColumnStorageDataType::I64,
+        },
+        DataType::Int32 => ColumnStorage::I64Storage {
+            column,
+            data: array
+                .as_primitive::<Int32Type>()
+                .iter()
+                .map(|value| value.map(i64::from))
+                .collect(),
+            column_type: ColumnStorageDataType::I64,
+        },
+        DataType::Decimal128(_, scale) if (0..=28).contains(scale) => {
+            ColumnStorage::DecimalStorage {
+                column,
+                data: array
+                    .as_primitive::<Decimal128Type>()
+                    .iter()
+                    .map(|value| {
+                        value
+                            .map(|value| {
+                                Decimal::try_from_i128_with_scale(value, *scale as u32).map_err(
+                                    |_| {
+                                        invalid_data(
+                                            field,
+                                            format!(
+                                                "{} with scale {} is too large for a decimal",
+                                                value, scale
+                                            ),
+                                        )
+                                    },
+                                )
+                            })
+                            .transpose()
+                    })
+                    .collect::<Result<_, _>>()?,
+                scale: *scale as u32,
+                column_type: ColumnStorageDataType::Decimal,
+            }
+        }
+        DataType::Date32 => ColumnStorage::DateStorage {
+            column,
+            data: array
+                .as_primitive::<Date32Type>()
+                .iter()
+                .map(|value| {
+                    value
+                        .map(|days| {
+                            unix_epoch()
+                                .checked_add_signed(Duration::days(days as i64))
+                                .ok_or_else(|| {
+                                    invalid_data(field, format!("{} days is not a date", days))
+                                })
+                        })
+                        .transpose()
+                })
+                .collect::<Result<_, _>>()?,
+            format: get_metadata(field, FORMAT_KEY, DEFAULT_DATE_FORMAT),
+            column_type: ColumnStorageDataType::Date,
+        },
+        DataType::Time64(TimeUnit::Nanosecond) => ColumnStorage::TimeStorage {
+            column,
+            data: array
+                .as_primitive::<Time64NanosecondType>()
+                .iter()
+                .map(|value| {
+                    value
+                        .map(|nanoseconds| {
+                            u32::try_from(nanoseconds / 1_000_000_000)
+                                .ok()
+                                .filter(|_| nanoseconds >= 0)
+                                .and_then(|seconds| {
+                                    NaiveTime::from_num_seconds_from_midnight_opt(
+                                        seconds,
+                                        (nanoseconds % 1_000_000_000) as u32,
+                                    )
+                                })
+                                .ok_or_else(|| {
+                                    invalid_data(
+                                        field,
+                                        format!("{} nanoseconds is not a time of day", nanoseconds),
+                                    )
+                                })
+                        })
+                        .transpose()
+                })
+                .collect::<Result<_, _>>()?,
+            format: get_metadata(field, FORMAT_KEY, DEFAULT_TIME_FORMAT),
+            column_type: ColumnStorageDataType::Time,
+        },
+        DataType::Duration(unit) => {
+            let data = match unit {
+                TimeUnit::Second => array
+                    .as_primitive::<DurationSecondType>()
+                    .iter()
+                    .map(|value| {
+                        value
+                            .map(|value| {
+                                Duration::try_seconds(value).ok_or_else(|| {
+                                    invalid_data(
+                                        field,
+                                        format!("{} seconds is not a duration", value),
+                                    )
+                                })
+                            })
+                            .transpose()
+                    })
+                    .collect::<Result<_, _>>()?,
+                TimeUnit::Millisecond => array
+                    .as_primitive::<DurationMillisecondType>()
+                    .iter()
+                    .map(|value| {
+                        value
+                            .map(|value| {
+                                Duration::try_milliseconds(value).ok_or_else(|| {
+                                    invalid_data(
+                                        field,
+                                        format!("{} milliseconds is not a duration", value),
+                                    )
+                                })
+                            })
+                            .transpose()
+                    })
+                    .collect::<Result<_, _>>()?,
+                TimeUnit::Microsecond => array
+                    .as_primitive::<DurationMicrosecondType>()
+                    .iter()
+                    .map(|value| value.map(Duration::microseconds))
+                    .collect(),
+                TimeUnit::Nanosecond => array
+                    .as_primitive::<DurationNanosecondType>()
+                    .iter()
+                    .map(|value| value.map(Duration::nanoseconds))
+                    .collect(),
+            };
+
+            ColumnStorage::DurationStorage {
+                column,
+                data,
+                column_type: ColumnStorageDataType::Duration,
+            }
+        }
+        DataType::FixedSizeBinary(UUID_SIZE) => ColumnStorage::UuidStorage {
+            column,
+            data: array
+                .as_fixed_size_binary()
+                .iter()
+                .map(|value| {
+                    value
+                        .map(|bytes| {
+                            Uuid::from_slice(bytes).map_err(|error| invalid_data(field, error))
+                        })
+                        .transpose()
+                })
+                .collect::<Result<_, _>>()?,
+            column_type: ColumnStorageDataType::Uuid,
+        },
+        DataType::FixedSizeBinary(size) => {
+            return Err(invalid_data(
+                field,
+                format!("{} byte values are not uuids", size),
+            ))
+        }
+        DataType::Timestamp(unit, _) => {
+            let data = (0..array.len())
+                .map(|index| match unit {
+                    TimeUnit::Second => array
+                        .as_primitive::<TimestampSecondType>()
+                        .value_as_datetime(index),
+                    TimeUnit::Millisecond => array
+                        .as_primitive::<TimestampMillisecondType>()
+                        .value_as_datetime(index),
+                    TimeUnit::Microsecond => array
+                        .as_primitive::<TimestampMicrosecondType>()
+                        .value_as_datetime(index),
+                    TimeUnit::Nanosecond => array
+                        .as_primitive::<TimestampNanosecondType>()
+                        .value_as_datetime(index),
+                })
+                .enumerate()
+                .map(|(index, value)| {
+                    if array.is_null(index) {
+                        Ok(None)
+                    } else {
+                        value.map(Some).ok_or_else(|| {
+                            invalid_data(field, format!("row {} is not a date and time", index))
+                        })
+                    }
+                })
+                .collect::<Result<_, _>>()?;
+
+            ColumnStorage::TimeDateStorage {
+                column,
+                data,
+                format: get_metadata(field, FORMAT_KEY, DEFAULT_FORMAT),
+                column_type: ColumnStorageDataType::TimeDate,
+            }
+        }
+        DataType::Dictionary(key_type, value_type)
+            if **key_type == DataType::Int32 && **value_type == DataType::Utf8 =>
+        {
+            let dictionary = array.as_dictionary::<Int32Type>();
+            let dictionary_values = strings(dictionary.values().as_ref());
+            let mut allowed_values =
+                decode_allowed_values(&get_metadata(field, ALLOWED_VALUES_KEY, ""));
+            for dictionary_value in dictionary_values.iter().flatten() {
+                if !allowed_values.contains(dictionary_value) {
+                    allowed_values.push(dictionary_value.clone());
+                }
+            }
+            if !column.default_value.is_empty() && !allowed_values.contains(&column.default_value) {
+                allowed_values.push(column.default_value.clone());
+            }
+            check_allowed_values(&allowed_values)?;
+
+            let codes: Vec<_> = dictionary_values
+                .iter()
+                .map(|value| {
+                    value
+                        .as_ref()
+                        .and_then(|value| find_code(&allowed_values, value))
+                })
+                .collect();
+            let data = dictionary
+                .keys_iter()
+                .map(|key| key.and_then(|key| codes[key]))
+                .collect();
+
+            ColumnStorage::EnumeratedStorage {
+                column,
+                data,
+                allowed_values,
+                column_type: ColumnStorageDataType::Enumerated,
+            }
+        }
+        data_type => return Err(CacheError::UnsupportedType(data_type.to_string())),
+    };
+
+    Ok(column_store)
+}
+
+fn append_column(target: &mut ColumnStorage, source: ColumnStorage) -> Result<(), CacheError> {
+    match (target, source) {
+        (
+            ColumnStorage::StringStorage { data, .. },
+            ColumnStorage::StringStorage { data: more, .. },
+        ) => data.extend(more),
+        (
+            ColumnStorage::BooleanStorage { data, .. },
+            ColumnStorage::BooleanStorage { data: more, .. },
+        ) => data.extend(more),
+        (ColumnStorage::F64Storage { data, .. }, ColumnStorage::F64Storage { data: more, .. }) => {
+            data.extend(more)
+        }
+        (
+            ColumnStorage::TimeDateStorage { data, .. },
+            ColumnStorage::TimeDateStorage { data: more, .. },
+        ) => data.extend(more),
+        (
+            ColumnStorage::EnumeratedStorage {
+                data,
+                allowed_values,
+                ..
+            },
+            ColumnStorage::EnumeratedStorage {
+                data: more,
+                allowed_values: more_allowed_values,
+                ..
+            },
+        ) => {
+            // the codes of the other batch have to be mapped to the merged allowed values
+            let mut codes = vec![];
+            for allowed_value in more_allowed_values {
+                if !allowed_values.contains(&allowed_value) {
+                    allowed_values.push(allowed_value.clone());
+                }
+                codes.push(find_code(allowed_values, &allowed_value));
+            }
+            check_allowed_values(allowed_values)?;
+            data.extend(
+                more.into_iter()
+                    .map(|code| code.and_then(|code| codes[code as usize])),
+            )
+        }
+        (ColumnStorage::I64Storage { data, .. }, ColumnStorage::I64Storage { data: more, .. }) => {
+            data.extend(more)
+        }
+        (
+            ColumnStorage::DecimalStorage { data, scale, .. },
+            ColumnStorage::DecimalStorage {
+                data: more,
+                scale: more_scale,
+                ..
+            },
+        ) if *scale == more_scale => data.extend(more),
+        (
+            ColumnStorage::DateStorage { data, .. },
+            ColumnStorage::DateStorage { data: more, .. },
+        ) => data.extend(more),
+        (
+            ColumnStorage::TimeStorage { data, .. },
+            ColumnStorage::TimeStorage { data: more, .. },
+        ) => data.extend(more),
+        (
+            ColumnStorage::DurationStorage { data, .. },
+            ColumnStorage::DurationStorage { data: more, .. },
+        ) => data.extend(more),
+        (
+            ColumnStorage::UuidStorage { data, .. },
+            ColumnStorage::UuidStorage { data: more, .. },
+        ) => data.extend(more),
+        _ => return Err(CacheError::IllegalState),
+    }
+
+    Ok(())
+}
+
+/// Checks that a later batch has the fields of the first batch in the same order, with the same
+/// types and column metadata. Allowed values may differ as they are merged.
+fn check_same_schema(first: &Schema, schema: &Schema, batch: usize) -> Result<(), CacheError> {
+    let mismatch = |reason: String| CacheError::SchemaMismatch { batch, reason };
+    if first.fields().len() != schema.fields().len() {
+        return Err(mismatch(format!(
+            "{} fields, expected {}",
+            schema.fields().len(),
+            first.fields().len()
+        )));
+    }
+
+    for (position, (expected, field)) in first.fields().iter().zip(schema.fields()).enumerate() {
+        if field.name() != expected.name() {
+            return Err(mismatch(format!(
+                "field {} is {}, expected {}",
+                position,
+                field.name(),
+                expected.name()
+            )));
+        }
+        if field.data_type() != expected.data_type() {
+            return Err(mismatch(format!(
+                "{} is {}, expected {}",
+                field.name(),
+                field.data_type(),
+                expected.data_type()
+            )));
+        }
+        for key in [DISPLAY_NAME_KEY, DEFAULT_VALUE_KEY, FORMAT_KEY] {
+            if get_metadata(field, key, "") != get_metadata(expected, key, "") {
+                return Err(mismatch(format!(
+                    "{} has a different {}",
+                    field.name(),
+                    key
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds column storage from record batches that share a schema. If there is no _guid field
+/// then new guids are generated.
+pub(crate) fn from_record_batches(
+    batches: &[RecordBatch],
+) -> Result<(Vec<ColumnStorage>, Vec<Uuid>), CacheError> {
+    let mut column_stores: Vec<ColumnStorage> = vec![];
+    let mut guids = vec![];
+
+    for (batch_index, batch) in batches.iter().enumerate() {
+        if batch_index > 0 {
+            check_same_schema(&batches[0].schema(), &batch.schema(), batch_index)?;
+        }
+        let mut column_index = 0;
+        let mut has_guids = false;
+
+        for (field, array) in batch.schema().fields().iter().zip(batch.columns()) {
+            if field.name() == GUID_FIELD_NAME && field.data_type() == &DataType::Utf8 {
+                if has_guids {
+                    return Err(CacheError::DuplicateColumn(GUID_FIELD_NAME.to_string()));
+                }
+                for guid in strings(array.as_ref()) {
+                    let guid = guid.ok_or(CacheError::IllegalState)?;
+                    guids.push(
+                        Uuid::parse_str(&guid)
+                            .map_err(|error| CacheError::ParseError(Box::new(error)))?,
+                    );
+                }
+                has_guids = true;
+                continue;
+            }
+
+            let column_store = from_field(field, array.as_ref())?;
+            if batch_index == 0 {
+                if column_stores
+                    .iter()
+                    .any(|existing| existing.get_column().name == *field.name())
+                {
+                    return Err(CacheError::DuplicateColumn(field.name().to_string()));
+                }
+                column_stores.push(column_store);
+            } else {
+                let target = column_stores
+                    .get_mut(column_index)
+                    .ok_or(CacheError::IllegalState)?;
+                append_column(target, column_store)?;
+            }
+            column_index += 1;
+        }
+
+        if !has_guids {
+            guids.extend((0..batch.num_rows()).map(|_| Uuid::new_v4()));
+        }
+    }
+
+    if column_stores
+        .iter()
+        .any(|column_store| column_store.get_length() != guids.len())
+    {
+        return Err(CacheError::IllegalState);
+    }
+
+    // the cache finds rows by guid so each guid must be unique
+    let mut seen = HashSet::new();
+    if let Some(guid) = guids.iter().find(|guid| !seen.insert(**guid)) {
+        return Err(CacheError::DuplicateGuid(*guid));
+    }
+
+    Ok((column_stores, guids))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowed_values_encoding() {
+        let allowed_values = vec![
+            "vanilla".to_string(),
+            "rocky road, with nuts".to_string(),
+            "\"fudge\"".to_string(),
+        ];
+        let encoded = encode_allowed_values(&allowed_values);
+        assert_eq!(decode_allowed_values(&encoded), allowed_values);
+        assert!(decode_allowed_values("").is_empty());
+    }
+}
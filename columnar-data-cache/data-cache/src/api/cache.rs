@@ -1,12 +1,75 @@
-pub use crate::api::cache_error::CacheError;
+use crate::api::aggregation;
+use crate::api::aggregation::{Aggregate, AggregateTable};
+use crate::api::arrow_conversion::{from_record_batches, to_record_batch};
+pub use crate::api::cache_error::{CacheError, Expected, FieldError};
+use crate::api::chunked::{Chunked, ChunkedMap};
 use crate::api::column::Column;
 use crate::api::column_metadata::ColumnMetadata;
-use crate::api::column_storage::{ColumnStorage, ColumnStorageDataType};
+use crate::api::column_storage::{check_allowed_values, ColumnStorage, ColumnStorageDataType};
+use crate::api::csv_loader;
+use crate::api::csv_loader::LoadReport;
+use crate::api::derived::{Compute, Derivation};
+use crate::api::events::{CacheEvent, CacheObserver, CellChange, ObserverId, Observers};
+use crate::api::json::{json_to_values, row_to_json};
+use crate::api::memory_usage::{column_memory_usage, guid_memory_usage, MemoryReport};
+use crate::api::migration;
+use crate::api::migration::{ColumnType, MigrationReport};
+use crate::api::query::{compare_values, Filter, Predicate, Query, QueryRows, SortKey};
+use crate::api::retention;
+use crate::api::retention::{EvictionReport, RetentionPolicy};
+use crate::api::row::Row;
+use crate::api::search::{Search, SearchIndex};
+use crate::api::snapshot::{read_snapshot, write_snapshot};
+use crate::api::transaction::{History, Transaction};
+use crate::api::validation::{ValidationContext, Validator, ValueCounts};
+use crate::api::value::Value;
+use arrow_array::RecordBatch;
+use chrono::NaiveDateTime;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
 use uuid::Uuid;
 
 pub struct Cache {
     column_stores: Vec<ColumnStorage>,
-    guids: Vec<Uuid>,
+    guids: Chunked<Uuid>,
+    guid_index: ChunkedMap<Uuid, usize>,
+    // the validators of each column, in column order
+    validators: Vec<Vec<Arc<dyn Validator>>>,
+    // how each derived column is computed, None for other columns, in column order
+    derivations: Vec<Option<Derivation>>,
+    // the search index of each String column that has one, in column order
+    search_indexes: Vec<Option<Arc<SearchIndex>>>,
+    // the value counts of each column with a validator that uses them, in column order
+    value_counts: Vec<Option<ValueCounts>>,
+    retention_policy: Option<RetentionPolicy>,
+    history: History,
+    observers: Observers,
+}
+
+/// Observers are not cloned, a clone starts without any observers. The rows are shared with
+/// the clone until either of them changes them, see Chunked.
+impl Clone for Cache {
+    fn clone(&self) -> Self {
+        Cache {
+            column_stores: self.column_stores.clone(),
+            guids: self.guids.clone(),
+            guid_index: self.guid_index.clone(),
+            validators: self.validators.clone(),
+            derivations: self.derivations.clone(),
+            search_indexes: self.search_indexes.clone(),
+            value_counts: self.value_counts.clone(),
+            retention_policy: self.retention_policy.clone(),
+            history: self.history.clone(),
+            observers: Observers::default(),
+        }
+    }
 }
 
 impl Default for Cache {
@@ -19,7 +82,15 @@ impl Cache {
     pub fn new() -> Self {
         Cache {
             column_stores: vec![],
-            guids: vec![],
+            guids: Chunked::new(),
+            guid_index: ChunkedMap::new(),
+            validators: vec![],
+            derivations: vec![],
+            search_indexes: vec![],
+            value_counts: vec![],
+            retention_policy: None,
+            history: History::default(),
+            observers: Observers::default(),
         }
     }
 
@@ -28,7 +99,7 @@ impl Cache {
             return 0;
         }
 
-        self.column_stores.get(0).unwrap().get_length()
+        self.column_stores.first().unwrap().get_length()
     }
 
     fn check_for_duplicate_column(&self, name: &str) -> Result<(), CacheError> {
@@ -50,14 +121,13 @@ impl Cache {
         default_value: &str,
     ) -> Result<(), CacheError> {
         self.check_for_duplicate_column(name)?;
-        let mut new_column_store = ColumnStorage::StringStorage {
+        let new_column_store = ColumnStorage::StringStorage {
             column: Column::new(name, display_name, default_value),
-            data: vec![],
+            data: Chunked::new(),
             column_type: ColumnStorageDataType::String,
         };
 
-        self.fill_in_column_store(&mut new_column_store);
-        self.column_stores.push(new_column_store);
+        self.add_column_store(new_column_store);
 
         Ok(())
     }
@@ -69,14 +139,13 @@ impl Cache {
         default_value: &str,
     ) -> Result<(), CacheError> {
         self.check_for_duplicate_column(name)?;
-        let mut new_column_store = ColumnStorage::BooleanStorage {
+        let new_column_store = ColumnStorage::BooleanStorage {
             column: Column::new(name, display_name, default_value),
-            data: vec![],
+            data: Chunked::new(),
             column_type: ColumnStorageDataType::Boolean,
         };
 
-        self.fill_in_column_store(&mut new_column_store);
-        self.column_stores.push(new_column_store);
+        self.add_column_store(new_column_store);
 
         Ok(())
     }
@@ -88,14 +157,13 @@ impl Cache {
         default_value: &str,
     ) -> Result<(), CacheError> {
         self.check_for_duplicate_column(name)?;
-        let mut new_column_store = ColumnStorage::F64Storage {
+        let new_column_store = ColumnStorage::F64Storage {
             column: Column::new(name, display_name, default_value),
-            data: vec![],
+            data: Chunked::new(),
             column_type: ColumnStorageDataType::F64,
         };
 
-        self.fill_in_column_store(&mut new_column_store);
-        self.column_stores.push(new_column_store);
+        self.add_column_store(new_column_store);
 
         Ok(())
     }
@@ -108,15 +176,14 @@ impl Cache {
         default_value: &str,
     ) -> Result<(), CacheError> {
         self.check_for_duplicate_column(name)?;
-        let mut new_column_store = ColumnStorage::TimeDateStorage {
+        let new_column_store = ColumnStorage::TimeDateStorage {
             column: Column::new(name, display_name, default_value),
-            data: vec![],
+            data: Chunked::new(),
             format: format.parse().unwrap(),
             column_type: ColumnStorageDataType::TimeDate,
         };
 
-        self.fill_in_column_store(&mut new_column_store);
-        self.column_stores.push(new_column_store);
+        self.add_column_store(new_column_store);
 
         Ok(())
     }
@@ -134,31 +201,718 @@ impl Cache {
         if !default_value.is_empty() && !full_allowed_values.contains(&default_value.to_string()) {
             full_allowed_values.push(default_value.to_string());
         }
+        check_allowed_values(&full_allowed_values)?;
 
-        let mut new_column_store = ColumnStorage::EnumeratedStorage {
+        let new_column_store = ColumnStorage::EnumeratedStorage {
             column: Column::new(name, display_name, default_value),
-            data: vec![],
+            data: Chunked::new(),
             allowed_values: full_allowed_values,
             column_type: ColumnStorageDataType::Enumerated,
         };
 
-        self.fill_in_column_store(&mut new_column_store);
-        self.column_stores.push(new_column_store);
+        self.add_column_store(new_column_store);
+
+        Ok(())
+    }
+
+    pub fn add_i64_column(
+        &mut self,
+        name: &str,
+        display_name: &str,
+        default_value: &str,
+    ) -> Result<(), CacheError> {
+        self.check_for_duplicate_column(name)?;
+        let mut new_column_store = ColumnStorage::I64Storage {
+            column: Column::new(name, display_name, ""),
+            data: Chunked::new(),
+            column_type: ColumnStorageDataType::I64,
+        };
+        new_column_store.set_default_value(default_value)?;
+
+        self.add_column_store(new_column_store);
+
+        Ok(())
+    }
+
+    /// values are rounded half away from zero to scale decimal places, at most 28
+    pub fn add_decimal_column(
+        &mut self,
+        name: &str,
+        display_name: &str,
+        scale: u32,
+        default_value: &str,
+    ) -> Result<(), CacheError> {
+        self.check_for_duplicate_column(name)?;
+        if scale > Decimal::MAX_SCALE {
+            return Err(CacheError::UnsupportedType(format!(
+                "decimal scale {}",
+                scale
+            )));
+        }
+        let mut new_column_store = ColumnStorage::DecimalStorage {
+            column: Column::new(name, display_name, ""),
+            data: Chunked::new(),
+            scale,
+            column_type: ColumnStorageDataType::Decimal,
+        };
+        new_column_store.set_default_value(default_value)?;
+
+        self.add_column_store(new_column_store);
+
+        Ok(())
+    }
+
+    pub fn add_date_column(
+        &mut self,
+        name: &str,
+        display_name: &str,
+        format: &str,
+        default_value: &str,
+    ) -> Result<(), CacheError> {
+        self.check_for_duplicate_column(name)?;
+        let mut new_column_store = ColumnStorage::DateStorage {
+            column: Column::new(name, display_name, ""),
+            data: Chunked::new(),
+            format: format.to_string(),
+            column_type: ColumnStorageDataType::Date,
+        };
+        new_column_store.set_default_value(default_value)?;
+
+        self.add_column_store(new_column_store);
+
+        Ok(())
+    }
+
+    pub fn add_time_column(
+        &mut self,
+        name: &str,
+        display_name: &str,
+        format: &str,
+        default_value: &str,
+    ) -> Result<(), CacheError> {
+        self.check_for_duplicate_column(name)?;
+        let mut new_column_store = ColumnStorage::TimeStorage {
+            column: Column::new(name, display_name, ""),
+            data: Chunked::new(),
+            format: format.to_string(),
+            column_type: ColumnStorageDataType::Time,
+        };
+        new_column_store.set_default_value(default_value)?;
+
+        self.add_column_store(new_column_store);
+
+        Ok(())
+    }
+
+    /// durations are written as [-]H:MM:SS[.fraction], a plain number is read as seconds
+    pub fn add_duration_column(
+        &mut self,
+        name: &str,
+        display_name: &str,
+        default_value: &str,
+    ) -> Result<(), CacheError> {
+        self.check_for_duplicate_column(name)?;
+        let mut new_column_store = ColumnStorage::DurationStorage {
+            column: Column::new(name, display_name, ""),
+            data: Chunked::new(),
+            column_type: ColumnStorageDataType::Duration,
+        };
+        new_column_store.set_default_value(default_value)?;
+
+        self.add_column_store(new_column_store);
+
+        Ok(())
+    }
+
+    pub fn add_uuid_column(
+        &mut self,
+        name: &str,
+        display_name: &str,
+        default_value: &str,
+    ) -> Result<(), CacheError> {
+        self.check_for_duplicate_column(name)?;
+        let mut new_column_store = ColumnStorage::UuidStorage {
+            column: Column::new(name, display_name, ""),
+            data: Chunked::new(),
+            column_type: ColumnStorageDataType::Uuid,
+        };
+        new_column_store.set_default_value(default_value)?;
+
+        self.add_column_store(new_column_store);
+
+        Ok(())
+    }
+
+    /// Adds a column of any type along with the validators that rows must pass.
+    pub fn add_column(
+        &mut self,
+        name: &str,
+        display_name: &str,
+        column_type: ColumnType,
+        default_value: &str,
+        validators: Vec<Box<dyn Validator>>,
+    ) -> Result<(), CacheError> {
+        self.check_for_duplicate_column(name)?;
+        let mut new_column_store =
+            column_type.create_storage(Column::new(name, display_name, ""))?;
+        new_column_store.set_default_value(default_value)?;
+        for validator in &validators {
+            validator.check_data_type(&new_column_store.get_data_type())?;
+        }
+
+        self.add_column_store(new_column_store);
+        let index = self.column_stores.len() - 1;
+        self.validators[index].extend(validators.into_iter().map(Arc::from));
+        self.add_value_counts(index);
+
+        Ok(())
+    }
+
+    /// Adds a column computed from the source columns of each row. The values are computed
+    /// for the existing rows straight away and again whenever a row is created or updated.
+    /// Sources must already exist, a derived column may be a source of a later one.
+    pub fn add_derived_column<F>(
+        &mut self,
+        name: &str,
+        display_name: &str,
+        column_type: ColumnType,
+        sources: &[&str],
+        compute: F,
+    ) -> Result<(), CacheError>
+    where
+        F: Fn(&[Option<Value>]) -> Option<Value> + Send + Sync + 'static,
+    {
+        self.check_for_duplicate_column(name)?;
+        for source in sources {
+            self.find_column_index(source)?;
+        }
+
+        let compute: Arc<Compute> = Arc::new(compute);
+        let derivation = Derivation::new(
+            sources.iter().map(|source| source.to_string()).collect(),
+            compute,
+        );
+        let mut new_column_store =
+            column_type.create_storage(Column::new(name, display_name, ""))?;
+        for index in 0..self.row_count() {
+            new_column_store.push_value(derivation.compute(&self.column_stores, index)?)?;
+        }
+
+        self.push_column_store(new_column_store, Some(derivation));
+        Ok(())
+    }
+
+    /// Indexes the words of a String column for search, the index is kept up to date as
+    /// rows are created, updated and deleted. Adding an index that exists does nothing.
+    pub fn add_search_index(&mut self, name: &str) -> Result<(), CacheError> {
+        let index = self.find_column_index(name)?;
+        let column_store = &self.column_stores[index];
+        if column_store.get_data_type() != ColumnStorageDataType::String {
+            return Err(CacheError::UnsupportedType(format!(
+                "{:?}",
+                column_store.get_data_type()
+            )));
+        }
+        if self.search_indexes[index].is_some() {
+            return Ok(());
+        }
+
+        let mut search_index = SearchIndex::default();
+        for (row_index, guid) in self.guids.iter().enumerate() {
+            search_index.insert(*guid, &column_store.get_value(row_index));
+        }
+        self.search_indexes[index] = Some(Arc::new(search_index));
+        Ok(())
+    }
+
+    /// returns true if the column had a search index
+    pub fn remove_search_index(&mut self, name: &str) -> Result<bool, CacheError> {
+        let index = self.find_column_index(name)?;
+        Ok(self.search_indexes[index].take().is_some())
+    }
+
+    /// The guids of the rows whose value in the column matches, in row order.
+    /// The column needs a search index, see add_search_index.
+    pub fn search(&self, name: &str, search: &Search) -> Result<Vec<Uuid>, CacheError> {
+        let index = self.find_column_index(name)?;
+        let search_index = self.search_indexes[index].as_ref().ok_or_else(|| {
+            CacheError::NotAllowed(format!("{} does not have a search index", name))
+        })?;
+
+        // a guid missing from the guid index means the search index is out of date
+        let mut indices = search_index
+            .search(search)
+            .iter()
+            .map(|guid| self.guid_index.get(guid).copied())
+            .collect::<Option<Vec<usize>>>()
+            .ok_or(CacheError::IllegalState)?;
+        indices.sort_unstable();
+        Ok(indices.into_iter().map(|index| self.guids[index]).collect())
+    }
+
+    /// the validator applies to rows created or updated from now on, existing rows are not checked
+    pub fn add_validator(
+        &mut self,
+        name: &str,
+        validator: Box<dyn Validator>,
+    ) -> Result<(), CacheError> {
+        let index = self.find_column_index(name)?;
+        validator.check_data_type(&self.column_stores[index].get_data_type())?;
+        self.validators[index].push(Arc::from(validator));
+        self.add_value_counts(index);
+        Ok(())
+    }
+
+    /// a column can not be removed while a derived column is computed from it
+    pub fn remove_column(&mut self, name: &str) -> Result<(), CacheError> {
+        let index = self.find_column_index(name)?;
+        self.check_not_derived_from(name)?;
+        self.column_stores.remove(index);
+        self.validators.remove(index);
+        self.derivations.remove(index);
+        self.search_indexes.remove(index);
+        self.value_counts.remove(index);
+        self.history = History::default();
+        if self
+            .retention_policy
+            .as_ref()
+            .is_some_and(|policy| policy.time_column == name)
+        {
+            self.retention_policy = None;
+        }
+        self.observers.notify(CacheEvent::ColumnRemoved {
+            name: name.to_string(),
+        });
+        Ok(())
+    }
+
+    pub fn rename_column(&mut self, name: &str, new_name: &str) -> Result<(), CacheError> {
+        let index = self.find_column_index(name)?;
+        if name == new_name {
+            return Ok(());
+        }
+        self.check_for_duplicate_column(new_name)?;
+
+        self.column_stores[index].get_column_mut().name = new_name.to_string();
+        for derivation in self.derivations.iter_mut().flatten() {
+            for source in derivation.sources.iter_mut() {
+                if source == name {
+                    *source = new_name.to_string();
+                }
+            }
+        }
+        if let Some(policy) = self.retention_policy.as_mut() {
+            if policy.time_column == name {
+                policy.time_column = new_name.to_string();
+            }
+        }
+        self.observers.notify(CacheEvent::ColumnRenamed {
+            name: name.to_string(),
+            new_name: new_name.to_string(),
+        });
+        Ok(())
+    }
+
+    pub fn set_display_name(&mut self, name: &str, display_name: &str) -> Result<(), CacheError> {
+        let index = self.find_column_index(name)?;
+        self.column_stores[index].get_column_mut().display_name = display_name.to_string();
+        self.observers.notify(CacheEvent::ColumnChanged {
+            name: name.to_string(),
+        });
+        Ok(())
+    }
 
+    /// the new default applies to rows created from now on, existing rows are not changed
+    pub fn set_default_value(&mut self, name: &str, default_value: &str) -> Result<(), CacheError> {
+        let index = self.find_column_index(name)?;
+        self.column_stores[index].set_default_value(default_value)?;
+        self.observers.notify(CacheEvent::ColumnChanged {
+            name: name.to_string(),
+        });
         Ok(())
     }
 
+    /// The distinct non null values of a column as strings, in the order they first appear.
+    /// Useful as the allowed values when migrating a string column to an enumerated one.
+    pub fn distinct_values(&self, name: &str) -> Result<Vec<String>, CacheError> {
+        let column_store = self.find_column_store(name)?;
+        let mut distinct_values: Vec<String> = vec![];
+        for index in 0..column_store.get_length() {
+            if column_store.get_value(index).is_some() {
+                let value = column_store.get_as_string(index)?;
+                if !distinct_values.contains(&value) {
+                    distinct_values.push(value);
+                }
+            }
+        }
+        Ok(distinct_values)
+    }
+
+    /// Converts a column to another type by reparsing each value, nulls stay null.
+    /// If any row fails to convert the column is left unchanged and the failed rows are
+    /// listed in the report.
+    pub fn migrate_column(
+        &mut self,
+        name: &str,
+        column_type: ColumnType,
+        default_value: &str,
+    ) -> Result<MigrationReport, CacheError> {
+        let index = self.find_column_index(name)?;
+        if self.derivations[index].is_some() {
+            return Err(CacheError::NotAllowed(format!(
+                "{} is a derived column",
+                name
+            )));
+        }
+        self.check_not_derived_from(name)?;
+        let new_data_type = column_type
+            .create_storage(Column::new(name, name, ""))?
+            .get_data_type();
+        for validator in &self.validators[index] {
+            validator.check_data_type(&new_data_type)?;
+        }
+
+        let (new_column_store, report) = migration::migrate(
+            &self.column_stores[index],
+            &self.guids,
+            &column_type,
+            default_value,
+        )?;
+
+        if let Some(new_column_store) = new_column_store {
+            self.column_stores[index] = new_column_store;
+            if self.column_stores[index].get_data_type() != ColumnStorageDataType::String {
+                self.search_indexes[index] = None;
+            }
+            if self.value_counts[index].is_some() {
+                self.value_counts[index] = Some(ValueCounts::new(&self.column_stores[index]));
+            }
+            self.history = History::default();
+            self.observers.notify(CacheEvent::ColumnChanged {
+                name: name.to_string(),
+            });
+        }
+        Ok(report)
+    }
+
+    /// Replaces the retention policy. Rows are only evicted when evict_expired is called.
+    pub fn set_retention_policy(&mut self, policy: RetentionPolicy) -> Result<(), CacheError> {
+        retention::check_time_column(self.find_column_store(&policy.time_column)?)?;
+        self.retention_policy = Some(policy);
+        Ok(())
+    }
+
+    pub fn clear_retention_policy(&mut self) {
+        self.retention_policy = None;
+    }
+
+    pub fn get_retention_policy(&self) -> Option<&RetentionPolicy> {
+        self.retention_policy.as_ref()
+    }
+
+    /// Removes every row that has expired under the retention policy as of now in one batch,
+    /// a RowRemoved event is sent for each evicted row. Without a policy nothing is evicted.
+    pub fn evict_expired(&mut self, now: NaiveDateTime) -> Result<EvictionReport, CacheError> {
+        let policy = match &self.retention_policy {
+            Some(policy) => policy,
+            None => return Ok(EvictionReport::default()),
+        };
+        let column_store = self.find_column_store(&policy.time_column)?;
+        retention::check_time_column(column_store)?;
+
+        let (indices, report) = retention::find_expired(policy, column_store, &self.guids, now);
+        self.remove_rows_by_indices(indices)?;
+        Ok(report)
+    }
+
+    /// The updated row keeps the position of the row it replaces. The row is CSV, values with
+    /// a comma, quote or line break are quoted and the spaces around every value, quoted or
+    /// not, are trimmed.
     pub fn update_row(&mut self, guid: &Uuid, row: &str) -> Result<Uuid, CacheError> {
+        let values = csv_loader::parse_row(row)?;
+        let values: Vec<&str> = values.iter().map(String::as_str).collect();
+        self.update_row_from_values(guid, &values)
+    }
+
+    /// values are in column order, an empty value is replaced by the column default
+    pub fn update_row_from_values(
+        &mut self,
+        guid: &Uuid,
+        values: &[&str],
+    ) -> Result<Uuid, CacheError> {
+        let values: Vec<Option<&str>> = values.iter().map(|value| Some(*value)).collect();
+        self.update_row_from_fields(guid, &values)
+    }
+
+    // None values are stored as null rather than replaced by the column default
+    fn update_row_from_fields(
+        &mut self,
+        guid: &Uuid,
+        values: &[Option<&str>],
+    ) -> Result<Uuid, CacheError> {
         let index = self.find_index(guid)?;
 
-        self.add_row(guid, row)?; // returns error if row is invalid
+        let before = self.values_for_index(index);
+
+        self.add_values(guid, values, Some(index))?; // returns error if row is invalid
         self.remove_row_by_index(index)?;
+        self.notify_updated(guid, index, before);
 
         Ok(*guid)
     }
+
+    /// The row is CSV in column order, values with a comma, quote or line break are quoted and
+    /// the spaces around every value, quoted or not, are trimmed. A row from csv_for_guid reads
+    /// back the same, except that a null reads back as the column default.
     pub fn create_row(&mut self, row: &str) -> Result<Uuid, CacheError> {
+        let values = csv_loader::parse_row(row)?;
+        let values: Vec<&str> = values.iter().map(String::as_str).collect();
+        self.create_row_from_values(&values)
+    }
+
+    /// values are in column order, an empty value is replaced by the column default
+    pub fn create_row_from_values(&mut self, values: &[&str]) -> Result<Uuid, CacheError> {
+        let values: Vec<Option<&str>> = values.iter().map(|value| Some(*value)).collect();
+        self.create_row_from_fields(&values)
+    }
+
+    // None values are stored as null rather than replaced by the column default
+    fn create_row_from_fields(&mut self, values: &[Option<&str>]) -> Result<Uuid, CacheError> {
         let guid = Uuid::new_v4();
-        self.add_row(&guid, row)
+        self.add_values(&guid, values, None)?;
+        self.observers.notify(CacheEvent::RowCreated { guid });
+        Ok(guid)
+    }
+
+    /// the last row is moved into the position of the deleted row
+    pub fn delete_row(&mut self, guid: &Uuid) -> Result<(), CacheError> {
+        self.remove_row(guid)?;
+        Ok(())
+    }
+
+    /// Deletes all of the rows or, if any guid is not found, none of them.
+    /// Returns the number of rows deleted, a guid listed twice is deleted once.
+    pub fn delete_rows(&mut self, guids: &[Uuid]) -> Result<usize, CacheError> {
+        let indices = guids
+            .iter()
+            .map(|guid| self.find_index(guid))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.remove_rows_by_indices(indices)?.len())
+    }
+
+    /// Deletes the rows that match all of the predicates and returns their guids.
+    /// Without predicates every row is deleted.
+    pub fn delete_where(&mut self, predicates: &[Predicate]) -> Result<Vec<Uuid>, CacheError> {
+        let indices = self.matching_indices(predicates)?;
+        self.remove_rows_by_indices(indices)
+    }
+
+    /// Runs several row edits as one transaction. When the closure returns an error every
+    /// edit it made is rolled back and the error is returned, or RollbackFailed holding both
+    /// errors if the rollback fails too. Otherwise the transaction is committed to the undo
+    /// history. Edits made outside a transaction are not recorded.
+    /// Adding, removing or migrating a column clears the history since the recorded rows no
+    /// longer fit the columns.
+    pub fn transaction<T, F>(&mut self, edit: F) -> Result<T, CacheError>
+    where
+        F: FnOnce(&mut Transaction) -> Result<T, CacheError>,
+    {
+        let mut transaction = Transaction::new(self);
+        match edit(&mut transaction) {
+            Ok(result) => {
+                let edits = transaction.into_edits();
+                self.history.commit(edits);
+                Ok(result)
+            }
+            Err(error) => Err(transaction.abort(error)),
+        }
+    }
+
+    /// Reverses the last committed transaction, returns false when there is nothing to undo.
+    pub fn undo(&mut self) -> Result<bool, CacheError> {
+        let edits = match self.history.pop_undo() {
+            Some(edits) => edits,
+            None => return Ok(false),
+        };
+
+        let mut transaction = Transaction::new(self);
+        match transaction.revert(&edits) {
+            Ok(()) => {
+                let reverted = transaction.into_edits();
+                self.history.push_redo(reverted);
+                Ok(true)
+            }
+            Err(error) => {
+                self.history.push_undo(edits);
+                Err(error)
+            }
+        }
+    }
+
+    /// Applies the last undone transaction again, returns false when there is nothing to redo.
+    pub fn redo(&mut self) -> Result<bool, CacheError> {
+        let edits = match self.history.pop_redo() {
+            Some(edits) => edits,
+            None => return Ok(false),
+        };
+
+        let mut transaction = Transaction::new(self);
+        match transaction.revert(&edits) {
+            Ok(()) => {
+                let reverted = transaction.into_edits();
+                self.history.push_undo(reverted);
+                Ok(true)
+            }
+            Err(error) => {
+                self.history.push_redo(edits);
+                Err(error)
+            }
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.undo_len() > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.redo_len() > 0
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history = History::default();
+    }
+
+    /// observers are called synchronously after each change, in the order they were added
+    pub fn add_observer<O: CacheObserver + 'static>(&mut self, observer: O) -> ObserverId {
+        self.observers.add(Box::new(observer))
+    }
+
+    /// returns true if the observer was found
+    pub fn remove_observer(&mut self, id: ObserverId) -> bool {
+        self.observers.remove(id)
+    }
+
+    /// convenience for observing through a channel
+    pub fn subscribe(&mut self) -> (ObserverId, Receiver<CacheEvent>) {
+        let (sender, receiver) = channel();
+        (self.add_observer(sender), receiver)
+    }
+
+    /// Loads rows from csv data with a header line. Headers are matched to column names,
+    /// unknown headers are ignored and missing columns get their default value. Headers and
+    /// values are trimmed the same way as for create_row.
+    /// Rows that fail are reported by line number instead of stopping the load.
+    pub fn load_csv<R: Read>(&mut self, reader: R) -> Result<LoadReport, CacheError> {
+        csv_loader::load_csv(self, reader)
+    }
+
+    pub fn load_csv_file<P: AsRef<Path>>(&mut self, path: P) -> Result<LoadReport, CacheError> {
+        let file = File::open(path)?;
+        self.load_csv(file)
+    }
+
+    /// writes all columns, their metadata and the guids in the binary snapshot format
+    pub fn save_snapshot<W: Write>(&self, writer: &mut W) -> Result<(), CacheError> {
+        write_snapshot(writer, &self.column_stores, &self.guids)
+    }
+
+    pub fn save_snapshot_file<P: AsRef<Path>>(&self, path: P) -> Result<(), CacheError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.save_snapshot(&mut writer)
+    }
+
+    pub fn load_snapshot<R: Read>(reader: &mut R) -> Result<Cache, CacheError> {
+        let (column_stores, guids) = read_snapshot(reader)?;
+        Ok(Cache::from_parts(column_stores, guids))
+    }
+
+    pub fn load_snapshot_file<P: AsRef<Path>>(path: P) -> Result<Cache, CacheError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Cache::load_snapshot(&mut reader)
+    }
+
+    /// one field per column plus a _guid field, enumerated columns are dictionary encoded
+    pub fn to_record_batch(&self) -> Result<RecordBatch, CacheError> {
+        to_record_batch(&self.column_stores, &self.guids)
+    }
+
+    /// Rebuilds a cache from record batches that share a schema. Column definitions are taken
+    /// from the field metadata when present and inferred from the Arrow types otherwise.
+    pub fn from_record_batches(batches: &[RecordBatch]) -> Result<Cache, CacheError> {
+        let (column_stores, guids) = from_record_batches(batches)?;
+        Ok(Cache::from_parts(column_stores, guids))
+    }
+
+    pub fn write_parquet<W: Write + Send>(&self, writer: W) -> Result<(), CacheError> {
+        let batch = self.to_record_batch()?;
+        let mut arrow_writer = ArrowWriter::try_new(writer, batch.schema(), None)?;
+        arrow_writer.write(&batch)?;
+        arrow_writer.close()?;
+        Ok(())
+    }
+
+    pub fn write_parquet_file<P: AsRef<Path>>(&self, path: P) -> Result<(), CacheError> {
+        self.write_parquet(File::create(path)?)
+    }
+
+    pub fn read_parquet_file<P: AsRef<Path>>(path: P) -> Result<Cache, CacheError> {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?.build()?;
+        let batches = reader.collect::<Result<Vec<_>, _>>()?;
+        Cache::from_record_batches(&batches)
+    }
+
+    /// Creates a row from a JSON object keyed by column name. Missing keys get the column
+    /// default and nulls are stored as null, keys that are not columns are an error and _guid
+    /// is ignored.
+    pub fn create_row_from_json(&mut self, json: &serde_json::Value) -> Result<Uuid, CacheError> {
+        let values = json_to_values(&self.column_stores, json)?;
+        let values: Vec<Option<&str>> = values.iter().map(|value| value.as_deref()).collect();
+        self.create_row_from_fields(&values)
+    }
+
+    pub fn update_row_from_json(
+        &mut self,
+        guid: &Uuid,
+        json: &serde_json::Value,
+    ) -> Result<Uuid, CacheError> {
+        let values = json_to_values(&self.column_stores, json)?;
+        let values: Vec<Option<&str>> = values.iter().map(|value| value.as_deref()).collect();
+        self.update_row_from_fields(guid, &values)
+    }
+
+    pub fn get_row(&self, guid: &Uuid) -> Result<Row, CacheError> {
+        let index = self.find_index(guid)?;
+        Ok(Row {
+            guid: *guid,
+            columns: self
+                .column_stores
+                .iter()
+                .map(|column_store| column_store.get_column().name.clone())
+                .collect(),
+            values: self
+                .column_stores
+                .iter()
+                .map(|column_store| column_store.get_value(index))
+                .collect(),
+        })
+    }
+
+    pub fn json_for_guid(&self, guid: &Uuid) -> Result<serde_json::Value, CacheError> {
+        let index = self.find_index(guid)?;
+        let column_stores: Vec<&ColumnStorage> = self.column_stores.iter().collect();
+        row_to_json(&column_stores, guid, index)
+    }
+
+    /// all rows as a JSON array of objects, in row order
+    pub fn to_json(&self) -> Result<serde_json::Value, CacheError> {
+        let column_stores: Vec<&ColumnStorage> = self.column_stores.iter().collect();
+        let rows = self
+            .guids
+            .iter()
+            .enumerate()
+            .map(|(index, guid)| row_to_json(&column_stores, guid, index))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(serde_json::Value::Array(rows))
     }
 
     pub fn csv_for_guid(&self, guid: &Uuid) -> Result<String, CacheError> {
@@ -166,6 +920,370 @@ impl Cache {
         self.csv_for_index(index)
     }
 
+    /// Selects the rows matching all of the query predicates, ordered by the sort columns,
+    /// and returns an iterator over the selected columns of those rows.
+    pub fn query(&self, query: &Query) -> Result<QueryRows<'_>, CacheError> {
+        let indices = self.query_indices(query)?;
+        let column_stores = self.query_column_stores(query)?;
+        Ok(QueryRows::new(column_stores, &self.guids, indices))
+    }
+
+    /// the rows selected by a query as a JSON array of objects holding the selected columns
+    pub fn query_to_json(&self, query: &Query) -> Result<serde_json::Value, CacheError> {
+        let column_stores = self.query_column_stores(query)?;
+        let rows = self
+            .query_indices(query)?
+            .into_iter()
+            .map(|index| row_to_json(&column_stores, &self.guids[index], index))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(serde_json::Value::Array(rows))
+    }
+
+    fn query_indices(&self, query: &Query) -> Result<Vec<usize>, CacheError> {
+        let mut indices = self.matching_indices(&query.predicates)?;
+
+        if !query.sort_columns.is_empty() {
+            let mut sort_keys = vec![];
+            for (name, direction) in &query.sort_columns {
+                sort_keys.push(SortKey::new(self.find_column_store(name)?, *direction));
+            }
+
+            let mut keyed_indices: Vec<(Vec<Option<Value>>, usize)> = indices
+                .iter()
+                .map(|index| {
+                    let keys = sort_keys
+                        .iter()
+                        .map(|sort_key| sort_key.get(*index))
+                        .collect();
+                    (keys, *index)
+                })
+                .collect();
+
+            keyed_indices.sort_by(|(left_keys, _), (right_keys, _)| {
+                left_keys
+                    .iter()
+                    .zip(right_keys.iter())
+                    .zip(sort_keys.iter())
+                    .map(|((left, right), sort_key)| {
+                        compare_values(left, right, sort_key.direction)
+                    })
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            });
+
+            indices = keyed_indices.into_iter().map(|(_, index)| index).collect();
+        }
+
+        Ok(indices)
+    }
+
+    fn query_column_stores(&self, query: &Query) -> Result<Vec<&ColumnStorage>, CacheError> {
+        if query.columns.is_empty() {
+            return Ok(self.column_stores.iter().collect());
+        }
+
+        query
+            .columns
+            .iter()
+            .map(|name| self.find_column_store(name))
+            .collect()
+    }
+
+    /// count, sum, min, max and mean of a F64 column, skipping nulls
+    pub fn aggregate(&self, column: &str) -> Result<Aggregate, CacheError> {
+        aggregation::aggregate(self.find_column_store(column)?)
+    }
+
+    /// aggregates a F64 column for each value of an enumerated or boolean column
+    pub fn aggregate_by(
+        &self,
+        column: &str,
+        group_column: &str,
+    ) -> Result<AggregateTable, CacheError> {
+        aggregation::aggregate_by(
+            self.find_column_store(column)?,
+            self.find_column_store(group_column)?,
+        )
+    }
+
+    fn from_parts(column_stores: Vec<ColumnStorage>, guids: Vec<Uuid>) -> Cache {
+        let guid_index = guids
+            .iter()
+            .enumerate()
+            .map(|(index, guid)| (*guid, index))
+            .collect();
+
+        // validators and derivations are not part of snapshots or record batches
+        let validators = column_stores.iter().map(|_| vec![]).collect();
+        let derivations = column_stores.iter().map(|_| None).collect();
+        let search_indexes = column_stores.iter().map(|_| None).collect();
+        let value_counts = column_stores.iter().map(|_| None).collect();
+
+        Cache {
+            column_stores,
+            guids: guids.into(),
+            guid_index,
+            validators,
+            derivations,
+            search_indexes,
+            value_counts,
+            retention_policy: None,
+            history: History::default(),
+            observers: Observers::default(),
+        }
+    }
+
+    fn find_column_index(&self, name: &str) -> Result<usize, CacheError> {
+        self.column_stores
+            .iter()
+            .position(|column_store| column_store.get_column().name == name)
+            .ok_or_else(|| CacheError::ColumnNotFound(name.to_string()))
+    }
+
+    fn find_column_store(&self, name: &str) -> Result<&ColumnStorage, CacheError> {
+        self.column_stores
+            .iter()
+            .find(|column_store| column_store.get_column().name == name)
+            .ok_or_else(|| CacheError::ColumnNotFound(name.to_string()))
+    }
+
+    fn add_column_store(&mut self, mut column_store: ColumnStorage) {
+        self.fill_in_column_store(&mut column_store);
+        self.push_column_store(column_store, None);
+    }
+
+    // counts the values of the column once one of its validators uses the counts
+    fn add_value_counts(&mut self, index: usize) {
+        if self.value_counts[index].is_none()
+            && self.validators[index]
+                .iter()
+                .any(|validator| validator.counts_values())
+        {
+            self.value_counts[index] = Some(ValueCounts::new(&self.column_stores[index]));
+        }
+    }
+
+    // the column store must already hold a value for every row
+    fn push_column_store(&mut self, column_store: ColumnStorage, derivation: Option<Derivation>) {
+        let name = column_store.get_column().name.clone();
+        self.column_stores.push(column_store);
+        self.validators.push(vec![]);
+        self.derivations.push(derivation);
+        self.search_indexes.push(None);
+        self.value_counts.push(None);
+        self.history = History::default();
+        self.observers.notify(CacheEvent::ColumnAdded { name });
+    }
+
+    // derived columns have to be removed before the columns they are computed from change
+    fn check_not_derived_from(&self, name: &str) -> Result<(), CacheError> {
+        let derived_index = self
+            .derivations
+            .iter()
+            .position(|derivation| derivation.as_ref().is_some_and(|d| d.uses(name)));
+        match derived_index {
+            Some(index) => Err(CacheError::NotAllowed(format!(
+                "{} is used by derived column {}",
+                name,
+                self.column_stores[index].get_column().name
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    // computes the derived values of the last row, which were pushed as nulls
+    fn compute_last_row(&mut self) -> Vec<FieldError> {
+        let mut field_errors = vec![];
+        let row_index = self.row_count() - 1;
+
+        for (position, derivation) in self.derivations.iter().enumerate() {
+            if let Some(derivation) = derivation {
+                let result = derivation
+                    .compute(&self.column_stores, row_index)
+                    .and_then(|value| self.column_stores[position].set_value(row_index, value));
+                if let Err(error) = result {
+                    field_errors
+                        .push(self.column_stores[position].field_error(position, "", error));
+                }
+            }
+        }
+
+        field_errors
+    }
+
+    fn notify_updated(&mut self, guid: &Uuid, index: usize, before: Vec<Option<Value>>) {
+        if self.observers.is_empty() {
+            return;
+        }
+
+        let changes = self
+            .column_stores
+            .iter()
+            .zip(before)
+            .map(|(column_store, before)| CellChange {
+                column: column_store.get_column().name.clone(),
+                before,
+                after: column_store.get_value(index),
+            })
+            .filter(|change| change.before != change.after)
+            .collect();
+        self.observers.notify(CacheEvent::RowUpdated {
+            guid: *guid,
+            changes,
+        });
+    }
+
+    pub(crate) fn row_values(&self, index: usize) -> Vec<Option<Value>> {
+        self.column_stores
+            .iter()
+            .map(|column_store| column_store.get_value(index))
+            .collect()
+    }
+
+    // pushes parsed values as a new last row, nothing is pushed if any value does not fit
+    // the values are validated but not parsed, replacing is the same as for add_values
+    fn push_row_values(
+        &mut self,
+        guid: &Uuid,
+        values: &[Option<Value>],
+        replacing: Option<usize>,
+    ) -> Result<(), CacheError> {
+        if values.len() != self.column_stores.len() {
+            return Err(CacheError::FieldCount {
+                expected: self.column_stores.len(),
+                actual: values.len(),
+            });
+        }
+
+        for (position, value) in values.iter().enumerate() {
+            if let Err(error) = self.column_stores[position].push_value(value.clone()) {
+                for column_store in self.column_stores[..position].iter_mut() {
+                    column_store.remove_last_value();
+                }
+                return Err(error);
+            }
+        }
+
+        // rejected fields are reported with the value the column would write
+        let row_index = self.row_count() - 1;
+        let fields: Vec<String> = self
+            .column_stores
+            .iter()
+            .map(|column_store| column_store.get_as_string(row_index).unwrap_or_default())
+            .collect();
+        let fields: Vec<Option<&str>> = fields.iter().map(|field| Some(field.as_str())).collect();
+        let field_errors = self.validate_last_row(&fields, replacing);
+        if !field_errors.is_empty() {
+            for column_store in self.column_stores.iter_mut() {
+                column_store.remove_last_value();
+            }
+            return Err(CacheError::InvalidRow(field_errors));
+        }
+
+        self.push_guid(guid);
+        Ok(())
+    }
+
+    fn swap_rows(&mut self, index: usize, other_index: usize) {
+        for column_store in self.column_stores.iter_mut() {
+            column_store.swap_values(index, other_index);
+        }
+        self.guids.swap(index, other_index);
+        self.guid_index.insert(self.guids[index], index);
+        self.guid_index.insert(self.guids[other_index], other_index);
+    }
+
+    /// Restores a row without parsing its values, which are still validated. The row is put
+    /// back at the index by moving the row there to the end, which reverses a swap remove.
+    pub(crate) fn insert_row(
+        &mut self,
+        guid: &Uuid,
+        values: &[Option<Value>],
+        index: usize,
+    ) -> Result<usize, CacheError> {
+        if self.guid_index.contains_key(guid) {
+            return Err(CacheError::IllegalState);
+        }
+
+        self.push_row_values(guid, values, None)?;
+        let last = self.row_count() - 1;
+        let index = index.min(last);
+        if index != last {
+            self.swap_rows(index, last);
+        }
+        self.observers
+            .notify(CacheEvent::RowCreated { guid: *guid });
+        Ok(index)
+    }
+
+    /// replaces the values of a row, validating but not parsing them, returns the old values
+    pub(crate) fn replace_row(
+        &mut self,
+        guid: &Uuid,
+        values: &[Option<Value>],
+    ) -> Result<Vec<Option<Value>>, CacheError> {
+        let index = self.find_index(guid)?;
+        let before = self.row_values(index);
+        self.push_row_values(guid, values, Some(index))?;
+        self.remove_row_by_index(index)?;
+        self.notify_updated(guid, index, before.clone());
+        Ok(before)
+    }
+
+    fn matching_indices(&self, predicates: &[Predicate]) -> Result<Vec<usize>, CacheError> {
+        let mut indices: Vec<usize> = (0..self.row_count()).collect();
+
+        for predicate in predicates {
+            let column_store = self.find_column_store(predicate.get_column_name())?;
+            let filter = Filter::new(predicate, column_store)?;
+            indices.retain(|index| filter.matches(*index));
+        }
+
+        Ok(indices)
+    }
+
+    // removing from the highest index down means no row still to be removed is moved by a
+    // swap remove, returns the removed guids in index order
+    fn remove_rows_by_indices(&mut self, mut indices: Vec<usize>) -> Result<Vec<Uuid>, CacheError> {
+        indices.sort_unstable();
+        indices.dedup();
+        let guids: Vec<Uuid> = indices.iter().map(|index| self.guids[*index]).collect();
+
+        for index in indices.into_iter().rev() {
+            self.remove_row_by_index(index)?;
+        }
+        for guid in &guids {
+            self.observers
+                .notify(CacheEvent::RowRemoved { guid: *guid });
+        }
+        Ok(guids)
+    }
+
+    /// returns the index the row had and its values
+    pub(crate) fn remove_row(
+        &mut self,
+        guid: &Uuid,
+    ) -> Result<(usize, Vec<Option<Value>>), CacheError> {
+        let index = self.find_index(guid)?;
+        let values = self.row_values(index);
+        self.remove_row_by_index(index)?;
+        self.observers
+            .notify(CacheEvent::RowRemoved { guid: *guid });
+        Ok((index, values))
+    }
+
+    // only collected when there is someone to tell about the change
+    fn values_for_index(&self, index: usize) -> Vec<Option<Value>> {
+        if self.observers.is_empty() {
+            return vec![];
+        }
+
+        self.column_stores
+            .iter()
+            .map(|column_store| column_store.get_value(index))
+            .collect()
+    }
+
     fn fill_in_column_store(&self, column_store: &mut ColumnStorage) {
         let default_value = column_store.get_default_value();
         for _ in 0..self.row_count() {
@@ -173,13 +1291,14 @@ impl Cache {
         }
     }
 
-    fn find_index(&self, guid: &Uuid) -> Result<usize, CacheError> {
-        match self.guids.iter().position(|g| g == guid) {
-            Some(index) => Ok(index),
+    pub(crate) fn find_index(&self, guid: &Uuid) -> Result<usize, CacheError> {
+        match self.guid_index.get(guid) {
+            Some(index) => Ok(*index),
             None => Err(CacheError::GuidNotFound(*guid)),
         }
     }
 
+    // the last row is moved into the position of the removed row so removal is O(1)
     fn remove_row_by_index(&mut self, index: usize) -> Result<(), CacheError> {
         if self.column_stores.is_empty() {
             return Err(CacheError::IllegalState {});
@@ -189,41 +1308,147 @@ impl Cache {
             return Err(CacheError::IllegalState {});
         }
 
-        for column_store in self.column_stores.iter_mut() {
+        for (column_store, value_counts) in self
+            .column_stores
+            .iter_mut()
+            .zip(self.value_counts.iter_mut())
+        {
+            if let Some(value_counts) = value_counts {
+                value_counts.remove(&column_store.get_value(index));
+            }
             column_store.remove_value(index).unwrap();
         }
 
-        self.guids.remove(index);
+        let removed_guid = self.guids.swap_remove(index);
+        self.guid_index.remove(&removed_guid);
+        if let Some(moved_guid) = self.guids.get(index) {
+            self.guid_index.insert(*moved_guid, index);
+        }
+
+        // an updated row is pushed with the same guid before the old row is removed
+        if !self.guid_index.contains_key(&removed_guid) {
+            for search_index in self.search_indexes.iter_mut().flatten() {
+                Arc::make_mut(search_index).remove(&removed_guid);
+            }
+        }
         Ok(())
     }
 
-    fn add_row(&mut self, guid: &Uuid, row: &str) -> Result<Uuid, CacheError> {
-        let mut error: Option<CacheError> = None;
-        let values: Vec<&str> = row.split(',').collect();
+    // completes a row whose values have been pushed, the search indexes replace whatever
+    // they held for the guid so updates are indexed here too
+    fn push_guid(&mut self, guid: &Uuid) {
+        let index = self.guids.len();
+        self.guid_index.insert(*guid, index);
+        self.guids.push(*guid);
+
+        for (column_store, search_index) in self
+            .column_stores
+            .iter()
+            .zip(self.search_indexes.iter_mut())
+        {
+            if let Some(search_index) = search_index {
+                Arc::make_mut(search_index).insert(*guid, &column_store.get_value(index));
+            }
+        }
+        for (column_store, value_counts) in
+            self.column_stores.iter().zip(self.value_counts.iter_mut())
+        {
+            if let Some(value_counts) = value_counts {
+                value_counts.add(&column_store.get_value(index));
+            }
+        }
+    }
+
+    // every field is tried so that all the rejected fields can be reported together
+    // replacing is the index of the row being updated, which unique values may repeat
+    // a None value is stored as null without applying the column default
+    fn add_values(
+        &mut self,
+        guid: &Uuid,
+        values: &[Option<&str>],
+        replacing: Option<usize>,
+    ) -> Result<Uuid, CacheError> {
+        let mut field_errors = vec![];
 
         if values.len() != self.column_stores.len() {
-            return Err(CacheError::IllegalState {});
+            return Err(CacheError::FieldCount {
+                expected: self.column_stores.len(),
+                actual: values.len(),
+            });
         }
 
-        for (index, value) in values.iter().enumerate() {
-            let column_store = self.column_stores.get_mut(index).unwrap();
-            let result = column_store.add_value(value);
-            if result.is_err() {
-                error = Some(result.err().unwrap());
+        for (position, value) in values.iter().enumerate() {
+            let column_store = self.column_stores.get_mut(position).unwrap();
+            // derived values are computed once the rest of the row is in place
+            let result = match (&self.derivations[position], value) {
+                (Some(_), _) | (None, None) => column_store.push_value(None),
+                (None, Some(value)) => column_store.add_value(value).map(|_| ()),
+            };
+            if let Err(error) = result {
+                field_errors.push(column_store.field_error(
+                    position,
+                    value.unwrap_or_default(),
+                    error,
+                ));
             }
         }
 
-        if let Some(error) = error {
+        if field_errors.is_empty() {
+            field_errors = self.compute_last_row();
+        }
+
+        if field_errors.is_empty() {
+            field_errors = self.validate_last_row(values, replacing);
+        }
+
+        if !field_errors.is_empty() {
             for column_store in self.column_stores.iter_mut() {
                 column_store.remove_last_value();
             }
-            Err(error)
+            Err(CacheError::InvalidRow(field_errors))
         } else {
-            self.guids.push(*guid);
+            self.push_guid(guid);
             Ok(*guid)
         }
     }
 
+    fn validate_last_row(
+        &self,
+        values: &[Option<&str>],
+        replacing: Option<usize>,
+    ) -> Vec<FieldError> {
+        let mut field_errors = vec![];
+        let row_index = self.row_count() - 1;
+
+        for (position, (column_store, validators)) in self
+            .column_stores
+            .iter()
+            .zip(self.validators.iter())
+            .enumerate()
+        {
+            let context = ValidationContext {
+                column_store,
+                row_index,
+                replacing,
+                value_counts: self.value_counts[position].as_ref(),
+            };
+            let value = column_store.get_value(row_index);
+            for validator in validators {
+                if let Err(reason) = validator.validate(&value, &context) {
+                    field_errors.push(FieldError {
+                        column: column_store.get_column().name.clone(),
+                        position,
+                        value: values[position].unwrap_or_default().to_string(),
+                        expected: Expected::Rule(validator.describe()),
+                        error: Box::new(CacheError::ValidationFailed(reason)),
+                    });
+                }
+            }
+        }
+
+        field_errors
+    }
+
     fn csv_for_index(&self, index: usize) -> Result<String, CacheError> {
         if self.column_stores.is_empty() {
             return Err(CacheError::IllegalState {});
@@ -233,17 +1458,22 @@ impl Cache {
             return Err(CacheError::IllegalState {});
         }
 
-        let row: String = self
-            .column_stores
-            .iter()
-            .map(|column_store| column_store.get_as_string(index).unwrap())
-            .collect::<Vec<_>>()
-            .join(",");
-        Ok(row)
+        // values holding a comma, quote or line break are quoted so the row reads back as CSV
+        Ok(csv_loader::format_row(self.column_stores.iter().map(
+            |column_store| column_store.get_as_string(index).unwrap(),
+        )))
     }
 
     pub fn get_metadata(&self) -> Vec<ColumnMetadata> {
-        self.column_stores.iter().map(ColumnMetadata::new).collect()
+        self.column_stores
+            .iter()
+            .zip(self.derivations.iter())
+            .map(|(column_store, derivation)| {
+                let mut metadata = ColumnMetadata::new(column_store);
+                metadata.derived = derivation.is_some();
+                metadata
+            })
+            .collect()
     }
 
     pub fn row_len(&self) -> usize {
@@ -251,12 +1481,20 @@ impl Cache {
             return 0;
         }
 
-        self.column_stores.get(0).unwrap().get_length()
+        self.column_stores.first().unwrap().get_length()
     }
 
     pub fn column_len(&self) -> usize {
         self.column_stores.len()
     }
+
+    /// approximate bytes used by each column and by the guids
+    pub fn memory_usage(&self) -> MemoryReport {
+        MemoryReport {
+            columns: self.column_stores.iter().map(column_memory_usage).collect(),
+            guid_bytes: guid_memory_usage(&self.guids, &self.guid_index),
+        }
+    }
 }
 
 // tests are in tests/integration_test_cache.rs since cache is intended for external use
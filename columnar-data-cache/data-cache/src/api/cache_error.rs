@@ -1,16 +1,94 @@
+use crate::api::column_storage::ColumnStorageDataType;
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Display, Formatter};
-use std::num::ParseFloatError;
+use std::num::{ParseFloatError, ParseIntError};
 use std::str::ParseBoolError;
 use uuid::Uuid;
 
+/// What a rejected field should have held.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expected {
+    DataType(ColumnStorageDataType),
+    AllowedValues(Vec<String>),
+    /// the description of the validator that rejected the value
+    Rule(String),
+}
+
+impl Display for Expected {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Expected::DataType(data_type) => write!(formatter, "{:?}", data_type),
+            Expected::AllowedValues(allowed_values) => {
+                write!(formatter, "one of [{}]", allowed_values.join(", "))
+            }
+            Expected::Rule(description) => write!(formatter, "{}", description),
+        }
+    }
+}
+
+/// A field of a row that could not be stored. The position is the 0 based index of the field
+/// in the row and the error is the parse or validation error for the raw value.
+#[derive(Debug, PartialEq)]
+pub struct FieldError {
+    pub column: String,
+    pub position: usize,
+    pub value: String,
+    pub expected: Expected,
+    pub error: Box<CacheError>,
+}
+
+impl Display for FieldError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{} at position {} has value '{}', expected {} ({})",
+            self.column, self.position, self.value, self.expected, self.error
+        )
+    }
+}
+
 #[derive(Debug)]
 pub enum CacheError {
     GuidNotFound(Uuid),
+    /// the same guid was given for more than one row
+    DuplicateGuid(Uuid),
     ParseError(Box<dyn Error>),
     IllegalState,
     DuplicateColumn(String),
+    ColumnNotFound(String),
+    IoError(std::io::Error),
+    NotAllowed(String),
+    InvalidSnapshot(String),
+    UnsupportedType(String),
+    ValidationFailed(String),
+    /// every field of the row that was rejected, in column order
+    InvalidRow(Vec<FieldError>),
+    FieldCount {
+        expected: usize,
+        actual: usize,
+    },
+    TypeMismatch {
+        column: String,
+        expected: ColumnStorageDataType,
+        actual: ColumnStorageDataType,
+    },
+    /// imported data that the column type cannot hold
+    InvalidColumnData {
+        column: String,
+        reason: String,
+    },
+    /// a record batch whose fields differ from those of the first batch
+    SchemaMismatch {
+        batch: usize,
+        reason: String,
+    },
+    /// an edit failed and rolling back the edits before it failed too, so the cache may be
+    /// left with some of the edits applied
+    RollbackFailed {
+        error: Box<CacheError>,
+        rollback_error: Box<CacheError>,
+    },
 }
 
 impl PartialEq for CacheError {
@@ -19,13 +97,95 @@ impl PartialEq for CacheError {
             (CacheError::GuidNotFound(left_guid), CacheError::GuidNotFound(right_guid)) => {
                 left_guid == right_guid
             }
+            (CacheError::DuplicateGuid(left_guid), CacheError::DuplicateGuid(right_guid)) => {
+                left_guid == right_guid
+            }
             (CacheError::IllegalState, CacheError::IllegalState) => true,
             (CacheError::DuplicateColumn(left_name), CacheError::DuplicateColumn(right_name)) => {
                 left_name == right_name
             }
+            (CacheError::ColumnNotFound(left_name), CacheError::ColumnNotFound(right_name)) => {
+                left_name == right_name
+            }
+            (CacheError::NotAllowed(left_value), CacheError::NotAllowed(right_value)) => {
+                left_value == right_value
+            }
+            (
+                CacheError::InvalidSnapshot(left_reason),
+                CacheError::InvalidSnapshot(right_reason),
+            ) => left_reason == right_reason,
+            (CacheError::UnsupportedType(left_type), CacheError::UnsupportedType(right_type)) => {
+                left_type == right_type
+            }
+            (
+                CacheError::ValidationFailed(left_reason),
+                CacheError::ValidationFailed(right_reason),
+            ) => left_reason == right_reason,
+            (CacheError::InvalidRow(left_errors), CacheError::InvalidRow(right_errors)) => {
+                left_errors == right_errors
+            }
+            (
+                CacheError::FieldCount {
+                    expected: left_expected,
+                    actual: left_actual,
+                },
+                CacheError::FieldCount {
+                    expected: right_expected,
+                    actual: right_actual,
+                },
+            ) => left_expected == right_expected && left_actual == right_actual,
+            (
+                CacheError::TypeMismatch {
+                    column: left_column,
+                    expected: left_expected,
+                    actual: left_actual,
+                },
+                CacheError::TypeMismatch {
+                    column: right_column,
+                    expected: right_expected,
+                    actual: right_actual,
+                },
+            ) => {
+                left_column == right_column
+                    && left_expected == right_expected
+                    && left_actual == right_actual
+            }
+            (
+                CacheError::InvalidColumnData {
+                    column: left_column,
+                    reason: left_reason,
+                },
+                CacheError::InvalidColumnData {
+                    column: right_column,
+                    reason: right_reason,
+                },
+            ) => left_column == right_column && left_reason == right_reason,
+            (
+                CacheError::SchemaMismatch {
+                    batch: left_batch,
+                    reason: left_reason,
+                },
+                CacheError::SchemaMismatch {
+                    batch: right_batch,
+                    reason: right_reason,
+                },
+            ) => left_batch == right_batch && left_reason == right_reason,
+            (CacheError::IoError(left_error), CacheError::IoError(right_error)) => {
+                left_error.kind() == right_error.kind()
+            }
             (CacheError::ParseError(left_error), CacheError::ParseError(right_error)) => {
                 left_error.to_string() == right_error.to_string()
             }
+            (
+                CacheError::RollbackFailed {
+                    error: left_error,
+                    rollback_error: left_rollback_error,
+                },
+                CacheError::RollbackFailed {
+                    error: right_error,
+                    rollback_error: right_rollback_error,
+                },
+            ) => left_error == right_error && left_rollback_error == right_rollback_error,
             _ => false,
         }
     }
@@ -35,13 +195,61 @@ impl Display for CacheError {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         match self {
             CacheError::GuidNotFound(guid) => write!(formatter, "Guid not found: {}", guid),
+            CacheError::DuplicateGuid(guid) => write!(formatter, "Duplicate guid: {}", guid),
             CacheError::DuplicateColumn(name) => {
                 write!(formatter, "Duplicate column: {}", name)
             }
+            CacheError::ColumnNotFound(name) => {
+                write!(formatter, "Column not found: {}", name)
+            }
+            CacheError::NotAllowed(value) => write!(formatter, "Value not allowed: {}", value),
+            CacheError::InvalidSnapshot(reason) => {
+                write!(formatter, "Invalid snapshot: {}", reason)
+            }
+            CacheError::UnsupportedType(data_type) => {
+                write!(formatter, "Unsupported type: {}", data_type)
+            }
+            CacheError::ValidationFailed(reason) => {
+                write!(formatter, "Validation failed: {}", reason)
+            }
+            CacheError::InvalidRow(field_errors) => {
+                let field_errors: Vec<String> =
+                    field_errors.iter().map(|error| error.to_string()).collect();
+                write!(formatter, "Invalid row: {}", field_errors.join("; "))
+            }
+            CacheError::FieldCount { expected, actual } => write!(
+                formatter,
+                "Wrong number of fields: expected {} but found {}",
+                expected, actual
+            ),
+            CacheError::TypeMismatch {
+                column,
+                expected,
+                actual,
+            } => write!(
+                formatter,
+                "Type mismatch: {} is {:?} not {:?}",
+                column, actual, expected
+            ),
+            CacheError::InvalidColumnData { column, reason } => {
+                write!(formatter, "Invalid data in column {}: {}", column, reason)
+            }
+            CacheError::SchemaMismatch { batch, reason } => {
+                write!(formatter, "Schema mismatch in batch {}: {}", batch, reason)
+            }
+            CacheError::IoError(error) => write!(formatter, "IoError: {}", error),
             CacheError::ParseError(error) => {
                 write!(formatter, "ParseError: {}", error.to_string().as_str())
             }
-            CacheError::IllegalState {} => write!(formatter, "Illegal state"),
+            CacheError::IllegalState => write!(formatter, "Illegal state"),
+            CacheError::RollbackFailed {
+                error,
+                rollback_error,
+            } => write!(
+                formatter,
+                "Rollback failed: {} after the edit failed with: {}",
+                rollback_error, error
+            ),
         }
     }
 }
@@ -50,9 +258,26 @@ impl Error for CacheError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             CacheError::GuidNotFound(..) => None,
+            CacheError::DuplicateGuid(..) => None,
             CacheError::DuplicateColumn(..) => None,
+            CacheError::ColumnNotFound(..) => None,
+            CacheError::NotAllowed(..) => None,
+            CacheError::InvalidSnapshot(..) => None,
+            CacheError::UnsupportedType(..) => None,
+            CacheError::ValidationFailed(..) => None,
+            // the first rejected field is the source, all of them are in the Display
+            CacheError::InvalidRow(field_errors) => field_errors
+                .first()
+                .map(|field_error| field_error.error.as_ref() as &(dyn Error + 'static)),
+            CacheError::FieldCount { .. } => None,
+            CacheError::TypeMismatch { .. } => None,
+            CacheError::InvalidColumnData { .. } => None,
+            CacheError::SchemaMismatch { .. } => None,
+            CacheError::IoError(error) => Some(error),
             CacheError::ParseError(error) => Some(error.as_ref()),
-            CacheError::IllegalState {} => None,
+            CacheError::IllegalState => None,
+            // the edit error is the cause, the rollback error is in the Display
+            CacheError::RollbackFailed { error, .. } => Some(error.as_ref()),
         }
     }
 }
@@ -76,3 +301,45 @@ impl From<ParseBoolError> for CacheError {
         CacheError::ParseError(Box::new(parse_bool_error))
     }
 }
+
+impl From<ParseIntError> for CacheError {
+    fn from(parse_int_error: ParseIntError) -> Self {
+        CacheError::ParseError(Box::new(parse_int_error))
+    }
+}
+
+impl From<rust_decimal::Error> for CacheError {
+    fn from(decimal_error: rust_decimal::Error) -> Self {
+        CacheError::ParseError(Box::new(decimal_error))
+    }
+}
+
+impl From<uuid::Error> for CacheError {
+    fn from(uuid_error: uuid::Error) -> Self {
+        CacheError::ParseError(Box::new(uuid_error))
+    }
+}
+
+impl From<csv::Error> for CacheError {
+    fn from(csv_error: csv::Error) -> Self {
+        CacheError::ParseError(Box::new(csv_error))
+    }
+}
+
+impl From<std::io::Error> for CacheError {
+    fn from(io_error: std::io::Error) -> Self {
+        CacheError::IoError(io_error)
+    }
+}
+
+impl From<arrow_schema::ArrowError> for CacheError {
+    fn from(arrow_error: arrow_schema::ArrowError) -> Self {
+        CacheError::ParseError(Box::new(arrow_error))
+    }
+}
+
+impl From<parquet::errors::ParquetError> for CacheError {
+    fn from(parquet_error: parquet::errors::ParquetError) -> Self {
+        CacheError::ParseError(Box::new(parquet_error))
+    }
+}
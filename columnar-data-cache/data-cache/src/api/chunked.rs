@@ -0,0 +1,358 @@
+//! Copy on write storage for the rows of a cache.
+//!
+//! Values are held in fixed size chunks behind an Arc. Cloning copies the chunk pointers
+//! rather than the values, and a chunk is only copied when a clone that shares it changes it.
+//! This is what lets a ConcurrentCache publish a copy after every batch while only paying
+//! for the chunks that the batch touched.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem::size_of;
+use std::ops::{Index, IndexMut};
+use std::sync::Arc;
+
+const CHUNK_SIZE: usize = 1024;
+const SHARD_COUNT: usize = 256;
+
+/// A growable list of values stored in shared chunks. Every chunk is full except the last.
+#[derive(Clone)]
+pub struct Chunked<T> {
+    chunks: Vec<Arc<Vec<T>>>,
+    len: usize,
+}
+
+impl<T> Chunked<T> {
+    pub fn new() -> Self {
+        Chunked {
+            chunks: vec![],
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        Some(&self.chunks[index / CHUNK_SIZE][index % CHUNK_SIZE])
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        self.chunks.iter().flat_map(|chunk| chunk.iter())
+    }
+
+    /// the bytes allocated for values, counting shared chunks in full
+    pub fn allocated_bytes(&self) -> usize {
+        self.chunks.capacity() * size_of::<Arc<Vec<T>>>()
+            + self
+                .chunks
+                .iter()
+                .map(|chunk| chunk.capacity() * size_of::<T>())
+                .sum::<usize>()
+    }
+}
+
+impl<T: Clone> Chunked<T> {
+    pub fn push(&mut self, value: T) {
+        if self.len.is_multiple_of(CHUNK_SIZE) {
+            self.chunks.push(Arc::new(Vec::new()));
+        }
+        Arc::make_mut(self.chunks.last_mut().unwrap()).push(value);
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let chunk = Arc::make_mut(self.chunks.last_mut()?);
+        let value = chunk.pop();
+        if chunk.is_empty() {
+            self.chunks.pop();
+        }
+        self.len -= 1;
+        value
+    }
+
+    /// copies the chunk holding the value if it is shared
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        Some(&mut Arc::make_mut(&mut self.chunks[index / CHUNK_SIZE])[index % CHUNK_SIZE])
+    }
+
+    pub fn swap(&mut self, index: usize, other_index: usize) {
+        assert!(index < self.len && other_index < self.len);
+        let (chunk, other_chunk) = (index / CHUNK_SIZE, other_index / CHUNK_SIZE);
+        if chunk == other_chunk {
+            Arc::make_mut(&mut self.chunks[chunk])
+                .swap(index % CHUNK_SIZE, other_index % CHUNK_SIZE);
+            return;
+        }
+
+        let (low, high) = (chunk.min(other_chunk), chunk.max(other_chunk));
+        let (before, after) = self.chunks.split_at_mut(high);
+        let low_chunk = Arc::make_mut(&mut before[low]);
+        let high_chunk = Arc::make_mut(&mut after[0]);
+        let (low_index, high_index) = if chunk < other_chunk {
+            (index, other_index)
+        } else {
+            (other_index, index)
+        };
+        std::mem::swap(
+            &mut low_chunk[low_index % CHUNK_SIZE],
+            &mut high_chunk[high_index % CHUNK_SIZE],
+        );
+    }
+
+    /// removes the value and moves the last value into its place, like Vec::swap_remove
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "swap_remove index out of bounds");
+        let last = self.pop().unwrap();
+        if index == self.len {
+            return last;
+        }
+        std::mem::replace(self.get_mut(index).unwrap(), last)
+    }
+
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T> Default for Chunked<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Chunked<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for Chunked<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T> Index<usize> for Chunked<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T: Clone> IndexMut<usize> for Chunked<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+impl<T: Clone> Extend<T> for Chunked<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        for value in values {
+            self.push(value);
+        }
+    }
+}
+
+impl<T: Clone> FromIterator<T> for Chunked<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        let mut chunked = Chunked::new();
+        chunked.extend(values);
+        chunked
+    }
+}
+
+impl<T: Clone> From<Vec<T>> for Chunked<T> {
+    fn from(values: Vec<T>) -> Self {
+        values.into_iter().collect()
+    }
+}
+
+impl<T: Clone> IntoIterator for Chunked<T> {
+    type Item = T;
+    type IntoIter =
+        std::iter::FlatMap<std::vec::IntoIter<Arc<Vec<T>>>, Vec<T>, fn(Arc<Vec<T>>) -> Vec<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chunks
+            .into_iter()
+            .flat_map(Arc::unwrap_or_clone as fn(Arc<Vec<T>>) -> Vec<T>)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Chunked<T> {
+    type Item = &'a T;
+    type IntoIter = Box<dyn DoubleEndedIterator<Item = &'a T> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+/// A hash map split into shards by the hash of the key, so that a change only copies the
+/// shard holding the key when the map is shared.
+#[derive(Clone)]
+pub(crate) struct ChunkedMap<K, V> {
+    shards: Vec<Arc<HashMap<K, V>>>,
+    len: usize,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> ChunkedMap<K, V> {
+    pub(crate) fn new() -> Self {
+        ChunkedMap {
+            shards: (0..SHARD_COUNT).map(|_| Arc::new(HashMap::new())).collect(),
+            len: 0,
+        }
+    }
+
+    fn shard(key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish() as usize % SHARD_COUNT
+    }
+
+    pub(crate) fn get(&self, key: &K) -> Option<&V> {
+        self.shards[Self::shard(key)].get(key)
+    }
+
+    pub(crate) fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// copies the shard holding the key if it is shared
+    pub(crate) fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let shard = &mut self.shards[Self::shard(key)];
+        if !shard.contains_key(key) {
+            return None;
+        }
+        Arc::make_mut(shard).get_mut(key)
+    }
+
+    pub(crate) fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let shard = Arc::make_mut(&mut self.shards[Self::shard(&key)]);
+        let previous = shard.insert(key, value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    pub(crate) fn remove(&mut self, key: &K) -> Option<V> {
+        let shard = &mut self.shards[Self::shard(key)];
+        if !shard.contains_key(key) {
+            return None;
+        }
+        let previous = Arc::make_mut(shard).remove(key);
+        self.len -= 1;
+        previous
+    }
+
+    /// the bytes allocated for entries, estimated from the capacity of each shard
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.capacity() * (size_of::<K>() + size_of::<V>()))
+            .sum()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Index<&K> for ChunkedMap<K, V> {
+    type Output = V;
+
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Default for ChunkedMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> FromIterator<(K, V)> for ChunkedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> Self {
+        let mut map = ChunkedMap::new();
+        for (key, value) in entries {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunked_like_vec() {
+        let count = CHUNK_SIZE * 2 + 10;
+        let mut chunked: Chunked<usize> = (0..count).collect();
+        let mut vec: Vec<usize> = (0..count).collect();
+
+        assert_eq!(chunked.swap_remove(3), vec.swap_remove(3));
+        chunked.swap(1, CHUNK_SIZE + 1);
+        vec.swap(1, CHUNK_SIZE + 1);
+        chunked.swap(CHUNK_SIZE * 2 + 1, 2);
+        vec.swap(CHUNK_SIZE * 2 + 1, 2);
+        chunked[5] = 99;
+        vec[5] = 99;
+        for _ in 0..20 {
+            assert_eq!(chunked.pop(), vec.pop());
+        }
+
+        assert_eq!(chunked.len(), vec.len());
+        assert_eq!(chunked.to_vec(), vec);
+        assert_eq!(chunked.iter().next_back(), vec.last());
+        assert_eq!(chunked.clone().into_iter().collect::<Vec<_>>(), vec);
+    }
+
+    #[test]
+    fn test_clone_shares_untouched_chunks() {
+        let original: Chunked<usize> = (0..CHUNK_SIZE * 3).collect();
+        let mut copy = original.clone();
+        copy[CHUNK_SIZE] = 0;
+
+        assert_eq!(original[CHUNK_SIZE], CHUNK_SIZE);
+        assert!(Arc::ptr_eq(&original.chunks[0], &copy.chunks[0]));
+        assert!(!Arc::ptr_eq(&original.chunks[1], &copy.chunks[1]));
+        assert!(Arc::ptr_eq(&original.chunks[2], &copy.chunks[2]));
+    }
+
+    #[test]
+    fn test_chunked_map() {
+        let original: ChunkedMap<usize, usize> = (0..1000).map(|key| (key, key * 2)).collect();
+        let mut copy = original.clone();
+        assert_eq!(copy.remove(&10), Some(20));
+        assert_eq!(copy.remove(&10), None);
+        copy.insert(2000, 1);
+        copy.insert(5, 0);
+
+        assert_eq!(original.len, 1000);
+        assert_eq!(copy.len, 1000);
+        assert_eq!(original.get(&10), Some(&20));
+        assert_eq!(original.get(&5), Some(&10));
+        assert_eq!(copy.get(&5), Some(&0));
+        assert_eq!(copy.get(&2000), Some(&1));
+        let shared = original
+            .shards
+            .iter()
+            .zip(copy.shards.iter())
+            .filter(|(left, right)| Arc::ptr_eq(left, right))
+            .count();
+        assert!(shared >= SHARD_COUNT - 3);
+    }
+}
@@ -1,11 +1,16 @@
 use crate::api::column_storage::{ColumnStorage, ColumnStorageDataType};
 
+#[derive(Debug, PartialEq)]
 pub struct ColumnMetadata {
     pub name: String,
     pub display_name: String,
     pub default_value: String,
     pub data_type: ColumnStorageDataType,
     pub format: String,
+    pub allowed_values: Vec<String>,
+    pub scale: u32,
+    /// true when the values are computed from other columns
+    pub derived: bool,
 }
 
 impl ColumnMetadata {
@@ -17,6 +22,9 @@ impl ColumnMetadata {
             default_value: column.default_value.clone(),
             format: column_storage.get_format(),
             data_type: column_storage.get_data_type().clone(),
+            allowed_values: column_storage.get_allowed_values(),
+            scale: column_storage.get_scale(),
+            derived: false,
         }
     }
 }
@@ -1,6 +1,14 @@
-use crate::api::cache_error::CacheError;
+use crate::api::cache_error::{CacheError, Expected, FieldError};
+use crate::api::chunked::Chunked;
 use crate::api::column::Column;
-use crate::api::parsers::{parse_bool, parse_date_time, parse_f64, parse_string};
+use crate::api::parsers::{
+    format_duration, parse_bool, parse_date, parse_date_time, parse_decimal, parse_duration,
+    parse_f64, parse_i64, parse_string, parse_time, parse_uuid, to_scale,
+};
+use crate::api::value::Value;
+use chrono::{Duration, NaiveDate, NaiveTime};
+use rust_decimal::Decimal;
+use uuid::Uuid;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum ColumnStorageDataType {
@@ -9,42 +17,82 @@ pub enum ColumnStorageDataType {
     F64,
     TimeDate,
     Enumerated,
+    I64,
+    Decimal,
+    Date,
+    Time,
+    Duration,
+    Uuid,
 }
 
-#[derive(Debug, PartialEq)]
-
+#[derive(Debug, PartialEq, Clone)]
 pub enum ColumnStorage {
     StringStorage {
         column: Column,
-        data: Vec<Option<String>>,
+        data: Chunked<Option<String>>,
         column_type: ColumnStorageDataType,
     },
     BooleanStorage {
         column: Column,
-        data: Vec<Option<bool>>,
+        data: Chunked<Option<bool>>,
         column_type: ColumnStorageDataType,
     },
     F64Storage {
         column: Column,
-        data: Vec<Option<f64>>,
+        data: Chunked<Option<f64>>,
         column_type: ColumnStorageDataType,
     },
     TimeDateStorage {
         column: Column,
-        data: Vec<Option<chrono::NaiveDateTime>>,
+        data: Chunked<Option<chrono::NaiveDateTime>>,
         format: String,
         column_type: ColumnStorageDataType,
     },
+    /// values are stored as codes, the position of the value in allowed values
     EnumeratedStorage {
         column: Column,
-        data: Vec<Option<String>>,
+        data: Chunked<Option<EnumeratedCode>>,
         allowed_values: Vec<String>,
         column_type: ColumnStorageDataType,
     },
+    I64Storage {
+        column: Column,
+        data: Chunked<Option<i64>>,
+        column_type: ColumnStorageDataType,
+    },
+    /// fixed point decimals, every value has scale decimal places
+    DecimalStorage {
+        column: Column,
+        data: Chunked<Option<Decimal>>,
+        scale: u32,
+        column_type: ColumnStorageDataType,
+    },
+    DateStorage {
+        column: Column,
+        data: Chunked<Option<NaiveDate>>,
+        format: String,
+        column_type: ColumnStorageDataType,
+    },
+    TimeStorage {
+        column: Column,
+        data: Chunked<Option<NaiveTime>>,
+        format: String,
+        column_type: ColumnStorageDataType,
+    },
+    DurationStorage {
+        column: Column,
+        data: Chunked<Option<Duration>>,
+        column_type: ColumnStorageDataType,
+    },
+    UuidStorage {
+        column: Column,
+        data: Chunked<Option<Uuid>>,
+        column_type: ColumnStorageDataType,
+    },
 }
 
-fn push_with_check<T>(
-    data: &mut Vec<Option<T>>,
+fn push_with_check<T: Clone>(
+    data: &mut Chunked<Option<T>>,
     parsed_value: Result<Option<T>, CacheError>,
 ) -> Result<Option<()>, CacheError> {
     match parsed_value {
@@ -59,7 +107,7 @@ fn push_with_check<T>(
     }
 }
 
-fn value_to_string<T>(data: &[Option<T>], index: usize) -> Result<String, CacheError>
+fn value_to_string<T>(data: &Chunked<Option<T>>, index: usize) -> Result<String, CacheError>
 where
     T: ToString,
 {
@@ -75,6 +123,38 @@ where
     }
 }
 
+// for values that are written with a column format rather than their Display
+fn formatted_value_to_string<T>(
+    data: &Chunked<Option<T>>,
+    index: usize,
+    format_value: impl Fn(&T) -> String,
+) -> Result<String, CacheError> {
+    match data.get(index) {
+        Some(value) => Ok(value.as_ref().map(format_value).unwrap_or_default()),
+        None => Err(CacheError::IllegalState),
+    }
+}
+
+/// The code of an enumerated value, which limits a column to 65536 allowed values.
+pub type EnumeratedCode = u16;
+
+pub(crate) fn check_allowed_values(allowed_values: &[String]) -> Result<(), CacheError> {
+    if allowed_values.len() > EnumeratedCode::MAX as usize + 1 {
+        return Err(CacheError::UnsupportedType(format!(
+            "{} allowed values",
+            allowed_values.len()
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn find_code(allowed_values: &[String], value: &str) -> Option<EnumeratedCode> {
+    allowed_values
+        .iter()
+        .position(|allowed_value| allowed_value == value)
+        .map(|position| position as EnumeratedCode)
+}
+
 impl ColumnStorage {
     pub fn add_value(&mut self, value: &str) -> Result<Option<()>, CacheError> {
         match self {
@@ -111,12 +191,12 @@ impl ColumnStorage {
                         if value.is_empty() {
                             data.push(None);
                             Ok(None)
-                        } else if allowed_values.contains(&value) {
-                            data.push(Some(value));
+                        } else if let Some(code) = find_code(allowed_values, &value) {
+                            data.push(Some(code));
                             Ok(None)
                         } else {
                             data.push(None);
-                            Err(CacheError::IllegalState {})
+                            Err(CacheError::NotAllowed(value))
                         }
                     }
                     Ok(None) => {
@@ -129,6 +209,45 @@ impl ColumnStorage {
                     }
                 }
             }
+            ColumnStorage::I64Storage { data, column, .. } => {
+                let parsed_value = parse_i64(value, column.default_value.as_str());
+                push_with_check(data, parsed_value)
+            }
+            ColumnStorage::DecimalStorage {
+                data,
+                column,
+                scale,
+                ..
+            } => {
+                let parsed_value = parse_decimal(value, column.default_value.as_str(), *scale);
+                push_with_check(data, parsed_value)
+            }
+            ColumnStorage::DateStorage {
+                data,
+                column,
+                format,
+                ..
+            } => {
+                let parsed_value = parse_date(value, column.default_value.as_str(), format);
+                push_with_check(data, parsed_value)
+            }
+            ColumnStorage::TimeStorage {
+                data,
+                column,
+                format,
+                ..
+            } => {
+                let parsed_value = parse_time(value, column.default_value.as_str(), format);
+                push_with_check(data, parsed_value)
+            }
+            ColumnStorage::DurationStorage { data, column, .. } => {
+                let parsed_value = parse_duration(value, column.default_value.as_str());
+                push_with_check(data, parsed_value)
+            }
+            ColumnStorage::UuidStorage { data, column, .. } => {
+                let parsed_value = parse_uuid(value, column.default_value.as_str());
+                push_with_check(data, parsed_value)
+            }
         }
     }
 
@@ -139,29 +258,60 @@ impl ColumnStorage {
             ColumnStorage::StringStorage { data, .. } => data.len(),
             ColumnStorage::TimeDateStorage { data, .. } => data.len(),
             ColumnStorage::EnumeratedStorage { data, .. } => data.len(),
+            ColumnStorage::I64Storage { data, .. } => data.len(),
+            ColumnStorage::DecimalStorage { data, .. } => data.len(),
+            ColumnStorage::DateStorage { data, .. } => data.len(),
+            ColumnStorage::TimeStorage { data, .. } => data.len(),
+            ColumnStorage::DurationStorage { data, .. } => data.len(),
+            ColumnStorage::UuidStorage { data, .. } => data.len(),
         }
     }
 
+    /// removes the value at the index by moving the last value into its place
     pub fn remove_value(&mut self, index: usize) -> Result<(), CacheError> {
         match self {
             ColumnStorage::BooleanStorage { data, .. } => {
-                data.remove(index);
+                data.swap_remove(index);
                 Ok(())
             }
             ColumnStorage::F64Storage { data, .. } => {
-                data.remove(index);
+                data.swap_remove(index);
                 Ok(())
             }
             ColumnStorage::StringStorage { data, .. } => {
-                data.remove(index);
+                data.swap_remove(index);
                 Ok(())
             }
             ColumnStorage::TimeDateStorage { data, .. } => {
-                data.remove(index);
+                data.swap_remove(index);
                 Ok(())
             }
             ColumnStorage::EnumeratedStorage { data, .. } => {
-                data.remove(index);
+                data.swap_remove(index);
+                Ok(())
+            }
+            ColumnStorage::I64Storage { data, .. } => {
+                data.swap_remove(index);
+                Ok(())
+            }
+            ColumnStorage::DecimalStorage { data, .. } => {
+                data.swap_remove(index);
+                Ok(())
+            }
+            ColumnStorage::DateStorage { data, .. } => {
+                data.swap_remove(index);
+                Ok(())
+            }
+            ColumnStorage::TimeStorage { data, .. } => {
+                data.swap_remove(index);
+                Ok(())
+            }
+            ColumnStorage::DurationStorage { data, .. } => {
+                data.swap_remove(index);
+                Ok(())
+            }
+            ColumnStorage::UuidStorage { data, .. } => {
+                data.swap_remove(index);
                 Ok(())
             }
         }
@@ -184,6 +334,24 @@ impl ColumnStorage {
             ColumnStorage::EnumeratedStorage { data, .. } => {
                 data.pop();
             }
+            ColumnStorage::I64Storage { data, .. } => {
+                data.pop();
+            }
+            ColumnStorage::DecimalStorage { data, .. } => {
+                data.pop();
+            }
+            ColumnStorage::DateStorage { data, .. } => {
+                data.pop();
+            }
+            ColumnStorage::TimeStorage { data, .. } => {
+                data.pop();
+            }
+            ColumnStorage::DurationStorage { data, .. } => {
+                data.pop();
+            }
+            ColumnStorage::UuidStorage { data, .. } => {
+                data.pop();
+            }
         }
     }
 
@@ -192,8 +360,81 @@ impl ColumnStorage {
             ColumnStorage::BooleanStorage { data, .. } => value_to_string(data, index),
             ColumnStorage::F64Storage { data, .. } => value_to_string(data, index),
             ColumnStorage::StringStorage { data, .. } => value_to_string(data, index),
-            ColumnStorage::TimeDateStorage { data, .. } => value_to_string(data, index),
-            ColumnStorage::EnumeratedStorage { data, .. } => value_to_string(data, index),
+            ColumnStorage::TimeDateStorage { data, format, .. } => {
+                formatted_value_to_string(data, index, |value| value.format(format).to_string())
+            }
+            ColumnStorage::EnumeratedStorage {
+                data,
+                allowed_values,
+                ..
+            } => formatted_value_to_string(data, index, |code| {
+                allowed_values[*code as usize].clone()
+            }),
+            ColumnStorage::I64Storage { data, .. } => value_to_string(data, index),
+            ColumnStorage::DecimalStorage { data, .. } => value_to_string(data, index),
+            ColumnStorage::DateStorage { data, format, .. } => {
+                formatted_value_to_string(data, index, |value| value.format(format).to_string())
+            }
+            ColumnStorage::TimeStorage { data, format, .. } => {
+                formatted_value_to_string(data, index, |value| value.format(format).to_string())
+            }
+            ColumnStorage::DurationStorage { data, .. } => {
+                formatted_value_to_string(data, index, format_duration)
+            }
+            ColumnStorage::UuidStorage { data, .. } => value_to_string(data, index),
+        }
+    }
+
+    pub fn get_value(&self, index: usize) -> Option<Value> {
+        match self {
+            ColumnStorage::BooleanStorage { data, .. } => data.get(index)?.map(Value::Boolean),
+            ColumnStorage::F64Storage { data, .. } => data.get(index)?.map(Value::F64),
+            ColumnStorage::StringStorage { data, .. } => {
+                data.get(index)?.clone().map(Value::String)
+            }
+            ColumnStorage::TimeDateStorage { data, .. } => data.get(index)?.map(Value::TimeDate),
+            ColumnStorage::EnumeratedStorage {
+                data,
+                allowed_values,
+                ..
+            } => data
+                .get(index)?
+                .map(|code| Value::Enumerated(allowed_values[code as usize].clone())),
+            ColumnStorage::I64Storage { data, .. } => data.get(index)?.map(Value::I64),
+            ColumnStorage::DecimalStorage { data, .. } => data.get(index)?.map(Value::Decimal),
+            ColumnStorage::DateStorage { data, .. } => data.get(index)?.map(Value::Date),
+            ColumnStorage::TimeStorage { data, .. } => data.get(index)?.map(Value::Time),
+            ColumnStorage::DurationStorage { data, .. } => data.get(index)?.map(Value::Duration),
+            ColumnStorage::UuidStorage { data, .. } => data.get(index)?.map(Value::Uuid),
+        }
+    }
+
+    /// parses a value using the parser for this column without falling back to the default value
+    pub fn parse_value(&self, value: &str) -> Result<Option<Value>, CacheError> {
+        match self {
+            ColumnStorage::BooleanStorage { .. } => Ok(parse_bool(value, "")?.map(Value::Boolean)),
+            ColumnStorage::F64Storage { .. } => Ok(parse_f64(value, "")?.map(Value::F64)),
+            ColumnStorage::StringStorage { .. } => Ok(parse_string(value, "")?.map(Value::String)),
+            ColumnStorage::TimeDateStorage { format, .. } => {
+                Ok(parse_date_time(value, "", format)?.map(Value::TimeDate))
+            }
+            ColumnStorage::EnumeratedStorage { .. } => {
+                Ok(parse_string(value, "")?.map(Value::Enumerated))
+            }
+            ColumnStorage::I64Storage { .. } => Ok(parse_i64(value, "")?.map(Value::I64)),
+            ColumnStorage::DecimalStorage { scale, .. } => {
+                Ok(parse_decimal(value, "", *scale)?.map(Value::Decimal))
+            }
+            ColumnStorage::DateStorage { format, .. } => {
+                Ok(parse_date(value, "", format)?.map(Value::Date))
+            }
+            ColumnStorage::TimeStorage { format, .. } => {
+                Ok(parse_time(value, "", format)?.map(Value::Time))
+            }
+            ColumnStorage::DurationStorage { .. } => {
+                Ok(parse_duration(value, "")?.map(Value::Duration))
+            }
+            ColumnStorage::UuidStorage { .. } => Ok(parse_uuid(value, "")?.map(Value::Uuid)),
         }
     }
 
@@ -204,6 +445,12 @@ impl ColumnStorage {
             ColumnStorage::StringStorage { column, .. } => column.default_value.clone(),
             ColumnStorage::TimeDateStorage { column, .. } => column.default_value.clone(),
             ColumnStorage::EnumeratedStorage { column, .. } => column.default_value.clone(),
+            ColumnStorage::I64Storage { column, .. } => column.default_value.clone(),
+            ColumnStorage::DecimalStorage { column, .. } => column.default_value.clone(),
+            ColumnStorage::DateStorage { column, .. } => column.default_value.clone(),
+            ColumnStorage::TimeStorage { column, .. } => column.default_value.clone(),
+            ColumnStorage::DurationStorage { column, .. } => column.default_value.clone(),
+            ColumnStorage::UuidStorage { column, .. } => column.default_value.clone(),
         }
     }
 
@@ -214,16 +461,38 @@ impl ColumnStorage {
             ColumnStorage::StringStorage { column_type, .. } => column_type.clone(),
             ColumnStorage::TimeDateStorage { column_type, .. } => column_type.clone(),
             ColumnStorage::EnumeratedStorage { column_type, .. } => column_type.clone(),
+            ColumnStorage::I64Storage { column_type, .. } => column_type.clone(),
+            ColumnStorage::DecimalStorage { column_type, .. } => column_type.clone(),
+            ColumnStorage::DateStorage { column_type, .. } => column_type.clone(),
+            ColumnStorage::TimeStorage { column_type, .. } => column_type.clone(),
+            ColumnStorage::DurationStorage { column_type, .. } => column_type.clone(),
+            ColumnStorage::UuidStorage { column_type, .. } => column_type.clone(),
         }
     }
 
     pub fn get_format(&self) -> String {
         match self {
             ColumnStorage::TimeDateStorage { format, .. } => format.clone(),
+            ColumnStorage::DateStorage { format, .. } => format.clone(),
+            ColumnStorage::TimeStorage { format, .. } => format.clone(),
             _ => "".to_string(),
         }
     }
 
+    pub fn get_scale(&self) -> u32 {
+        match self {
+            ColumnStorage::DecimalStorage { scale, .. } => *scale,
+            _ => 0,
+        }
+    }
+
+    pub fn get_allowed_values(&self) -> Vec<String> {
+        match self {
+            ColumnStorage::EnumeratedStorage { allowed_values, .. } => allowed_values.clone(),
+            _ => vec![],
+        }
+    }
+
     pub fn get_column(&self) -> &Column {
         match self {
             ColumnStorage::BooleanStorage { column, .. } => column,
@@ -231,6 +500,180 @@ impl ColumnStorage {
             ColumnStorage::StringStorage { column, .. } => column,
             ColumnStorage::TimeDateStorage { column, .. } => column,
             ColumnStorage::EnumeratedStorage { column, .. } => column,
+            ColumnStorage::I64Storage { column, .. } => column,
+            ColumnStorage::DecimalStorage { column, .. } => column,
+            ColumnStorage::DateStorage { column, .. } => column,
+            ColumnStorage::TimeStorage { column, .. } => column,
+            ColumnStorage::DurationStorage { column, .. } => column,
+            ColumnStorage::UuidStorage { column, .. } => column,
+        }
+    }
+
+    /// the code of an enumerated cell, None for nulls and for other column types
+    pub(crate) fn get_code(&self, index: usize) -> Option<EnumeratedCode> {
+        match self {
+            ColumnStorage::EnumeratedStorage { data, .. } => *data.get(index)?,
+            _ => None,
+        }
+    }
+
+    pub(crate) fn field_error(
+        &self,
+        position: usize,
+        value: &str,
+        error: CacheError,
+    ) -> FieldError {
+        let expected = match self {
+            ColumnStorage::EnumeratedStorage { allowed_values, .. } => {
+                Expected::AllowedValues(allowed_values.clone())
+            }
+            _ => Expected::DataType(self.get_data_type()),
+        };
+
+        FieldError {
+            column: self.get_column().name.clone(),
+            position,
+            value: value.to_string(),
+            expected,
+            error: Box::new(error),
+        }
+    }
+
+    pub(crate) fn get_column_mut(&mut self) -> &mut Column {
+        match self {
+            ColumnStorage::BooleanStorage { column, .. } => column,
+            ColumnStorage::F64Storage { column, .. } => column,
+            ColumnStorage::StringStorage { column, .. } => column,
+            ColumnStorage::TimeDateStorage { column, .. } => column,
+            ColumnStorage::EnumeratedStorage { column, .. } => column,
+            ColumnStorage::I64Storage { column, .. } => column,
+            ColumnStorage::DecimalStorage { column, .. } => column,
+            ColumnStorage::DateStorage { column, .. } => column,
+            ColumnStorage::TimeStorage { column, .. } => column,
+            ColumnStorage::DurationStorage { column, .. } => column,
+            ColumnStorage::UuidStorage { column, .. } => column,
+        }
+    }
+
+    /// Changes the default value after checking that it parses. An enumerated default that is
+    /// not in the allowed values is added to them, the same as when the column is created.
+    pub(crate) fn set_default_value(&mut self, default_value: &str) -> Result<(), CacheError> {
+        if let ColumnStorage::EnumeratedStorage { allowed_values, .. } = self {
+            if !default_value.is_empty() && find_code(allowed_values, default_value).is_none() {
+                allowed_values.push(default_value.to_string());
+                check_allowed_values(allowed_values).inspect_err(|_| {
+                    allowed_values.pop();
+                })?;
+            }
+        } else {
+            self.parse_value(default_value)?;
+        }
+
+        self.get_column_mut().default_value = default_value.to_string();
+        Ok(())
+    }
+
+    /// Adds a value that has already been parsed, used to restore rows exactly as they were
+    /// without applying the default value. Nulls are stored as nulls.
+    pub(crate) fn push_value(&mut self, value: Option<Value>) -> Result<(), CacheError> {
+        let data_type = self.get_data_type();
+        match (self, value) {
+            (ColumnStorage::BooleanStorage { data, .. }, Some(Value::Boolean(value))) => {
+                data.push(Some(value))
+            }
+            (ColumnStorage::F64Storage { data, .. }, Some(Value::F64(value))) => {
+                data.push(Some(value))
+            }
+            (ColumnStorage::StringStorage { data, .. }, Some(Value::String(value))) => {
+                data.push(Some(value))
+            }
+            (ColumnStorage::TimeDateStorage { data, .. }, Some(Value::TimeDate(value))) => {
+                data.push(Some(value))
+            }
+            (
+                ColumnStorage::EnumeratedStorage {
+                    data,
+                    allowed_values,
+                    ..
+                },
+                Some(Value::Enumerated(value)),
+            ) => match find_code(allowed_values, &value) {
+                Some(code) => data.push(Some(code)),
+                None => return Err(CacheError::NotAllowed(value)),
+            },
+            (ColumnStorage::I64Storage { data, .. }, Some(Value::I64(value))) => {
+                data.push(Some(value))
+            }
+            // computed decimals are rounded to the column scale like parsed ones
+            (ColumnStorage::DecimalStorage { data, scale, .. }, Some(Value::Decimal(value))) => {
+                data.push(Some(to_scale(value, *scale)?))
+            }
+            (ColumnStorage::DateStorage { data, .. }, Some(Value::Date(value))) => {
+                data.push(Some(value))
+            }
+            (ColumnStorage::TimeStorage { data, .. }, Some(Value::Time(value))) => {
+                data.push(Some(value))
+            }
+            (ColumnStorage::DurationStorage { data, .. }, Some(Value::Duration(value))) => {
+                data.push(Some(value))
+            }
+            (ColumnStorage::UuidStorage { data, .. }, Some(Value::Uuid(value))) => {
+                data.push(Some(value))
+            }
+            (column_store, None) => column_store.push_null(),
+            (column_store, Some(value)) => {
+                return Err(CacheError::TypeMismatch {
+                    column: column_store.get_column().name.clone(),
+                    expected: data_type,
+                    actual: value.data_type(),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// replaces the value at the index, by pushing it and moving it into place
+    pub(crate) fn set_value(
+        &mut self,
+        index: usize,
+        value: Option<Value>,
+    ) -> Result<(), CacheError> {
+        if index >= self.get_length() {
+            return Err(CacheError::IllegalState);
+        }
+        self.push_value(value)?;
+        self.remove_value(index)
+    }
+
+    fn push_null(&mut self) {
+        match self {
+            ColumnStorage::BooleanStorage { data, .. } => data.push(None),
+            ColumnStorage::F64Storage { data, .. } => data.push(None),
+            ColumnStorage::StringStorage { data, .. } => data.push(None),
+            ColumnStorage::TimeDateStorage { data, .. } => data.push(None),
+            ColumnStorage::EnumeratedStorage { data, .. } => data.push(None),
+            ColumnStorage::I64Storage { data, .. } => data.push(None),
+            ColumnStorage::DecimalStorage { data, .. } => data.push(None),
+            ColumnStorage::DateStorage { data, .. } => data.push(None),
+            ColumnStorage::TimeStorage { data, .. } => data.push(None),
+            ColumnStorage::DurationStorage { data, .. } => data.push(None),
+            ColumnStorage::UuidStorage { data, .. } => data.push(None),
+        }
+    }
+
+    pub(crate) fn swap_values(&mut self, index: usize, other_index: usize) {
+        match self {
+            ColumnStorage::BooleanStorage { data, .. } => data.swap(index, other_index),
+            ColumnStorage::F64Storage { data, .. } => data.swap(index, other_index),
+            ColumnStorage::StringStorage { data, .. } => data.swap(index, other_index),
+            ColumnStorage::TimeDateStorage { data, .. } => data.swap(index, other_index),
+            ColumnStorage::EnumeratedStorage { data, .. } => data.swap(index, other_index),
+            ColumnStorage::I64Storage { data, .. } => data.swap(index, other_index),
+            ColumnStorage::DecimalStorage { data, .. } => data.swap(index, other_index),
+            ColumnStorage::DateStorage { data, .. } => data.swap(index, other_index),
+            ColumnStorage::TimeStorage { data, .. } => data.swap(index, other_index),
+            ColumnStorage::DurationStorage { data, .. } => data.swap(index, other_index),
+            ColumnStorage::UuidStorage { data, .. } => data.swap(index, other_index),
         }
     }
 }
@@ -242,7 +685,7 @@ mod tests {
     fn create_boolean_storage(default_value: &str) -> ColumnStorage {
         ColumnStorage::BooleanStorage {
             column: Column::new("verified", "Verified", default_value),
-            data: vec![],
+            data: Chunked::new(),
             column_type: ColumnStorageDataType::Boolean,
         }
     }
@@ -250,7 +693,7 @@ mod tests {
     fn create_f64_storage(default_value: &str) -> ColumnStorage {
         ColumnStorage::F64Storage {
             column: Column::new("price", "Price", default_value),
-            data: vec![],
+            data: Chunked::new(),
             column_type: ColumnStorageDataType::F64,
         }
     }
@@ -258,7 +701,7 @@ mod tests {
     fn create_string_storage(default_value: &str) -> ColumnStorage {
         ColumnStorage::StringStorage {
             column: Column::new("name", "Name", default_value),
-            data: vec![],
+            data: Chunked::new(),
             column_type: ColumnStorageDataType::String,
         }
     }
@@ -266,7 +709,7 @@ mod tests {
     fn create_time_date_storage() -> ColumnStorage {
         ColumnStorage::TimeDateStorage {
             column: Column::new("start_time", "Start Time", ""),
-            data: vec![],
+            data: Chunked::new(),
             format: "%Y-%m-%d %H:%M:%S".to_string(),
             column_type: ColumnStorageDataType::TimeDate,
         }
@@ -278,7 +721,7 @@ mod tests {
     ) -> ColumnStorage {
         ColumnStorage::EnumeratedStorage {
             column: Column::new("flavor", "Flavor", default_value),
-            data: vec![],
+            data: Chunked::new(),
             allowed_values,
             column_type: ColumnStorageDataType::Enumerated,
         }
@@ -370,7 +813,10 @@ mod tests {
         assert_eq!(storage.add_value("red"), Ok(None));
         assert_eq!(storage.add_value("green"), Ok(None));
         assert_eq!(storage.add_value("blue"), Ok(None));
-        assert_eq!(storage.add_value("xyz"), Err(CacheError::IllegalState {}));
+        assert_eq!(
+            storage.add_value("xyz"),
+            Err(CacheError::NotAllowed("xyz".to_string()))
+        );
         assert_eq!(storage.add_value(""), Ok(None));
         assert_eq!(storage.get_as_string(0), Ok("red".to_string()));
         assert_eq!(storage.get_as_string(1), Ok("green".to_string()));
@@ -379,6 +825,37 @@ mod tests {
         assert_eq!(storage.get_as_string(4), Ok("purple".to_string()));
     }
 
+    #[test]
+    fn test_get_value() {
+        let mut storage = create_f64_storage("");
+        storage.add_value("1.5").unwrap();
+        storage.add_value("").unwrap();
+        assert_eq!(storage.get_value(0), Some(Value::F64(1.5)));
+        assert_eq!(storage.get_value(1), None);
+        assert_eq!(storage.get_value(2), None);
+
+        let storage = create_time_date_storage();
+        assert!(storage
+            .parse_value("2020-01-01 00:00:00")
+            .unwrap()
+            .is_some());
+        assert_eq!(storage.parse_value("").unwrap(), None);
+        assert!(storage.parse_value("invalid").is_err());
+    }
+
+    #[test]
+    fn test_remove_value() {
+        let mut storage = create_string_storage("");
+        storage.add_value("a").unwrap();
+        storage.add_value("b").unwrap();
+        storage.add_value("c").unwrap();
+
+        storage.remove_value(0).unwrap();
+        assert_eq!(storage.get_length(), 2);
+        assert_eq!(storage.get_as_string(0), Ok("c".to_string()));
+        assert_eq!(storage.get_as_string(1), Ok("b".to_string()));
+    }
+
     #[test]
     fn test_remove_last_value() {
         let mut storage = create_string_storage("unknown");
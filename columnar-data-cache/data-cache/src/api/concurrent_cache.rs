@@ -0,0 +1,106 @@
+use crate::api::cache::Cache;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+/// Shares a cache between threads. A single writer applies batches to its own copy of the
+/// cache and then publishes an immutable copy. Readers take the latest published copy, which
+/// stays consistent for as long as they hold it and is never blocked by a running batch.
+///
+/// Publishing is cheap because a copy shares the rows of the cache it was made from, only the
+/// chunks of rows that a batch changes are copied.
+pub struct ConcurrentCache {
+    writer: Mutex<Cache>,
+    published: RwLock<Arc<Cache>>,
+}
+
+impl ConcurrentCache {
+    pub fn new(cache: Cache) -> Self {
+        let published = RwLock::new(Arc::new(cache.clone()));
+        ConcurrentCache {
+            writer: Mutex::new(cache),
+            published,
+        }
+    }
+
+    /// the point in time copy of the cache as of the last completed batch
+    pub fn snapshot(&self) -> Arc<Cache> {
+        self.published
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Runs a batch of changes against the writer copy and publishes the result when the batch
+    /// returns. Batches run one at a time. Changes made before a batch returns an error are
+    /// still published, the same as if they were applied to a cache directly.
+    ///
+    /// A batch that panics is not published. The next batch starts from the last published
+    /// copy instead, which does not have the observers of the writer copy.
+    pub fn write<T, F>(&self, batch: F) -> T
+    where
+        F: FnOnce(&mut Cache) -> T,
+    {
+        let mut cache = self.writer.lock().unwrap_or_else(|poisoned| {
+            let mut cache = poisoned.into_inner();
+            *cache = self.snapshot().as_ref().clone();
+            self.writer.clear_poison();
+            cache
+        });
+        let result = batch(&mut cache);
+
+        // the copy is made outside of the published lock so readers only wait for the swap
+        let next = Arc::new(cache.clone());
+        *self
+            .published
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = next;
+
+        result
+    }
+}
+
+impl Default for ConcurrentCache {
+    fn default() -> Self {
+        Self::new(Cache::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_is_point_in_time() {
+        let concurrent_cache = ConcurrentCache::default();
+        concurrent_cache.write(|cache| cache.add_string_column("name", "Name", "").unwrap());
+
+        let before = concurrent_cache.snapshot();
+        let guid = concurrent_cache.write(|cache| cache.create_row("fred").unwrap());
+        let after = concurrent_cache.snapshot();
+
+        assert_eq!(before.row_len(), 0);
+        assert!(before.csv_for_guid(&guid).is_err());
+        assert_eq!(after.csv_for_guid(&guid).unwrap(), "fred");
+    }
+
+    #[test]
+    fn test_panicked_batch_is_not_published() {
+        let concurrent_cache = ConcurrentCache::default();
+        concurrent_cache.write(|cache| cache.add_string_column("name", "Name", "").unwrap());
+        let guid = concurrent_cache.write(|cache| cache.create_row("fred").unwrap());
+
+        let result = std::panic::catch_unwind(|| {
+            concurrent_cache.write(|cache| {
+                cache.create_row("wilma").unwrap();
+                panic!("batch failed");
+            })
+        });
+        assert!(result.is_err());
+        assert_eq!(concurrent_cache.snapshot().row_len(), 1);
+
+        let other_guid = concurrent_cache.write(|cache| cache.create_row("barney").unwrap());
+        let snapshot = concurrent_cache.snapshot();
+        assert_eq!(snapshot.row_len(), 2);
+        assert_eq!(snapshot.csv_for_guid(&guid).unwrap(), "fred");
+        assert_eq!(snapshot.csv_for_guid(&other_guid).unwrap(), "barney");
+    }
+}
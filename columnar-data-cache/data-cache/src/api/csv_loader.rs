@@ -0,0 +1,186 @@
+use crate::api::cache::Cache;
+use crate::api::cache_error::CacheError;
+use csv::{ReaderBuilder, StringRecord, Trim};
+use std::io::Read;
+use uuid::Uuid;
+
+/// A row that could not be loaded, the line number is 1 based and includes the header line.
+#[derive(Debug, PartialEq)]
+pub struct LineError {
+    pub line: u64,
+    pub error: CacheError,
+}
+
+/// Summary of a bulk load. Rows that failed do not stop the load and are listed in errors.
+#[derive(Debug, PartialEq, Default)]
+pub struct LoadReport {
+    pub created: Vec<Uuid>,
+    pub errors: Vec<LineError>,
+    pub ignored_headers: Vec<String>,
+    pub missing_columns: Vec<String>,
+}
+
+impl LoadReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+pub(crate) fn load_csv<R: Read>(cache: &mut Cache, reader: R) -> Result<LoadReport, CacheError> {
+    let mut records = CsvRecords::new(reader, true);
+    let headers = records.headers()?;
+    let mut report = LoadReport::default();
+
+    let column_names: Vec<String> = cache
+        .get_metadata()
+        .into_iter()
+        .map(|metadata| metadata.name)
+        .collect();
+
+    // for each column, the position of the matching field in a record
+    let field_positions: Vec<Option<usize>> = column_names
+        .iter()
+        .map(|name| headers.iter().position(|header| header == name))
+        .collect();
+
+    for (name, position) in column_names.iter().zip(field_positions.iter()) {
+        if position.is_none() {
+            report.missing_columns.push(name.clone());
+        }
+    }
+
+    for header in headers.iter() {
+        if !column_names.iter().any(|name| name == header) {
+            report.ignored_headers.push(header.to_string());
+        }
+    }
+
+    for result in records {
+        let (line, fields) = match result {
+            Ok(record) => record,
+            Err(error) => {
+                let line = error.position().map_or(0, |position| position.line());
+                report.errors.push(LineError {
+                    line,
+                    error: error.into(),
+                });
+                continue;
+            }
+        };
+
+        let values: Vec<&str> = field_positions
+            .iter()
+            .map(|position| {
+                position
+                    .and_then(|position| fields.get(position))
+                    .map_or("", String::as_str)
+            })
+            .collect();
+        match cache.create_row_from_values(&values) {
+            Ok(guid) => report.created.push(guid),
+            Err(error) => report.errors.push(LineError { line, error }),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Splits a single CSV row into its fields, see CsvRecords for how fields are trimmed.
+/// An empty row is one empty field.
+pub(crate) fn parse_row(row: &str) -> Result<Vec<String>, CacheError> {
+    let mut records = CsvRecords::new(row.as_bytes(), false);
+    let fields = match records.next() {
+        Some(record) => record?.1,
+        None => vec![String::new()],
+    };
+    if records.next().is_some() {
+        return Err(CacheError::ParseError(
+            "a row is a single CSV record, quote values holding a line break".into(),
+        ));
+    }
+    Ok(fields)
+}
+
+/// Joins values into a CSV row that parse_row reads back the same. Values holding a comma,
+/// quote or line break are quoted.
+pub(crate) fn format_row<I: IntoIterator<Item = String>>(values: I) -> String {
+    values
+        .into_iter()
+        .map(|value| {
+            if value.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Reads CSV records as 1 based line numbers and fields, with the spaces around every header
+/// and field trimmed.
+pub(crate) struct CsvRecords<R> {
+    reader: csv::Reader<R>,
+}
+
+impl<R: Read> CsvRecords<R> {
+    pub(crate) fn new(reader: R, has_headers: bool) -> Self {
+        let reader = ReaderBuilder::new()
+            .trim(Trim::All)
+            .has_headers(has_headers)
+            .from_reader(reader);
+        CsvRecords { reader }
+    }
+
+    pub(crate) fn headers(&mut self) -> Result<StringRecord, CacheError> {
+        Ok(self.reader.headers()?.clone())
+    }
+}
+
+impl<R: Read> Iterator for CsvRecords<R> {
+    type Item = Result<(u64, Vec<String>), csv::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = StringRecord::new();
+        match self.reader.read_record(&mut record) {
+            Ok(false) => None,
+            Ok(true) => {
+                let line = record.position().map_or(0, |position| position.line());
+                Some(Ok((line, record.iter().map(str::to_string).collect())))
+            }
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_row() {
+        assert_eq!(
+            parse_row(" fred ,\" Rubble, Barney \",\"say \"\"hi\"\"\",").unwrap(),
+            vec!["fred", "Rubble, Barney", "say \"hi\"", ""]
+        );
+        assert_eq!(parse_row("").unwrap(), vec![""]);
+        assert!(parse_row("fred\nwilma").is_err());
+    }
+
+    #[test]
+    fn test_format_row_reads_back() {
+        let values = vec![
+            "fred".to_string(),
+            "Flintstone, Fred".to_string(),
+            "say \"hi\"\non two lines".to_string(),
+            "".to_string(),
+        ];
+        let row = format_row(values.clone());
+        assert_eq!(
+            row,
+            "fred,\"Flintstone, Fred\",\"say \"\"hi\"\"\non two lines\","
+        );
+        assert_eq!(parse_row(&row).unwrap(), values);
+        assert_eq!(format_row(vec![String::new()]), "");
+    }
+}
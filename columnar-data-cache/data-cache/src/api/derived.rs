@@ -0,0 +1,95 @@
+//! Columns whose values are computed from other columns of the same row.
+//!
+//! A derived column is stored like any other column so it can be queried, sorted and
+//! exported, but its values are always computed. They are recomputed whenever a row is
+//! created or updated, and any value supplied for a derived column is ignored.
+
+use crate::api::cache_error::CacheError;
+use crate::api::column_storage::ColumnStorage;
+use crate::api::value::Value;
+use std::sync::Arc;
+
+/// Computes the value of a derived column from the values of its source columns, given in
+/// the order the sources were listed. Returning None stores a null.
+pub type Compute = dyn Fn(&[Option<Value>]) -> Option<Value> + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct Derivation {
+    pub(crate) sources: Vec<String>,
+    compute: Arc<Compute>,
+}
+
+impl Derivation {
+    pub(crate) fn new(sources: Vec<String>, compute: Arc<Compute>) -> Self {
+        Derivation { sources, compute }
+    }
+
+    pub(crate) fn uses(&self, name: &str) -> bool {
+        self.sources.iter().any(|source| source == name)
+    }
+
+    pub(crate) fn compute(
+        &self,
+        column_stores: &[ColumnStorage],
+        index: usize,
+    ) -> Result<Option<Value>, CacheError> {
+        let values = self
+            .sources
+            .iter()
+            .map(|source| {
+                column_stores
+                    .iter()
+                    .find(|column_store| column_store.get_column().name == *source)
+                    .map(|column_store| column_store.get_value(index))
+                    .ok_or_else(|| CacheError::ColumnNotFound(source.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((self.compute)(&values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::chunked::Chunked;
+    use crate::api::column::Column;
+    use crate::api::column_storage::ColumnStorageDataType;
+
+    #[test]
+    fn test_compute_in_source_order() {
+        let mut column_stores = vec![];
+        for name in ["width", "height"] {
+            let mut column_store = ColumnStorage::F64Storage {
+                column: Column::new(name, name, ""),
+                data: Chunked::new(),
+                column_type: ColumnStorageDataType::F64,
+            };
+            column_store.add_value("4").unwrap();
+            column_stores.push(column_store);
+        }
+        column_stores[1].add_value("").unwrap();
+        column_stores[0].add_value("3").unwrap();
+
+        let derivation = Derivation::new(
+            vec!["height".to_string(), "width".to_string()],
+            Arc::new(|values| match values {
+                [Some(Value::F64(height)), Some(Value::F64(width))] => {
+                    Some(Value::F64(height / width))
+                }
+                _ => None,
+            }),
+        );
+        assert!(derivation.uses("width"));
+        assert_eq!(
+            derivation.compute(&column_stores, 0),
+            Ok(Some(Value::F64(1.0)))
+        );
+        assert_eq!(derivation.compute(&column_stores, 1), Ok(None));
+
+        let derivation = Derivation::new(vec!["depth".to_string()], Arc::new(|_| None));
+        assert_eq!(
+            derivation.compute(&column_stores, 0),
+            Err(CacheError::ColumnNotFound("depth".to_string()))
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use crate::api::value::Value;
+use std::sync::mpsc::Sender;
+use uuid::Uuid;
+
+/// The before and after value of a single cell that changed in an update.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CellChange {
+    pub column: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum CacheEvent {
+    RowCreated {
+        guid: Uuid,
+    },
+    /// changes only holds the cells whose value changed
+    RowUpdated {
+        guid: Uuid,
+        changes: Vec<CellChange>,
+    },
+    RowRemoved {
+        guid: Uuid,
+    },
+    ColumnAdded {
+        name: String,
+    },
+    ColumnRemoved {
+        name: String,
+    },
+    ColumnRenamed {
+        name: String,
+        new_name: String,
+    },
+    /// the display name, default value or type of the column changed
+    ColumnChanged {
+        name: String,
+    },
+}
+
+/// Receives the events of a cache. Observers must be Send and Sync so that a cache can be
+/// shared between threads, which rules out the Rc<RefCell<dyn Observer>> approach.
+pub trait CacheObserver: Send + Sync {
+    fn update(&mut self, event: &CacheEvent);
+}
+
+impl<F> CacheObserver for F
+where
+    F: FnMut(&CacheEvent) + Send + Sync,
+{
+    fn update(&mut self, event: &CacheEvent) {
+        self(event)
+    }
+}
+
+impl CacheObserver for Sender<CacheEvent> {
+    fn update(&mut self, event: &CacheEvent) {
+        // a receiver that has gone away is not an error for the cache
+        let _ = self.send(event.clone());
+    }
+}
+
+/// Identifies an observer so that it can be removed again.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct ObserverId(pub(crate) usize);
+
+#[derive(Default)]
+pub(crate) struct Observers {
+    next_id: usize,
+    observers: Vec<(ObserverId, Box<dyn CacheObserver>)>,
+}
+
+impl Observers {
+    pub(crate) fn add(&mut self, observer: Box<dyn CacheObserver>) -> ObserverId {
+        let id = ObserverId(self.next_id);
+        self.next_id += 1;
+        self.observers.push((id, observer));
+        id
+    }
+
+    pub(crate) fn remove(&mut self, id: ObserverId) -> bool {
+        let length = self.observers.len();
+        self.observers.retain(|(observer_id, _)| *observer_id != id);
+        self.observers.len() != length
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    pub(crate) fn notify(&mut self, event: CacheEvent) {
+        for (_, observer) in self.observers.iter_mut() {
+            observer.update(&event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_observers() {
+        let mut observers = Observers::default();
+        let (sender, receiver) = channel();
+        let id = observers.add(Box::new(sender));
+
+        observers.notify(CacheEvent::ColumnAdded {
+            name: "name".to_string(),
+        });
+        assert_eq!(
+            receiver.try_recv(),
+            Ok(CacheEvent::ColumnAdded {
+                name: "name".to_string()
+            })
+        );
+
+        assert!(observers.remove(id));
+        assert!(!observers.remove(id));
+        assert!(observers.is_empty());
+    }
+}
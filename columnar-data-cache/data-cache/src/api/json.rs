@@ -0,0 +1,77 @@
+//! JSON objects keyed by column name. Booleans and numbers use JSON types, nulls are JSON null
+//! and everything else is the string the column would write to csv, so that decimals keep their
+//! precision and dates use the column format. The guid is written under the _guid key.
+
+use crate::api::arrow_conversion::GUID_FIELD_NAME;
+use crate::api::cache_error::CacheError;
+use crate::api::column_storage::ColumnStorage;
+use crate::api::value::Value;
+use serde_json::{Map, Number};
+use uuid::Uuid;
+
+pub(crate) fn row_to_json(
+    column_stores: &[&ColumnStorage],
+    guid: &Uuid,
+    index: usize,
+) -> Result<serde_json::Value, CacheError> {
+    let mut object = Map::new();
+    object.insert(
+        GUID_FIELD_NAME.to_string(),
+        serde_json::Value::String(guid.to_string()),
+    );
+
+    for column_store in column_stores {
+        let json_value = match column_store.get_value(index) {
+            None => serde_json::Value::Null,
+            Some(Value::Boolean(value)) => serde_json::Value::Bool(value),
+            Some(Value::I64(value)) => serde_json::Value::Number(value.into()),
+            // JSON has no NaN or infinity so those become null
+            Some(Value::F64(value)) => Number::from_f64(value)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            Some(_) => serde_json::Value::String(column_store.get_as_string(index)?),
+        };
+        object.insert(column_store.get_column().name.clone(), json_value);
+    }
+
+    Ok(serde_json::Value::Object(object))
+}
+
+/// Converts a JSON object to values in column order. Missing keys become empty values so the
+/// column default applies, nulls become None so they are stored as null.
+pub(crate) fn json_to_values(
+    column_stores: &[ColumnStorage],
+    json: &serde_json::Value,
+) -> Result<Vec<Option<String>>, CacheError> {
+    let object = json
+        .as_object()
+        .ok_or_else(|| CacheError::ParseError(format!("not a JSON object: {}", json).into()))?;
+
+    for key in object.keys() {
+        if key != GUID_FIELD_NAME
+            && !column_stores
+                .iter()
+                .any(|column_store| column_store.get_column().name == *key)
+        {
+            return Err(CacheError::ColumnNotFound(key.clone()));
+        }
+    }
+
+    let values = column_stores
+        .iter()
+        .map(
+            |column_store| match object.get(&column_store.get_column().name) {
+                None => Ok(Some("".to_string())),
+                Some(serde_json::Value::Null) => Ok(None),
+                Some(serde_json::Value::String(value)) => Ok(Some(value.clone())),
+                Some(serde_json::Value::Bool(value)) => Ok(Some(value.to_string())),
+                Some(serde_json::Value::Number(value)) => Ok(Some(value.to_string())),
+                Some(value) => Err(CacheError::ParseError(
+                    format!("{} can not hold {}", column_store.get_column().name, value).into(),
+                )),
+            },
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(values)
+}
@@ -0,0 +1,119 @@
+use crate::api::chunked::{Chunked, ChunkedMap};
+use crate::api::column_storage::{ColumnStorage, ColumnStorageDataType};
+use std::mem::size_of;
+use uuid::Uuid;
+
+/// Approximate bytes held by one column. Data is the per row storage including the heap
+/// allocations of string values, dictionary is the allowed values of an enumerated column.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ColumnMemoryUsage {
+    pub name: String,
+    pub data_type: ColumnStorageDataType,
+    pub data_bytes: usize,
+    pub dictionary_bytes: usize,
+}
+
+impl ColumnMemoryUsage {
+    pub fn total_bytes(&self) -> usize {
+        self.data_bytes + self.dictionary_bytes
+    }
+}
+
+/// Approximate memory used by a cache, guid bytes covers the guids and the guid index.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MemoryReport {
+    pub columns: Vec<ColumnMemoryUsage>,
+    pub guid_bytes: usize,
+}
+
+impl MemoryReport {
+    pub fn total_bytes(&self) -> usize {
+        self.guid_bytes
+            + self
+                .columns
+                .iter()
+                .map(ColumnMemoryUsage::total_bytes)
+                .sum::<usize>()
+    }
+}
+
+fn vec_bytes<T>(data: &Vec<T>) -> usize {
+    data.capacity() * size_of::<T>()
+}
+
+fn strings_bytes<'a>(values: impl Iterator<Item = &'a String>) -> usize {
+    values.map(|value| value.capacity()).sum()
+}
+
+pub(crate) fn column_memory_usage(column_store: &ColumnStorage) -> ColumnMemoryUsage {
+    let (data_bytes, dictionary_bytes) = match column_store {
+        ColumnStorage::StringStorage { data, .. } => (
+            data.allocated_bytes() + strings_bytes(data.iter().flatten()),
+            0,
+        ),
+        ColumnStorage::EnumeratedStorage {
+            data,
+            allowed_values,
+            ..
+        } => (
+            data.allocated_bytes(),
+            vec_bytes(allowed_values) + strings_bytes(allowed_values.iter()),
+        ),
+        ColumnStorage::BooleanStorage { data, .. } => (data.allocated_bytes(), 0),
+        ColumnStorage::F64Storage { data, .. } => (data.allocated_bytes(), 0),
+        ColumnStorage::TimeDateStorage { data, .. } => (data.allocated_bytes(), 0),
+        ColumnStorage::I64Storage { data, .. } => (data.allocated_bytes(), 0),
+        ColumnStorage::DecimalStorage { data, .. } => (data.allocated_bytes(), 0),
+        ColumnStorage::DateStorage { data, .. } => (data.allocated_bytes(), 0),
+        ColumnStorage::TimeStorage { data, .. } => (data.allocated_bytes(), 0),
+        ColumnStorage::DurationStorage { data, .. } => (data.allocated_bytes(), 0),
+        ColumnStorage::UuidStorage { data, .. } => (data.allocated_bytes(), 0),
+    };
+
+    ColumnMemoryUsage {
+        name: column_store.get_column().name.clone(),
+        data_type: column_store.get_data_type(),
+        data_bytes,
+        dictionary_bytes,
+    }
+}
+
+// the index is estimated from its capacity since the hash map does not expose its allocation
+pub(crate) fn guid_memory_usage(
+    guids: &Chunked<Uuid>,
+    guid_index: &ChunkedMap<Uuid, usize>,
+) -> usize {
+    guids.allocated_bytes() + guid_index.allocated_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::column::Column;
+
+    #[test]
+    fn test_enumerated_is_smaller_than_string() {
+        let flavors = ["vanilla", "chocolate", "strawberry"];
+        let mut string_storage = ColumnStorage::StringStorage {
+            column: Column::new("flavor", "Flavor", ""),
+            data: Chunked::new(),
+            column_type: ColumnStorageDataType::String,
+        };
+        let mut enumerated_storage = ColumnStorage::EnumeratedStorage {
+            column: Column::new("flavor", "Flavor", ""),
+            data: Chunked::new(),
+            allowed_values: flavors.iter().map(|flavor| flavor.to_string()).collect(),
+            column_type: ColumnStorageDataType::Enumerated,
+        };
+        for index in 0..1000 {
+            string_storage.add_value(flavors[index % 3]).unwrap();
+            enumerated_storage.add_value(flavors[index % 3]).unwrap();
+        }
+
+        let string_usage = column_memory_usage(&string_storage);
+        let enumerated_usage = column_memory_usage(&enumerated_storage);
+        assert_eq!(string_usage.dictionary_bytes, 0);
+        assert!(enumerated_usage.dictionary_bytes > 0);
+        assert!(enumerated_usage.total_bytes() * 5 < string_usage.total_bytes());
+    }
+}
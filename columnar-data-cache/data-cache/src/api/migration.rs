@@ -0,0 +1,201 @@
+use crate::api::cache_error::CacheError;
+use crate::api::chunked::Chunked;
+use crate::api::column::Column;
+use crate::api::column_storage::{check_allowed_values, ColumnStorage, ColumnStorageDataType};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// The type to migrate a column to, along with the settings that type needs.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ColumnType {
+    String,
+    Boolean,
+    F64,
+    TimeDate { format: String },
+    Enumerated { allowed_values: Vec<String> },
+    I64,
+    Decimal { scale: u32 },
+    Date { format: String },
+    Time { format: String },
+    Duration,
+    Uuid,
+}
+
+impl ColumnType {
+    pub(crate) fn create_storage(&self, column: Column) -> Result<ColumnStorage, CacheError> {
+        let column_store = match self {
+            ColumnType::String => ColumnStorage::StringStorage {
+                column,
+                data: Chunked::new(),
+                column_type: ColumnStorageDataType::String,
+            },
+            ColumnType::Boolean => ColumnStorage::BooleanStorage {
+                column,
+                data: Chunked::new(),
+                column_type: ColumnStorageDataType::Boolean,
+            },
+            ColumnType::F64 => ColumnStorage::F64Storage {
+                column,
+                data: Chunked::new(),
+                column_type: ColumnStorageDataType::F64,
+            },
+            ColumnType::TimeDate { format } => ColumnStorage::TimeDateStorage {
+                column,
+                data: Chunked::new(),
+                format: format.clone(),
+                column_type: ColumnStorageDataType::TimeDate,
+            },
+            ColumnType::Enumerated { allowed_values } => {
+                check_allowed_values(allowed_values)?;
+                ColumnStorage::EnumeratedStorage {
+                    column,
+                    data: Chunked::new(),
+                    allowed_values: allowed_values.clone(),
+                    column_type: ColumnStorageDataType::Enumerated,
+                }
+            }
+            ColumnType::I64 => ColumnStorage::I64Storage {
+                column,
+                data: Chunked::new(),
+                column_type: ColumnStorageDataType::I64,
+            },
+            ColumnType::Decimal { scale } => {
+                if *scale > Decimal::MAX_SCALE {
+                    return Err(CacheError::UnsupportedType(format!(
+                        "decimal scale {}",
+                        scale
+                    )));
+                }
+                ColumnStorage::DecimalStorage {
+                    column,
+                    data: Chunked::new(),
+                    scale: *scale,
+                    column_type: ColumnStorageDataType::Decimal,
+                }
+            }
+            ColumnType::Date { format } => ColumnStorage::DateStorage {
+                column,
+                data: Chunked::new(),
+                format: format.clone(),
+                column_type: ColumnStorageDataType::Date,
+            },
+            ColumnType::Time { format } => ColumnStorage::TimeStorage {
+                column,
+                data: Chunked::new(),
+                format: format.clone(),
+                column_type: ColumnStorageDataType::Time,
+            },
+            ColumnType::Duration => ColumnStorage::DurationStorage {
+                column,
+                data: Chunked::new(),
+                column_type: ColumnStorageDataType::Duration,
+            },
+            ColumnType::Uuid => ColumnStorage::UuidStorage {
+                column,
+                data: Chunked::new(),
+                column_type: ColumnStorageDataType::Uuid,
+            },
+        };
+
+        Ok(column_store)
+    }
+}
+
+/// A row whose value could not be converted to the new type.
+#[derive(Debug, PartialEq)]
+pub struct RowError {
+    pub guid: Uuid,
+    pub value: String,
+    pub error: CacheError,
+}
+
+/// Summary of a type migration. The column is only replaced when there are no errors,
+/// otherwise the cache is left as it was.
+#[derive(Debug, PartialEq, Default)]
+pub struct MigrationReport {
+    pub converted: usize,
+    pub errors: Vec<RowError>,
+}
+
+impl MigrationReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Converts every value through its string form, nulls stay null. Returns the new column storage
+/// only when all the rows converted.
+pub(crate) fn migrate(
+    column_store: &ColumnStorage,
+    guids: &Chunked<Uuid>,
+    column_type: &ColumnType,
+    default_value: &str,
+) -> Result<(Option<ColumnStorage>, MigrationReport), CacheError> {
+    let column = column_store.get_column();
+    // an empty default while converting so that nulls are not replaced
+    let mut new_column_store =
+        column_type.create_storage(Column::new(&column.name, &column.display_name, ""))?;
+    new_column_store.set_default_value(default_value)?;
+    new_column_store.get_column_mut().default_value = "".to_string();
+
+    let mut report = MigrationReport::default();
+    for (index, guid) in guids.iter().enumerate() {
+        let value = column_store.get_as_string(index)?;
+        match new_column_store.add_value(&value) {
+            Ok(_) => report.converted += 1,
+            Err(error) => report.errors.push(RowError {
+                guid: *guid,
+                value,
+                error,
+            }),
+        }
+    }
+
+    if !report.is_ok() {
+        return Ok((None, report));
+    }
+
+    new_column_store.get_column_mut().default_value = default_value.to_string();
+    Ok((Some(new_column_store), report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_default() {
+        let column_store = ColumnStorage::StringStorage {
+            column: Column::new("count", "Count", ""),
+            data: Chunked::new(),
+            column_type: ColumnStorageDataType::String,
+        };
+        assert!(migrate(&column_store, &Chunked::new(), &ColumnType::I64, "many").is_err());
+        assert!(migrate(&column_store, &Chunked::new(), &ColumnType::I64, "7").is_ok());
+    }
+
+    #[test]
+    fn test_enumerated_default_is_allowed() {
+        let column_store = ColumnStorage::StringStorage {
+            column: Column::new("flavor", "Flavor", ""),
+            data: vec![Some("mint".to_string()), None].into(),
+            column_type: ColumnStorageDataType::String,
+        };
+        let column_type = ColumnType::Enumerated {
+            allowed_values: vec!["mint".to_string()],
+        };
+        let (new_column_store, report) = migrate(
+            &column_store,
+            &vec![Uuid::new_v4(), Uuid::new_v4()].into(),
+            &column_type,
+            "lime",
+        )
+        .unwrap();
+        let new_column_store = new_column_store.unwrap();
+
+        assert_eq!(report.converted, 2);
+        assert_eq!(new_column_store.get_allowed_values(), vec!["mint", "lime"]);
+        assert_eq!(new_column_store.get_value(1), None);
+        assert_eq!(new_column_store.get_column().default_value, "lime");
+    }
+}
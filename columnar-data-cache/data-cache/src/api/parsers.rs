@@ -1,8 +1,12 @@
-use crate::api::cache_error::CacheError;
-use chrono::NaiveDateTime;
+//! The parsers module contains type specific functions that parse a string value into a specific type.
+//! Each function returns a Result<Option<T>, CacheError::ParseError> where T is the desired output type.
 
-/// The parsers module contains type specific functions that parse a string value into a specific type.
-/// Each function returns a Result<Option<T>, CacheError::ParseError> where T is the desired output type.
+use crate::api::cache_error::CacheError;
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::str::FromStr;
+use uuid::Uuid;
 
 // This is a helper function that returns the best value based on the value and the default value.
 fn get_value(value: &str, default_value: &str) -> Option<String> {
@@ -57,6 +61,147 @@ pub fn parse_date_time(
     }
 }
 
+/// Parses a string value into an i64.
+pub fn parse_i64(value: &str, default_value: &str) -> Result<Option<i64>, CacheError> {
+    match get_value(value, default_value) {
+        Some(the_value) => Ok(Some(the_value.parse::<i64>()?)),
+        _ => Ok(None),
+    }
+}
+
+/// Rounds a decimal half away from zero to the given number of decimal places and gives it
+/// exactly that scale. Fails when the value is too large to have that many decimal places.
+pub fn to_scale(decimal: Decimal, scale: u32) -> Result<Decimal, CacheError> {
+    let mut scaled = decimal.round_dp_with_strategy(scale, RoundingStrategy::MidpointAwayFromZero);
+    scaled.rescale(scale);
+    if scaled.scale() != scale {
+        return Err(CacheError::ParseError(
+            format!("{} is too large for {} decimal places", decimal, scale).into(),
+        ));
+    }
+    Ok(scaled)
+}
+
+/// Parses a string value into a decimal with the given number of decimal places.
+/// Values with more decimal places are rounded half away from zero.
+pub fn parse_decimal(
+    value: &str,
+    default_value: &str,
+    scale: u32,
+) -> Result<Option<Decimal>, CacheError> {
+    match get_value(value, default_value) {
+        Some(the_value) => Ok(Some(to_scale(
+            Decimal::from_str(the_value.as_str())?,
+            scale,
+        )?)),
+        _ => Ok(None),
+    }
+}
+
+pub fn parse_date(
+    value: &str,
+    default_value: &str,
+    format: &str,
+) -> Result<Option<NaiveDate>, CacheError> {
+    match get_value(value, default_value) {
+        Some(the_value) => Ok(Some(NaiveDate::parse_from_str(the_value.as_str(), format)?)),
+        _ => Ok(None),
+    }
+}
+
+pub fn parse_time(
+    value: &str,
+    default_value: &str,
+    format: &str,
+) -> Result<Option<NaiveTime>, CacheError> {
+    match get_value(value, default_value) {
+        Some(the_value) => Ok(Some(NaiveTime::parse_from_str(the_value.as_str(), format)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Parses a string value into a duration. The following forms are supported:
+/// * hours:minutes:seconds with optional fractional seconds, for example 1:30:00 or 0:00:01.5
+/// * a number of seconds, for example 90 or 1.5
+///
+/// Either form may start with a minus sign.
+pub fn parse_duration(value: &str, default_value: &str) -> Result<Option<Duration>, CacheError> {
+    match get_value(value, default_value) {
+        Some(the_value) => {
+            let (negative, unsigned) = match the_value.strip_prefix('-') {
+                Some(unsigned) => (true, unsigned),
+                None => (false, the_value.as_str()),
+            };
+
+            let invalid_duration =
+                || CacheError::ParseError(format!("invalid duration {}", the_value).into());
+            let parts: Vec<&str> = unsigned.split(':').collect();
+            let (hours, minutes, seconds) = match parts.as_slice() {
+                [hours, minutes, seconds] => (
+                    hours.parse::<u32>()?,
+                    minutes.parse::<u32>()?,
+                    Decimal::from_str(seconds)?,
+                ),
+                [seconds] => (0, 0, Decimal::from_str(seconds)?),
+                _ => return Err(invalid_duration()),
+            };
+            if parts.len() == 3 && (minutes >= 60 || seconds >= Decimal::from(60)) {
+                return Err(invalid_duration());
+            }
+            if seconds.is_sign_negative() {
+                return Err(invalid_duration());
+            }
+
+            let nanoseconds = seconds
+                .checked_mul(Decimal::from(1_000_000_000))
+                .ok_or_else(invalid_duration)?
+                .trunc()
+                .to_i64()
+                .ok_or_else(invalid_duration)?;
+            let duration = Duration::hours(hours as i64)
+                + Duration::minutes(minutes as i64)
+                + Duration::nanoseconds(nanoseconds);
+            Ok(Some(if negative { -duration } else { duration }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Formats a duration the same way parse_duration reads it, as hours:minutes:seconds.
+pub fn format_duration(duration: &Duration) -> String {
+    let sign = if *duration < Duration::zero() {
+        "-"
+    } else {
+        ""
+    };
+    let duration = duration.abs();
+    let total_seconds = duration.num_seconds();
+    let nanoseconds = duration.subsec_nanos();
+    let fraction = if nanoseconds == 0 {
+        "".to_string()
+    } else {
+        format!(".{:09}", nanoseconds)
+            .trim_end_matches('0')
+            .to_string()
+    };
+
+    format!(
+        "{}{}:{:02}:{:02}{}",
+        sign,
+        total_seconds / 3600,
+        (total_seconds / 60) % 60,
+        total_seconds % 60,
+        fraction
+    )
+}
+
+pub fn parse_uuid(value: &str, default_value: &str) -> Result<Option<Uuid>, CacheError> {
+    match get_value(value, default_value) {
+        Some(the_value) => Ok(Some(Uuid::parse_str(the_value.as_str())?)),
+        _ => Ok(None),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -155,4 +300,99 @@ mod tests {
             panic!("Expected ParseError");
         }
     }
+
+    #[test]
+    fn test_i64() {
+        assert_eq!(
+            parse_i64("9007199254740993", "").unwrap(),
+            Some(9007199254740993)
+        );
+        assert_eq!(parse_i64("", "-1").unwrap(), Some(-1));
+        assert_eq!(parse_i64("", "").unwrap(), None);
+        assert!(parse_i64("1.5", "").is_err());
+    }
+
+    #[test]
+    fn test_decimal() {
+        assert_eq!(
+            parse_decimal("1.5", "", 2).unwrap().unwrap().to_string(),
+            "1.50"
+        );
+        assert_eq!(
+            parse_decimal("0.125", "", 2).unwrap().unwrap().to_string(),
+            "0.13"
+        );
+        assert_eq!(
+            parse_decimal("-0.125", "", 2).unwrap().unwrap().to_string(),
+            "-0.13"
+        );
+        assert_eq!(
+            parse_decimal("", "10", 0).unwrap().unwrap().to_string(),
+            "10"
+        );
+        assert!(parse_decimal("ten", "", 2).is_err());
+
+        // 28 decimal places leave room for a single digit before the point
+        assert_eq!(
+            parse_decimal("7.9228162514264337593543950335", "", 28)
+                .unwrap()
+                .unwrap()
+                .to_string(),
+            "7.9228162514264337593543950335"
+        );
+        assert_eq!(
+            parse_decimal("8", "", 28).err().unwrap().to_string(),
+            "ParseError: 8 is too large for 28 decimal places"
+        );
+        assert!(parse_decimal("1000", "", 28).is_err());
+    }
+
+    #[test]
+    fn test_date_and_time() {
+        assert_eq!(
+            parse_date("2020-02-29", "", "%Y-%m-%d").unwrap(),
+            NaiveDate::from_ymd_opt(2020, 2, 29)
+        );
+        assert!(parse_date("2021-02-29", "", "%Y-%m-%d").is_err());
+        assert_eq!(
+            parse_time("13:45:00", "", "%H:%M:%S").unwrap(),
+            NaiveTime::from_hms_opt(13, 45, 0)
+        );
+        assert!(parse_time("25:00:00", "", "%H:%M:%S").is_err());
+    }
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(
+            parse_duration("1:30:00", "").unwrap(),
+            Some(Duration::minutes(90))
+        );
+        assert_eq!(
+            parse_duration("90", "").unwrap(),
+            Some(Duration::seconds(90))
+        );
+        assert_eq!(
+            parse_duration("-0:00:01.5", "").unwrap(),
+            Some(Duration::milliseconds(-1500))
+        );
+        assert!(parse_duration("1:30", "").is_err());
+        assert!(parse_duration("soon", "").is_err());
+        assert!(parse_duration("1:60:00", "").is_err());
+        assert!(parse_duration("1:-5:00", "").is_err());
+        assert!(parse_duration("--90", "").is_err());
+
+        assert_eq!(format_duration(&Duration::minutes(90)), "1:30:00");
+        assert_eq!(format_duration(&Duration::hours(100)), "100:00:00");
+        assert_eq!(
+            format_duration(&Duration::milliseconds(-1500)),
+            "-0:00:01.5"
+        );
+    }
+
+    #[test]
+    fn test_uuid() {
+        let guid = Uuid::new_v4();
+        assert_eq!(parse_uuid(&guid.to_string(), "").unwrap(), Some(guid));
+        assert!(parse_uuid("not a uuid", "").is_err());
+    }
 }
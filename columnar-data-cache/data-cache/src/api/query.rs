@@ -0,0 +1,377 @@
+use crate::api::cache_error::CacheError;
+use crate::api::chunked::Chunked;
+use crate::api::column_storage::{find_code, ColumnStorage, EnumeratedCode};
+use crate::api::value::Value;
+use std::cmp::Ordering;
+use uuid::Uuid;
+
+/// A filter on a single column. Values are supplied as strings and parsed with the
+/// parser for the column, the same way values are parsed by create_row.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Predicate {
+    /// an empty value matches null cells
+    Equals {
+        column: String,
+        value: String,
+    },
+    /// inclusive on both ends, an empty min or max leaves that end unbounded
+    Range {
+        column: String,
+        min: String,
+        max: String,
+    },
+    In {
+        column: String,
+        values: Vec<String>,
+    },
+    IsNull {
+        column: String,
+    },
+    IsNotNull {
+        column: String,
+    },
+}
+
+impl Predicate {
+    pub fn equals(column: &str, value: &str) -> Self {
+        Predicate::Equals {
+            column: column.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    pub fn range(column: &str, min: &str, max: &str) -> Self {
+        Predicate::Range {
+            column: column.to_string(),
+            min: min.to_string(),
+            max: max.to_string(),
+        }
+    }
+
+    pub fn one_of(column: &str, values: &[&str]) -> Self {
+        Predicate::In {
+            column: column.to_string(),
+            values: values.iter().map(|value| value.to_string()).collect(),
+        }
+    }
+
+    pub fn is_null(column: &str) -> Self {
+        Predicate::IsNull {
+            column: column.to_string(),
+        }
+    }
+
+    pub fn is_not_null(column: &str) -> Self {
+        Predicate::IsNotNull {
+            column: column.to_string(),
+        }
+    }
+
+    pub fn get_column_name(&self) -> &str {
+        match self {
+            Predicate::Equals { column, .. } => column,
+            Predicate::Range { column, .. } => column,
+            Predicate::In { column, .. } => column,
+            Predicate::IsNull { column } => column,
+            Predicate::IsNotNull { column } => column,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+/// Describes which rows to select, how to order them and which columns to return.
+/// Built up with chained calls, for example
+/// Query::new().filter(Predicate::equals("flavor", "vanilla")).sort_by("age", SortDirection::Descending).select(&["name", "age"])
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Query {
+    pub predicates: Vec<Predicate>,
+    pub sort_columns: Vec<(String, SortDirection)>,
+    pub columns: Vec<String>,
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Query::default()
+    }
+
+    /// all predicates must match for a row to be selected
+    pub fn filter(mut self, predicate: Predicate) -> Self {
+        self.predicates.push(predicate);
+        self
+    }
+
+    /// Earlier sort columns take precedence over later ones. Nulls sort as the lowest value, so
+    /// they come before values when ascending and after them when descending.
+    pub fn sort_by(mut self, column: &str, direction: SortDirection) -> Self {
+        self.sort_columns.push((column.to_string(), direction));
+        self
+    }
+
+    /// if no columns are selected then all columns are returned
+    pub fn select(mut self, columns: &[&str]) -> Self {
+        self.columns = columns.iter().map(|column| column.to_string()).collect();
+        self
+    }
+}
+
+/// One row of a query result holding the values of the selected columns in selection order.
+#[derive(Debug, PartialEq, Clone)]
+pub struct QueryRow {
+    pub guid: Uuid,
+    pub values: Vec<Option<Value>>,
+}
+
+/// Iterates over the rows selected by a query. Values are read from the column storage lazily.
+pub struct QueryRows<'a> {
+    column_stores: Vec<&'a ColumnStorage>,
+    guids: &'a Chunked<Uuid>,
+    indices: std::vec::IntoIter<usize>,
+}
+
+impl<'a> QueryRows<'a> {
+    pub(crate) fn new(
+        column_stores: Vec<&'a ColumnStorage>,
+        guids: &'a Chunked<Uuid>,
+        indices: Vec<usize>,
+    ) -> Self {
+        QueryRows {
+            column_stores,
+            guids,
+            indices: indices.into_iter(),
+        }
+    }
+}
+
+impl Iterator for QueryRows<'_> {
+    type Item = QueryRow;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.indices.next()?;
+        Some(QueryRow {
+            guid: self.guids[index],
+            values: self
+                .column_stores
+                .iter()
+                .map(|column_store| column_store.get_value(index))
+                .collect(),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+}
+
+impl ExactSizeIterator for QueryRows<'_> {}
+
+// A predicate with its values parsed by the parser of the column it applies to
+pub(crate) enum Condition {
+    Equals(Option<Value>),
+    Range(Option<Value>, Option<Value>),
+    In(Vec<Option<Value>>),
+    IsNull,
+    IsNotNull,
+}
+
+impl Condition {
+    pub(crate) fn new(
+        predicate: &Predicate,
+        column_store: &ColumnStorage,
+    ) -> Result<Condition, CacheError> {
+        match predicate {
+            Predicate::Equals { value, .. } => {
+                Ok(Condition::Equals(column_store.parse_value(value)?))
+            }
+            Predicate::Range { min, max, .. } => Ok(Condition::Range(
+                column_store.parse_value(min)?,
+                column_store.parse_value(max)?,
+            )),
+            Predicate::In { values, .. } => Ok(Condition::In(
+                values
+                    .iter()
+                    .map(|value| column_store.parse_value(value))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            Predicate::IsNull { .. } => Ok(Condition::IsNull),
+            Predicate::IsNotNull { .. } => Ok(Condition::IsNotNull),
+        }
+    }
+
+    pub(crate) fn matches(&self, value: &Option<Value>) -> bool {
+        match self {
+            Condition::Equals(expected) => value == expected,
+            Condition::Range(min, max) => match value {
+                Some(value) => {
+                    min.as_ref().is_none_or(|min| value >= min)
+                        && max.as_ref().is_none_or(|max| value <= max)
+                }
+                None => false,
+            },
+            Condition::In(allowed) => value.is_some() && allowed.contains(value),
+            Condition::IsNull => value.is_none(),
+            Condition::IsNotNull => value.is_some(),
+        }
+    }
+}
+
+// Filters the rows of one column. Equals and in on an enumerated column are matched on codes
+// so that the values do not have to be looked up for every row.
+pub(crate) enum Filter<'a> {
+    Values(&'a ColumnStorage, Condition),
+    /// a None code matches null cells
+    Codes(&'a ColumnStorage, Vec<Option<EnumeratedCode>>),
+}
+
+impl<'a> Filter<'a> {
+    pub(crate) fn new(
+        predicate: &Predicate,
+        column_store: &'a ColumnStorage,
+    ) -> Result<Filter<'a>, CacheError> {
+        let condition = Condition::new(predicate, column_store)?;
+        let allowed_values = match column_store {
+            ColumnStorage::EnumeratedStorage { allowed_values, .. } => allowed_values,
+            _ => return Ok(Filter::Values(column_store, condition)),
+        };
+
+        // values that are not allowed have no code and can not match any row
+        let to_code = |value: &Option<Value>| match value {
+            Some(Value::Enumerated(value)) => find_code(allowed_values, value).map(Some),
+            _ => None,
+        };
+        let filter = match condition {
+            Condition::Equals(None) => Filter::Codes(column_store, vec![None]),
+            Condition::Equals(value) => {
+                Filter::Codes(column_store, to_code(&value).into_iter().collect())
+            }
+            Condition::In(values) => {
+                Filter::Codes(column_store, values.iter().filter_map(to_code).collect())
+            }
+            condition => Filter::Values(column_store, condition),
+        };
+        Ok(filter)
+    }
+
+    pub(crate) fn matches(&self, index: usize) -> bool {
+        match self {
+            Filter::Values(column_store, condition) => {
+                condition.matches(&column_store.get_value(index))
+            }
+            Filter::Codes(column_store, codes) => codes.contains(&column_store.get_code(index)),
+        }
+    }
+}
+
+// The sort key of one column. Enumerated columns sort on the rank of each code among the
+// allowed values, which gives the same order as sorting the values themselves.
+pub(crate) struct SortKey<'a> {
+    column_store: &'a ColumnStorage,
+    ranks: Option<Vec<i64>>,
+    pub(crate) direction: SortDirection,
+}
+
+impl<'a> SortKey<'a> {
+    pub(crate) fn new(column_store: &'a ColumnStorage, direction: SortDirection) -> Self {
+        let ranks = match column_store {
+            ColumnStorage::EnumeratedStorage { allowed_values, .. } => {
+                let mut codes: Vec<usize> = (0..allowed_values.len()).collect();
+                codes.sort_by(|left, right| allowed_values[*left].cmp(&allowed_values[*right]));
+                let mut ranks = vec![0; allowed_values.len()];
+                for (rank, code) in codes.into_iter().enumerate() {
+                    ranks[code] = rank as i64;
+                }
+                Some(ranks)
+            }
+            _ => None,
+        };
+
+        SortKey {
+            column_store,
+            ranks,
+            direction,
+        }
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<Value> {
+        match &self.ranks {
+            Some(ranks) => self
+                .column_store
+                .get_code(index)
+                .map(|code| Value::I64(ranks[code as usize])),
+            None => self.column_store.get_value(index),
+        }
+    }
+}
+
+pub(crate) fn compare_values(
+    left: &Option<Value>,
+    right: &Option<Value>,
+    direction: SortDirection,
+) -> Ordering {
+    let ordering = left.partial_cmp(right).unwrap_or(Ordering::Equal);
+    match direction {
+        SortDirection::Ascending => ordering,
+        SortDirection::Descending => ordering.reverse(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let query = Query::new()
+            .filter(Predicate::equals("flavor", "vanilla"))
+            .filter(Predicate::range("age", "1", ""))
+            .sort_by("age", SortDirection::Descending)
+            .select(&["name", "age"]);
+
+        assert_eq!(query.predicates.len(), 2);
+        assert_eq!(query.predicates[1].get_column_name(), "age");
+        assert_eq!(
+            query.sort_columns,
+            vec![("age".to_string(), SortDirection::Descending)]
+        );
+        assert_eq!(query.columns, vec!["name".to_string(), "age".to_string()]);
+    }
+
+    #[test]
+    fn test_range_condition() {
+        let condition = Condition::Range(Some(Value::F64(1.0)), None);
+        assert!(condition.matches(&Some(Value::F64(1.0))));
+        assert!(condition.matches(&Some(Value::F64(100.0))));
+        assert!(!condition.matches(&Some(Value::F64(0.5))));
+        assert!(!condition.matches(&None));
+    }
+
+    #[test]
+    fn test_compare_values() {
+        let one = Some(Value::F64(1.0));
+        let two = Some(Value::F64(2.0));
+        assert_eq!(
+            compare_values(&one, &two, SortDirection::Ascending),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_values(&one, &two, SortDirection::Descending),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_values(&None, &one, SortDirection::Ascending),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_values(&None, &one, SortDirection::Descending),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_values(&None, &None, SortDirection::Descending),
+            Ordering::Equal
+        );
+    }
+}
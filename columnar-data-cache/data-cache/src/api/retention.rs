@@ -0,0 +1,158 @@
+use crate::api::cache_error::CacheError;
+use crate::api::chunked::Chunked;
+use crate::api::column_storage::{ColumnStorage, ColumnStorageDataType};
+use chrono::{Duration, NaiveDateTime};
+use uuid::Uuid;
+
+/// Limits the rows kept in a cache using a TimeDate column as the age of each row.
+/// Rows older than max age are expired, then the oldest rows beyond max rows are expired.
+/// Rows with a null time never expire by age but are the first to go when there are too many.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RetentionPolicy {
+    pub(crate) time_column: String,
+    pub(crate) max_rows: Option<usize>,
+    pub(crate) max_age: Option<Duration>,
+}
+
+impl RetentionPolicy {
+    pub fn new(time_column: &str) -> Self {
+        RetentionPolicy {
+            time_column: time_column.to_string(),
+            max_rows: None,
+            max_age: None,
+        }
+    }
+
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = Some(max_rows);
+        self
+    }
+
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn get_time_column(&self) -> &str {
+        &self.time_column
+    }
+}
+
+/// The rows removed by one eviction. A row that is both too old and beyond max rows
+/// is counted as expired by age.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct EvictionReport {
+    pub evicted: Vec<Uuid>,
+    pub expired_by_age: usize,
+    pub expired_by_count: usize,
+}
+
+pub(crate) fn check_time_column(column_store: &ColumnStorage) -> Result<(), CacheError> {
+    match column_store.get_data_type() {
+        ColumnStorageDataType::TimeDate => Ok(()),
+        data_type => Err(CacheError::UnsupportedType(format!("{:?}", data_type))),
+    }
+}
+
+// returns the indices of the expired rows and the report, the guids are in index order
+pub(crate) fn find_expired(
+    policy: &RetentionPolicy,
+    column_store: &ColumnStorage,
+    guids: &Chunked<Uuid>,
+    now: NaiveDateTime,
+) -> (Vec<usize>, EvictionReport) {
+    let times: Vec<Option<NaiveDateTime>> = match column_store {
+        ColumnStorage::TimeDateStorage { data, .. } => data.to_vec(),
+        _ => vec![],
+    };
+
+    let mut expired = vec![false; times.len()];
+    let mut report = EvictionReport::default();
+
+    // an age that reaches back past the earliest time there is cannot expire anything
+    if let Some(cutoff) = policy
+        .max_age
+        .and_then(|max_age| now.checked_sub_signed(max_age))
+    {
+        for (index, time) in times.iter().enumerate() {
+            if time.is_some_and(|time| time < cutoff) {
+                expired[index] = true;
+                report.expired_by_age += 1;
+            }
+        }
+    }
+
+    if let Some(max_rows) = policy.max_rows {
+        let mut remaining: Vec<usize> = (0..times.len()).filter(|index| !expired[*index]).collect();
+        if remaining.len() > max_rows {
+            // oldest first, with nulls before any time
+            remaining.sort_by_key(|index| times[*index]);
+            let excess = remaining.len() - max_rows;
+            for index in &remaining[..excess] {
+                expired[*index] = true;
+            }
+            report.expired_by_count = excess;
+        }
+    }
+
+    let indices: Vec<usize> = (0..times.len()).filter(|index| expired[*index]).collect();
+    report.evicted = indices.iter().map(|index| guids[*index]).collect();
+    (indices, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::column::Column;
+
+    fn create_storage(times: &[&str]) -> ColumnStorage {
+        let mut column_store = ColumnStorage::TimeDateStorage {
+            column: Column::new("time", "Time", ""),
+            data: Chunked::new(),
+            format: "%Y-%m-%d %H:%M:%S".to_string(),
+            column_type: ColumnStorageDataType::TimeDate,
+        };
+        for time in times {
+            column_store.add_value(time).unwrap();
+        }
+        column_store
+    }
+
+    fn time(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_max_age_and_max_rows() {
+        let column_store = create_storage(&[
+            "2020-01-01 10:00:00",
+            "2020-01-01 09:00:00",
+            "",
+            "2020-01-01 11:00:00",
+            "2020-01-01 10:30:00",
+        ]);
+        let guids: Chunked<Uuid> = (0..5).map(|_| Uuid::new_v4()).collect();
+        let policy = RetentionPolicy::new("time")
+            .max_age(Duration::hours(2))
+            .max_rows(2);
+
+        let (indices, report) =
+            find_expired(&policy, &column_store, &guids, time("2020-01-01 11:30:00"));
+        assert_eq!(indices, vec![0, 1, 2]);
+        assert_eq!(report.expired_by_age, 1);
+        assert_eq!(report.expired_by_count, 2);
+        assert_eq!(report.evicted, vec![guids[0], guids[1], guids[2]]);
+    }
+
+    #[test]
+    fn test_nothing_expired() {
+        let column_store = create_storage(&["", "2020-01-01 10:00:00"]);
+        let guids: Chunked<Uuid> = (0..2).map(|_| Uuid::new_v4()).collect();
+        let policy = RetentionPolicy::new("time").max_age(Duration::minutes(1));
+
+        let (indices, report) =
+            find_expired(&policy, &column_store, &guids, time("2020-01-01 10:00:30"));
+        assert!(indices.is_empty());
+        assert_eq!(report, EvictionReport::default());
+    }
+}
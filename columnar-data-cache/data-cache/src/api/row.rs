@@ -0,0 +1,97 @@
+use crate::api::cache_error::CacheError;
+use crate::api::column_storage::ColumnStorageDataType;
+use crate::api::value::Value;
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// A copy of one row of a cache with typed access by column name.
+/// The typed getters return None for null cells and an error when the column holds another type.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Row {
+    pub guid: Uuid,
+    pub columns: Vec<String>,
+    pub values: Vec<Option<Value>>,
+}
+
+macro_rules! typed_getter {
+    ($name:ident, $variant:ident, $data_type:ident, $type:ty) => {
+        pub fn $name(&self, column: &str) -> Result<Option<$type>, CacheError> {
+            match self.get(column)? {
+                Some(Value::$variant(value)) => Ok(Some(value.clone())),
+                Some(value) => {
+                    Err(self.type_mismatch(column, ColumnStorageDataType::$data_type, value))
+                }
+                None => Ok(None),
+            }
+        }
+    };
+}
+
+impl Row {
+    pub fn get(&self, column: &str) -> Result<Option<&Value>, CacheError> {
+        let index = self
+            .columns
+            .iter()
+            .position(|name| name == column)
+            .ok_or_else(|| CacheError::ColumnNotFound(column.to_string()))?;
+        Ok(self.values[index].as_ref())
+    }
+
+    fn type_mismatch(
+        &self,
+        column: &str,
+        expected: ColumnStorageDataType,
+        value: &Value,
+    ) -> CacheError {
+        CacheError::TypeMismatch {
+            column: column.to_string(),
+            expected,
+            actual: value.data_type(),
+        }
+    }
+
+    typed_getter!(get_string, String, String, String);
+    typed_getter!(get_bool, Boolean, Boolean, bool);
+    typed_getter!(get_f64, F64, F64, f64);
+    typed_getter!(get_datetime, TimeDate, TimeDate, NaiveDateTime);
+    typed_getter!(get_enumerated, Enumerated, Enumerated, String);
+    typed_getter!(get_i64, I64, I64, i64);
+    typed_getter!(get_decimal, Decimal, Decimal, Decimal);
+    typed_getter!(get_date, Date, Date, NaiveDate);
+    typed_getter!(get_time, Time, Time, NaiveTime);
+    typed_getter!(get_duration, Duration, Duration, Duration);
+    typed_getter!(get_uuid, Uuid, Uuid, Uuid);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_row() -> Row {
+        Row {
+            guid: Uuid::new_v4(),
+            columns: vec!["name".to_string(), "weight".to_string()],
+            values: vec![Some(Value::String("fred".to_string())), None],
+        }
+    }
+
+    #[test]
+    fn test_typed_getters() {
+        let row = create_row();
+        assert_eq!(row.get_string("name"), Ok(Some("fred".to_string())));
+        assert_eq!(row.get_f64("weight"), Ok(None));
+        assert_eq!(
+            row.get_f64("name"),
+            Err(CacheError::TypeMismatch {
+                column: "name".to_string(),
+                expected: ColumnStorageDataType::F64,
+                actual: ColumnStorageDataType::String,
+            })
+        );
+        assert_eq!(
+            row.get_bool("height"),
+            Err(CacheError::ColumnNotFound("height".to_string()))
+        );
+    }
+}
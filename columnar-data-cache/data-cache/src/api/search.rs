@@ -0,0 +1,199 @@
+//! Case insensitive search over the words of String columns.
+//!
+//! Values are split into tokens at every character that is not alphanumeric and the tokens
+//! are lowercased. An index maps each distinct token to the rows that contain it, kept in
+//! token order so that prefix searches are a range scan. Substring searches scan the distinct
+//! tokens, which are usually far fewer than the rows.
+
+use crate::api::chunked::ChunkedMap;
+use crate::api::value::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// What to search for. The text is split into tokens the same way as the values and a row
+/// matches when every token of the text matches one of its tokens. Text without any tokens
+/// matches nothing.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Search {
+    /// whole tokens, "Fred" matches "fred flintstone" but not "freddie"
+    Token(String),
+    /// the start of tokens, "fr flint" matches "Fred Flintstone"
+    Prefix(String),
+    /// part of a token, "lint" matches "Flintstone"
+    Substring(String),
+}
+
+impl Search {
+    pub fn token(text: &str) -> Self {
+        Search::Token(text.to_string())
+    }
+
+    pub fn prefix(text: &str) -> Self {
+        Search::Prefix(text.to_string())
+    }
+
+    pub fn substring(text: &str) -> Self {
+        Search::Substring(text.to_string())
+    }
+}
+
+/// the distinct lowercased tokens of a value, in the order they first appear
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = vec![];
+    for token in text
+        .split(|character: char| !character.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+    {
+        let token = token.to_lowercase();
+        if !tokens.contains(&token) {
+            tokens.push(token);
+        }
+    }
+    tokens
+}
+
+// the rows of each token are behind an Arc so a copy of the index only copies the token list
+#[derive(Clone, Default)]
+pub(crate) struct SearchIndex {
+    postings: BTreeMap<String, Arc<HashSet<Uuid>>>,
+    // the tokens indexed for each row so that they can be removed again
+    tokens: ChunkedMap<Uuid, Vec<String>>,
+}
+
+impl SearchIndex {
+    /// indexes the value of a row, replacing whatever was indexed for the row before
+    pub(crate) fn insert(&mut self, guid: Uuid, value: &Option<Value>) {
+        self.remove(&guid);
+
+        let tokens = match value {
+            Some(Value::String(text)) => tokenize(text),
+            _ => vec![],
+        };
+        if tokens.is_empty() {
+            return;
+        }
+        for token in &tokens {
+            Arc::make_mut(self.postings.entry(token.clone()).or_default()).insert(guid);
+        }
+        self.tokens.insert(guid, tokens);
+    }
+
+    pub(crate) fn remove(&mut self, guid: &Uuid) {
+        for token in self.tokens.remove(guid).unwrap_or_default() {
+            if let Some(guids) = self.postings.get_mut(&token) {
+                Arc::make_mut(guids).remove(guid);
+                if guids.is_empty() {
+                    self.postings.remove(&token);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn search(&self, search: &Search) -> HashSet<Uuid> {
+        let text = match search {
+            Search::Token(text) | Search::Prefix(text) | Search::Substring(text) => text,
+        };
+
+        let mut found: Option<HashSet<Uuid>> = None;
+        for part in tokenize(text) {
+            let guids = self.find(search, &part);
+            found = Some(match found {
+                Some(found) => found.intersection(&guids).copied().collect(),
+                None => guids,
+            });
+        }
+        found.unwrap_or_default()
+    }
+
+    // the rows with any token that matches the part
+    fn find(&self, search: &Search, part: &str) -> HashSet<Uuid> {
+        let mut guids = HashSet::new();
+        match search {
+            Search::Token(_) => {
+                if let Some(found) = self.postings.get(part) {
+                    guids.extend(found.iter());
+                }
+            }
+            Search::Prefix(_) => {
+                for (_, found) in self
+                    .postings
+                    .range(part.to_string()..)
+                    .take_while(|(token, _)| token.starts_with(part))
+                {
+                    guids.extend(found.iter());
+                }
+            }
+            Search::Substring(_) => {
+                for (token, found) in &self.postings {
+                    if token.contains(part) {
+                        guids.extend(found.iter());
+                    }
+                }
+            }
+        }
+        guids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(value: &str) -> Option<Value> {
+        Some(Value::String(value.to_string()))
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("Fred-Flintstone, fred  ÉLAN"),
+            vec!["fred", "flintstone", "élan"]
+        );
+        assert!(tokenize(" ,. ").is_empty());
+    }
+
+    #[test]
+    fn test_search() {
+        let mut index = SearchIndex::default();
+        let fred = Uuid::new_v4();
+        let freddie = Uuid::new_v4();
+        let wilma = Uuid::new_v4();
+        index.insert(fred, &string("Fred Flintstone"));
+        index.insert(freddie, &string("Freddie Mercury"));
+        index.insert(wilma, &string("Wilma Flintstone"));
+
+        assert_eq!(index.search(&Search::token("FRED")), HashSet::from([fred]));
+        assert_eq!(
+            index.search(&Search::prefix("fre")),
+            HashSet::from([fred, freddie])
+        );
+        assert_eq!(
+            index.search(&Search::prefix("fr flint")),
+            HashSet::from([fred])
+        );
+        assert_eq!(
+            index.search(&Search::substring("lint")),
+            HashSet::from([fred, wilma])
+        );
+        assert!(index.search(&Search::substring("")).is_empty());
+    }
+
+    #[test]
+    fn test_replace_and_remove() {
+        let mut index = SearchIndex::default();
+        let guid = Uuid::new_v4();
+        index.insert(guid, &string("fred"));
+        index.insert(guid, &string("barney"));
+        assert!(index.search(&Search::token("fred")).is_empty());
+        assert_eq!(
+            index.search(&Search::token("barney")),
+            HashSet::from([guid])
+        );
+
+        index.remove(&guid);
+        assert!(index.search(&Search::token("barney")).is_empty());
+        assert!(index.postings.is_empty());
+        assert!(index.tokens.get(&guid).is_none());
+    }
+}
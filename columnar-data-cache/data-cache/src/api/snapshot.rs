@@ -0,0 +1,531 @@
+//! Binary snapshot format for a cache. All numbers are little endian.
+//!
+//! header:  magic "DCSNAP", u32 version, u64 row count, u32 column count
+//! column:  u8 data type, name, display name, default value, format, allowed values,
+//!          u32 decimal scale, null bitmap with one bit per row (set when the row has a value),
+//!          then the typed values of the rows that have a value, enumerated values are u16 codes
+//!          into the allowed values
+//! guids:   16 bytes per row
+//!
+//! Strings are a u32 byte length followed by utf8 bytes. Decimals are their 16 byte serialized
+//! form, dates are i32 days from the common era, times and durations are i64 seconds and u32
+//! nanoseconds.
+
+use crate::api::cache_error::CacheError;
+use crate::api::chunked::Chunked;
+use crate::api::column::Column;
+use crate::api::column_storage::{check_allowed_values, ColumnStorage, ColumnStorageDataType};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use rust_decimal::Decimal;
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use uuid::Uuid;
+
+const MAGIC: &[u8; 6] = b"DCSNAP";
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+fn data_type_to_tag(data_type: &ColumnStorageDataType) -> u8 {
+    match data_type {
+        ColumnStorageDataType::String => 0,
+        ColumnStorageDataType::Boolean => 1,
+        ColumnStorageDataType::F64 => 2,
+        ColumnStorageDataType::TimeDate => 3,
+        ColumnStorageDataType::Enumerated => 4,
+        ColumnStorageDataType::I64 => 5,
+        ColumnStorageDataType::Decimal => 6,
+        ColumnStorageDataType::Date => 7,
+        ColumnStorageDataType::Time => 8,
+        ColumnStorageDataType::Duration => 9,
+        ColumnStorageDataType::Uuid => 10,
+    }
+}
+
+fn tag_to_data_type(tag: u8) -> Result<ColumnStorageDataType, CacheError> {
+    match tag {
+        0 => Ok(ColumnStorageDataType::String),
+        1 => Ok(ColumnStorageDataType::Boolean),
+        2 => Ok(ColumnStorageDataType::F64),
+        3 => Ok(ColumnStorageDataType::TimeDate),
+        4 => Ok(ColumnStorageDataType::Enumerated),
+        5 => Ok(ColumnStorageDataType::I64),
+        6 => Ok(ColumnStorageDataType::Decimal),
+        7 => Ok(ColumnStorageDataType::Date),
+        8 => Ok(ColumnStorageDataType::Time),
+        9 => Ok(ColumnStorageDataType::Duration),
+        10 => Ok(ColumnStorageDataType::Uuid),
+        _ => Err(CacheError::InvalidSnapshot(format!(
+            "unknown data type {}",
+            tag
+        ))),
+    }
+}
+
+fn write_u8<W: Write>(writer: &mut W, value: u8) -> Result<(), CacheError> {
+    writer.write_all(&[value])?;
+    Ok(())
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: u32) -> Result<(), CacheError> {
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_u64<W: Write>(writer: &mut W, value: u64) -> Result<(), CacheError> {
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_string<W: Write>(writer: &mut W, value: &str) -> Result<(), CacheError> {
+    write_u32(writer, value.len() as u32)?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+fn write_time_date<W: Write>(writer: &mut W, value: &NaiveDateTime) -> Result<(), CacheError> {
+    let utc = value.and_utc();
+    writer.write_all(&utc.timestamp().to_le_bytes())?;
+    write_u32(writer, utc.timestamp_subsec_nanos())
+}
+
+fn write_i64<W: Write>(writer: &mut W, value: i64) -> Result<(), CacheError> {
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_time<W: Write>(writer: &mut W, value: &NaiveTime) -> Result<(), CacheError> {
+    write_i64(writer, value.num_seconds_from_midnight() as i64)?;
+    write_u32(writer, value.nanosecond())
+}
+
+fn write_duration<W: Write>(writer: &mut W, value: &Duration) -> Result<(), CacheError> {
+    write_i64(writer, value.num_seconds())?;
+    write_u32(writer, value.subsec_nanos() as u32)
+}
+
+fn read_bytes<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N], CacheError> {
+    let mut buffer = [0u8; N];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> Result<u8, CacheError> {
+    Ok(read_bytes::<R, 1>(reader)?[0])
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, CacheError> {
+    Ok(u32::from_le_bytes(read_bytes(reader)?))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, CacheError> {
+    Ok(u64::from_le_bytes(read_bytes(reader)?))
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String, CacheError> {
+    let length = read_u32(reader)? as usize;
+    // read through take so a corrupt length can not allocate more than the file holds
+    let mut buffer = vec![];
+    reader.take(length as u64).read_to_end(&mut buffer)?;
+    if buffer.len() != length {
+        return Err(CacheError::InvalidSnapshot("truncated string".to_string()));
+    }
+    String::from_utf8(buffer).map_err(|error| CacheError::InvalidSnapshot(error.to_string()))
+}
+
+fn read_time_date<R: Read>(reader: &mut R) -> Result<NaiveDateTime, CacheError> {
+    let seconds = i64::from_le_bytes(read_bytes(reader)?);
+    let nanoseconds = read_u32(reader)?;
+    DateTime::from_timestamp(seconds, nanoseconds)
+        .map(|date_time| date_time.naive_utc())
+        .ok_or_else(|| CacheError::InvalidSnapshot("invalid time date".to_string()))
+}
+
+fn read_i64<R: Read>(reader: &mut R) -> Result<i64, CacheError> {
+    Ok(i64::from_le_bytes(read_bytes(reader)?))
+}
+
+fn read_date<R: Read>(reader: &mut R) -> Result<NaiveDate, CacheError> {
+    let days = i32::from_le_bytes(read_bytes(reader)?);
+    NaiveDate::from_num_days_from_ce_opt(days)
+        .ok_or_else(|| CacheError::InvalidSnapshot("invalid date".to_string()))
+}
+
+fn read_time<R: Read>(reader: &mut R) -> Result<NaiveTime, CacheError> {
+    let seconds = read_i64(reader)?;
+    let nanoseconds = read_u32(reader)?;
+    u32::try_from(seconds)
+        .ok()
+        .and_then(|seconds| NaiveTime::from_num_seconds_from_midnight_opt(seconds, nanoseconds))
+        .ok_or_else(|| CacheError::InvalidSnapshot("invalid time".to_string()))
+}
+
+fn read_duration<R: Read>(reader: &mut R) -> Result<Duration, CacheError> {
+    let seconds = read_i64(reader)?;
+    let nanoseconds = read_u32(reader)?;
+    // the nanoseconds carry the sign of the seconds, as returned by subsec_nanos
+    Duration::try_seconds(seconds)
+        .and_then(|duration| {
+            duration.checked_add(&Duration::nanoseconds(nanoseconds as i32 as i64))
+        })
+        .ok_or_else(|| CacheError::InvalidSnapshot("invalid duration".to_string()))
+}
+
+fn write_data<W: Write, T>(
+    writer: &mut W,
+    data: &Chunked<Option<T>>,
+    write_value: impl Fn(&mut W, &T) -> Result<(), CacheError>,
+) -> Result<(), CacheError> {
+    let mut bitmap = vec![0u8; data.len().div_ceil(8)];
+    for (index, value) in data.iter().enumerate() {
+        if value.is_some() {
+            bitmap[index / 8] |= 1 << (index % 8);
+        }
+    }
+    writer.write_all(&bitmap)?;
+
+    for value in data.iter().flatten() {
+        write_value(writer, value)?;
+    }
+    Ok(())
+}
+
+fn read_data<R: Read, T: Clone>(
+    reader: &mut R,
+    row_count: usize,
+    read_value: impl Fn(&mut R) -> Result<T, CacheError>,
+) -> Result<Chunked<Option<T>>, CacheError> {
+    let mut bitmap = vec![];
+    let bitmap_length = row_count.div_ceil(8);
+    reader.take(bitmap_length as u64).read_to_end(&mut bitmap)?;
+    if bitmap.len() != bitmap_length {
+        return Err(CacheError::InvalidSnapshot("truncated bitmap".to_string()));
+    }
+
+    let mut data = Chunked::new();
+    for index in 0..row_count {
+        if bitmap[index / 8] & (1 << (index % 8)) != 0 {
+            data.push(Some(read_value(reader)?));
+        } else {
+            data.push(None);
+        }
+    }
+    Ok(data)
+}
+
+fn write_column<W: Write>(writer: &mut W, column_store: &ColumnStorage) -> Result<(), CacheError> {
+    let column = column_store.get_column();
+    write_u8(writer, data_type_to_tag(&column_store.get_data_type()))?;
+    write_string(writer, &column.name)?;
+    write_string(writer, &column.display_name)?;
+    write_string(writer, &column.default_value)?;
+    write_string(writer, &column_store.get_format())?;
+
+    let allowed_values = column_store.get_allowed_values();
+    write_u32(writer, allowed_values.len() as u32)?;
+    for allowed_value in &allowed_values {
+        write_string(writer, allowed_value)?;
+    }
+    write_u32(writer, column_store.get_scale())?;
+
+    match column_store {
+        ColumnStorage::StringStorage { data, .. } => {
+            write_data(writer, data, |writer, value| write_string(writer, value))
+        }
+        ColumnStorage::BooleanStorage { data, .. } => {
+            write_data(writer, data, |writer, value| write_u8(writer, *value as u8))
+        }
+        ColumnStorage::F64Storage { data, .. } => write_data(writer, data, |writer, value| {
+            writer.write_all(&value.to_le_bytes())?;
+            Ok(())
+        }),
+        ColumnStorage::TimeDateStorage { data, .. } => write_data(writer, data, write_time_date),
+        ColumnStorage::EnumeratedStorage { data, .. } => {
+            write_data(writer, data, |writer, code| {
+                writer.write_all(&code.to_le_bytes())?;
+                Ok(())
+            })
+        }
+        ColumnStorage::I64Storage { data, .. } => {
+            write_data(writer, data, |writer, value| write_i64(writer, *value))
+        }
+        ColumnStorage::DecimalStorage { data, .. } => write_data(writer, data, |writer, value| {
+            writer.write_all(&value.serialize())?;
+            Ok(())
+        }),
+        ColumnStorage::DateStorage { data, .. } => write_data(writer, data, |writer, value| {
+            writer.write_all(&value.num_days_from_ce().to_le_bytes())?;
+            Ok(())
+        }),
+        ColumnStorage::TimeStorage { data, .. } => write_data(writer, data, write_time),
+        ColumnStorage::DurationStorage { data, .. } => write_data(writer, data, write_duration),
+        ColumnStorage::UuidStorage { data, .. } => write_data(writer, data, |writer, value| {
+            writer.write_all(value.as_bytes())?;
+            Ok(())
+        }),
+    }
+}
+
+fn read_column<R: Read>(reader: &mut R, row_count: usize) -> Result<ColumnStorage, CacheError> {
+    let column_type = tag_to_data_type(read_u8(reader)?)?;
+    let name = read_string(reader)?;
+    let display_name = read_string(reader)?;
+    let default_value = read_string(reader)?;
+    let format = read_string(reader)?;
+
+    let allowed_value_count = read_u32(reader)?;
+    let mut allowed_values = vec![];
+    for _ in 0..allowed_value_count {
+        allowed_values.push(read_string(reader)?);
+    }
+    let scale = read_u32(reader)?;
+    if scale > Decimal::MAX_SCALE {
+        return Err(CacheError::InvalidSnapshot(format!(
+            "invalid decimal scale {}",
+            scale
+        )));
+    }
+
+    let column = Column::new(&name, &display_name, &default_value);
+    let column_store = match column_type {
+        ColumnStorageDataType::String => ColumnStorage::StringStorage {
+            column,
+            data: read_data(reader, row_count, read_string)?,
+            column_type,
+        },
+        ColumnStorageDataType::Boolean => ColumnStorage::BooleanStorage {
+            column,
+            data: read_data(reader, row_count, |reader| Ok(read_u8(reader)? != 0))?,
+            column_type,
+        },
+        ColumnStorageDataType::F64 => ColumnStorage::F64Storage {
+            column,
+            data: read_data(reader, row_count, |reader| {
+                Ok(f64::from_le_bytes(read_bytes(reader)?))
+            })?,
+            column_type,
+        },
+        ColumnStorageDataType::TimeDate => ColumnStorage::TimeDateStorage {
+            column,
+            data: read_data(reader, row_count, read_time_date)?,
+            format,
+            column_type,
+        },
+        ColumnStorageDataType::Enumerated => {
+            check_allowed_values(&allowed_values)?;
+            let data = read_data(reader, row_count, |reader| {
+                let code = u16::from_le_bytes(read_bytes(reader)?);
+                if code as usize >= allowed_values.len() {
+                    return Err(CacheError::InvalidSnapshot(format!(
+                        "invalid code {}",
+                        code
+                    )));
+                }
+                Ok(code)
+            })?;
+
+            ColumnStorage::EnumeratedStorage {
+                column,
+                data,
+                allowed_values,
+                column_type,
+            }
+        }
+        ColumnStorageDataType::I64 => ColumnStorage::I64Storage {
+            column,
+            data: read_data(reader, row_count, read_i64)?,
+            column_type,
+        },
+        ColumnStorageDataType::Decimal => ColumnStorage::DecimalStorage {
+            column,
+            data: read_data(reader, row_count, |reader| {
+                Ok(Decimal::deserialize(read_bytes(reader)?))
+            })?,
+            scale,
+            column_type,
+        },
+        ColumnStorageDataType::Date => ColumnStorage::DateStorage {
+            column,
+            data: read_data(reader, row_count, read_date)?,
+            format,
+            column_type,
+        },
+        ColumnStorageDataType::Time => ColumnStorage::TimeStorage {
+            column,
+            data: read_data(reader, row_count, read_time)?,
+            format,
+            column_type,
+        },
+        ColumnStorageDataType::Duration => ColumnStorage::DurationStorage {
+            column,
+            data: read_data(reader, row_count, read_duration)?,
+            column_type,
+        },
+        ColumnStorageDataType::Uuid => ColumnStorage::UuidStorage {
+            column,
+            data: read_data(reader, row_count, |reader| {
+                Ok(Uuid::from_bytes(read_bytes(reader)?))
+            })?,
+            column_type,
+        },
+    };
+
+    Ok(column_store)
+}
+
+pub(crate) fn write_snapshot<W: Write>(
+    writer: &mut W,
+    column_stores: &[ColumnStorage],
+    guids: &Chunked<Uuid>,
+) -> Result<(), CacheError> {
+    writer.write_all(MAGIC)?;
+    write_u32(writer, SNAPSHOT_VERSION)?;
+    write_u64(writer, guids.len() as u64)?;
+    write_u32(writer, column_stores.len() as u32)?;
+
+    for column_store in column_stores {
+        write_column(writer, column_store)?;
+    }
+
+    for guid in guids {
+        writer.write_all(guid.as_bytes())?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+pub(crate) fn read_snapshot<R: Read>(
+    reader: &mut R,
+) -> Result<(Vec<ColumnStorage>, Vec<Uuid>), CacheError> {
+    let magic: [u8; 6] = read_bytes(reader)?;
+    if &magic != MAGIC {
+        return Err(CacheError::InvalidSnapshot("not a snapshot".to_string()));
+    }
+
+    let version = read_u32(reader)?;
+    if version != SNAPSHOT_VERSION {
+        return Err(CacheError::InvalidSnapshot(format!(
+            "unsupported version {}",
+            version
+        )));
+    }
+
+    let row_count = read_u64(reader)? as usize;
+    let column_count = read_u32(reader)?;
+
+    let mut column_stores: Vec<ColumnStorage> = vec![];
+    for _ in 0..column_count {
+        let column_store = read_column(reader, row_count)?;
+        let name = &column_store.get_column().name;
+        if column_stores
+            .iter()
+            .any(|existing| existing.get_column().name == *name)
+        {
+            return Err(CacheError::InvalidSnapshot(format!(
+                "duplicate column {}",
+                name
+            )));
+        }
+        column_stores.push(column_store);
+    }
+
+    // the cache finds rows by guid so each guid must be unique
+    let mut guids = vec![];
+    let mut seen = HashSet::new();
+    for _ in 0..row_count {
+        let guid = Uuid::from_bytes(read_bytes(reader)?);
+        if !seen.insert(guid) {
+            return Err(CacheError::InvalidSnapshot(format!(
+                "duplicate guid {}",
+                guid
+            )));
+        }
+        guids.push(guid);
+    }
+
+    Ok((column_stores, guids))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strings() {
+        let mut buffer = vec![];
+        write_string(&mut buffer, "hello").unwrap();
+        write_string(&mut buffer, "").unwrap();
+
+        let mut reader = buffer.as_slice();
+        assert_eq!(read_string(&mut reader).unwrap(), "hello");
+        assert_eq!(read_string(&mut reader).unwrap(), "");
+        assert!(read_string(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_data_with_nulls() {
+        let data: Chunked<Option<f64>> = Chunked::from(vec![
+            Some(1.5),
+            None,
+            None,
+            Some(-2.0),
+            None,
+            None,
+            None,
+            None,
+            Some(3.0),
+        ]);
+        let mut buffer = vec![];
+        write_data(&mut buffer, &data, |writer, value: &f64| {
+            writer.write_all(&value.to_le_bytes())?;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(buffer.len(), 2 + 3 * 8);
+
+        let read = read_data(&mut buffer.as_slice(), data.len(), |reader| {
+            Ok(f64::from_le_bytes(read_bytes(reader)?))
+        })
+        .unwrap();
+        assert_eq!(read, data);
+    }
+
+    #[test]
+    fn test_decimal_scale_is_checked() {
+        let mut buffer = vec![];
+        write_u8(
+            &mut buffer,
+            data_type_to_tag(&ColumnStorageDataType::Decimal),
+        )
+        .unwrap();
+        for value in ["price", "Price", "", ""] {
+            write_string(&mut buffer, value).unwrap();
+        }
+        write_u32(&mut buffer, 0).unwrap();
+        write_u32(&mut buffer, Decimal::MAX_SCALE + 1).unwrap();
+
+        assert_eq!(
+            read_column(&mut buffer.as_slice(), 0).err(),
+            Some(CacheError::InvalidSnapshot(
+                "invalid decimal scale 29".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_durations() {
+        let durations = [
+            Duration::seconds(3661),
+            Duration::milliseconds(-1500),
+            Duration::zero(),
+        ];
+        let mut buffer = vec![];
+        for duration in &durations {
+            write_duration(&mut buffer, duration).unwrap();
+        }
+
+        let mut reader = buffer.as_slice();
+        for duration in &durations {
+            assert_eq!(read_duration(&mut reader).unwrap(), *duration);
+        }
+    }
+}
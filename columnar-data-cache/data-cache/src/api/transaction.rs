@@ -0,0 +1,331 @@
+//! Transactions group row edits so that they are applied or rolled back together.
+//!
+//! Every edit is recorded with the values before and after it, so that a committed
+//! transaction can be undone and redone. Undo and redo restore the recorded values without
+//! parsing them again, and removed rows go back to the position they had. The restored rows
+//! are validated, an undo or redo that would break a validator fails with InvalidRow.
+
+use crate::api::cache::Cache;
+use crate::api::cache_error::CacheError;
+use crate::api::csv_loader;
+use crate::api::row::Row;
+use crate::api::value::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// One row edit with enough recorded to reverse it.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Edit {
+    Inserted {
+        guid: Uuid,
+        index: usize,
+        values: Vec<Option<Value>>,
+    },
+    Updated {
+        guid: Uuid,
+        before: Vec<Option<Value>>,
+        after: Vec<Option<Value>>,
+    },
+    Removed {
+        guid: Uuid,
+        index: usize,
+        values: Vec<Option<Value>>,
+    },
+}
+
+impl Edit {
+    fn reverse(&self) -> Edit {
+        match self.clone() {
+            Edit::Inserted {
+                guid,
+                index,
+                values,
+            } => Edit::Removed {
+                guid,
+                index,
+                values,
+            },
+            Edit::Updated {
+                guid,
+                before,
+                after,
+            } => Edit::Updated {
+                guid,
+                before: after,
+                after: before,
+            },
+            Edit::Removed {
+                guid,
+                index,
+                values,
+            } => Edit::Inserted {
+                guid,
+                index,
+                values,
+            },
+        }
+    }
+}
+
+/// The committed transactions that can be undone and the undone ones that can be redone.
+/// Each transaction is behind an Arc so copies of a cache share them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct History {
+    undo: Vec<Arc<Vec<Edit>>>,
+    redo: Vec<Arc<Vec<Edit>>>,
+}
+
+impl History {
+    // a new transaction replaces whatever could have been redone
+    pub(crate) fn commit(&mut self, edits: Vec<Edit>) {
+        if !edits.is_empty() {
+            self.undo.push(Arc::new(edits));
+            self.redo.clear();
+        }
+    }
+
+    pub(crate) fn pop_undo(&mut self) -> Option<Vec<Edit>> {
+        self.undo.pop().map(Arc::unwrap_or_clone)
+    }
+
+    pub(crate) fn push_undo(&mut self, edits: Vec<Edit>) {
+        self.undo.push(Arc::new(edits));
+    }
+
+    pub(crate) fn pop_redo(&mut self) -> Option<Vec<Edit>> {
+        self.redo.pop().map(Arc::unwrap_or_clone)
+    }
+
+    pub(crate) fn push_redo(&mut self, edits: Vec<Edit>) {
+        self.redo.push(Arc::new(edits));
+    }
+
+    pub(crate) fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub(crate) fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+}
+
+/// The edits of a transaction in progress, see Cache::transaction.
+pub struct Transaction<'a> {
+    cache: &'a mut Cache,
+    edits: Vec<Edit>,
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) fn new(cache: &'a mut Cache) -> Self {
+        Transaction {
+            cache,
+            edits: vec![],
+        }
+    }
+
+    /// the cache as it is with the edits made so far
+    pub fn cache(&self) -> &Cache {
+        self.cache
+    }
+
+    pub fn get_row(&self, guid: &Uuid) -> Result<Row, CacheError> {
+        self.cache.get_row(guid)
+    }
+
+    pub fn create_row(&mut self, row: &str) -> Result<Uuid, CacheError> {
+        let values = csv_loader::parse_row(row)?;
+        let values: Vec<&str> = values.iter().map(String::as_str).collect();
+        self.create_row_from_values(&values)
+    }
+
+    pub fn create_row_from_values(&mut self, values: &[&str]) -> Result<Uuid, CacheError> {
+        let guid = self.cache.create_row_from_values(values)?;
+        let index = self.cache.find_index(&guid)?;
+        self.edits.push(Edit::Inserted {
+            guid,
+            index,
+            values: self.cache.row_values(index),
+        });
+        Ok(guid)
+    }
+
+    pub fn update_row(&mut self, guid: &Uuid, row: &str) -> Result<Uuid, CacheError> {
+        let values = csv_loader::parse_row(row)?;
+        let values: Vec<&str> = values.iter().map(String::as_str).collect();
+        self.update_row_from_values(guid, &values)
+    }
+
+    pub fn update_row_from_values(
+        &mut self,
+        guid: &Uuid,
+        values: &[&str],
+    ) -> Result<Uuid, CacheError> {
+        let index = self.cache.find_index(guid)?;
+        let before = self.cache.row_values(index);
+        self.cache.update_row_from_values(guid, values)?;
+        self.edits.push(Edit::Updated {
+            guid: *guid,
+            before,
+            after: self.cache.row_values(index),
+        });
+        Ok(*guid)
+    }
+
+    pub fn delete_row(&mut self, guid: &Uuid) -> Result<(), CacheError> {
+        let (index, values) = self.cache.remove_row(guid)?;
+        self.edits.push(Edit::Removed {
+            guid: *guid,
+            index,
+            values,
+        });
+        Ok(())
+    }
+
+    // applies a recorded edit and records it again with the values actually replaced
+    fn apply(&mut self, edit: &Edit) -> Result<(), CacheError> {
+        let applied = match edit {
+            Edit::Inserted {
+                guid,
+                index,
+                values,
+            } => Edit::Inserted {
+                guid: *guid,
+                index: self.cache.insert_row(guid, values, *index)?,
+                values: values.clone(),
+            },
+            Edit::Updated { guid, after, .. } => Edit::Updated {
+                guid: *guid,
+                before: self.cache.replace_row(guid, after)?,
+                after: after.clone(),
+            },
+            Edit::Removed { guid, .. } => {
+                let (index, values) = self.cache.remove_row(guid)?;
+                Edit::Removed {
+                    guid: *guid,
+                    index,
+                    values,
+                }
+            }
+        };
+        self.edits.push(applied);
+        Ok(())
+    }
+
+    /// Reverses the given edits, last edit first. If one of them can not be reversed, because
+    /// the row has since been removed for example, the cache is left as it was.
+    pub(crate) fn revert(&mut self, edits: &[Edit]) -> Result<(), CacheError> {
+        for edit in edits.iter().rev() {
+            if let Err(error) = self.apply(&edit.reverse()) {
+                return Err(self.abort(error));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rolls back after an edit failed with the error. The error is returned as it is unless
+    /// the rollback fails as well, then both are returned in RollbackFailed.
+    pub(crate) fn abort(&mut self, error: CacheError) -> CacheError {
+        match self.rollback() {
+            Ok(()) => error,
+            Err(rollback_error) => CacheError::RollbackFailed {
+                error: Box::new(error),
+                rollback_error: Box::new(rollback_error),
+            },
+        }
+    }
+
+    /// reverses every edit made in this transaction
+    pub(crate) fn rollback(&mut self) -> Result<(), CacheError> {
+        let edits = std::mem::take(&mut self.edits);
+        for edit in edits.iter().rev() {
+            self.apply(&edit.reverse())?;
+        }
+        self.edits.clear();
+        Ok(())
+    }
+
+    pub(crate) fn into_edits(self) -> Vec<Edit> {
+        self.edits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reverse() {
+        let guid = Uuid::new_v4();
+        let values = vec![Some(Value::I64(1)), None];
+        let inserted = Edit::Inserted {
+            guid,
+            index: 3,
+            values: values.clone(),
+        };
+        assert_eq!(
+            inserted.reverse(),
+            Edit::Removed {
+                guid,
+                index: 3,
+                values
+            }
+        );
+        assert_eq!(inserted.reverse().reverse(), inserted);
+
+        let updated = Edit::Updated {
+            guid,
+            before: vec![Some(Value::I64(1))],
+            after: vec![Some(Value::I64(2))],
+        };
+        assert_eq!(
+            updated.reverse(),
+            Edit::Updated {
+                guid,
+                before: vec![Some(Value::I64(2))],
+                after: vec![Some(Value::I64(1))],
+            }
+        );
+    }
+
+    #[test]
+    fn test_commit_clears_redo() {
+        let mut history = History::default();
+        history.commit(vec![]);
+        assert_eq!(history.undo_len(), 0);
+
+        history.redo.push(Arc::new(vec![]));
+        history.commit(vec![Edit::Removed {
+            guid: Uuid::new_v4(),
+            index: 0,
+            values: vec![],
+        }]);
+        assert_eq!(history.undo_len(), 1);
+        assert_eq!(history.redo_len(), 0);
+    }
+
+    #[test]
+    fn test_abort_keeps_both_errors() {
+        let mut cache = Cache::new();
+        cache.add_string_column("name", "Name", "").unwrap();
+        let mut transaction = Transaction::new(&mut cache);
+        let guid = transaction.create_row("fred").unwrap();
+        let missing = Uuid::new_v4();
+
+        // nothing to roll back yet so the error is returned as it is
+        let mut empty = Transaction::new(transaction.cache);
+        assert_eq!(
+            empty.abort(CacheError::GuidNotFound(missing)),
+            CacheError::GuidNotFound(missing)
+        );
+
+        // a row removed behind the transaction's back can not be rolled back
+        transaction.cache.delete_row(&guid).unwrap();
+        assert_eq!(
+            transaction.abort(CacheError::GuidNotFound(missing)),
+            CacheError::RollbackFailed {
+                error: Box::new(CacheError::GuidNotFound(missing)),
+                rollback_error: Box::new(CacheError::GuidNotFound(guid)),
+            }
+        );
+    }
+}
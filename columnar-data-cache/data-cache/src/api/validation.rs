@@ -0,0 +1,474 @@
+//! Column constraints that are checked when a row is created or updated.
+//!
+//! Validators are attached to a column with Cache::add_column or Cache::add_validator.
+//! A value is only validated after it has been parsed, so validators see typed values.
+//! Except for Required, validators accept nulls the way SQL constraints do.
+
+use crate::api::cache_error::CacheError;
+use crate::api::chunked::ChunkedMap;
+use crate::api::column_storage::{ColumnStorage, ColumnStorageDataType};
+use crate::api::value::Value;
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// The column and rows a value is validated against.
+pub struct ValidationContext<'a> {
+    pub(crate) column_store: &'a ColumnStorage,
+    pub(crate) row_index: usize,
+    pub(crate) replacing: Option<usize>,
+    // the counts of the values of every row but the new one, when a validator asked for them
+    pub(crate) value_counts: Option<&'a ValueCounts>,
+}
+
+impl ValidationContext<'_> {
+    pub fn column_store(&self) -> &ColumnStorage {
+        self.column_store
+    }
+
+    /// the values of the other rows, leaving out the new row and the row it replaces
+    pub fn other_values(&self) -> impl Iterator<Item = Option<Value>> + '_ {
+        (0..self.column_store.get_length())
+            .filter(|index| *index != self.row_index && Some(*index) != self.replacing)
+            .map(|index| self.column_store.get_value(index))
+    }
+
+    /// How many of the other rows hold the value. This is a lookup when a validator of the
+    /// column has counts_values set, otherwise the other values are scanned.
+    pub fn count_other(&self, value: &Value) -> usize {
+        let counts = match self.value_counts {
+            Some(counts) => counts,
+            None => {
+                return self
+                    .other_values()
+                    .filter(|other| other.as_ref() == Some(value))
+                    .count()
+            }
+        };
+
+        let key = match ValueKey::new(value) {
+            Some(key) => key,
+            None => return 0,
+        };
+        let replaced = self.replacing.is_some_and(|index| {
+            self.column_store
+                .get_value(index)
+                .is_some_and(|replaced| ValueKey::new(&replaced).as_ref() == Some(&key))
+        });
+        counts.count(&key) - replaced as usize
+    }
+}
+
+/// A value that can be hashed and is equal to another key exactly when the values are equal,
+/// so -0.0 and 0.0 share a key. NaN is not equal to any value, NaN included, so it has no key.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum ValueKey {
+    String(String),
+    Boolean(bool),
+    F64(u64),
+    TimeDate(NaiveDateTime),
+    Enumerated(String),
+    I64(i64),
+    Decimal(Decimal),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Duration(Duration),
+    Uuid(Uuid),
+}
+
+impl ValueKey {
+    fn new(value: &Value) -> Option<Self> {
+        let key = match value {
+            Value::String(value) => ValueKey::String(value.clone()),
+            Value::Boolean(value) => ValueKey::Boolean(*value),
+            Value::F64(value) if value.is_nan() => return None,
+            Value::F64(value) if *value == 0.0 => ValueKey::F64(0.0_f64.to_bits()),
+            Value::F64(value) => ValueKey::F64(value.to_bits()),
+            Value::TimeDate(value) => ValueKey::TimeDate(*value),
+            Value::Enumerated(value) => ValueKey::Enumerated(value.clone()),
+            Value::I64(value) => ValueKey::I64(*value),
+            // equal decimals can have different scales, 1.0 and 1.00
+            Value::Decimal(value) => ValueKey::Decimal(value.normalize()),
+            Value::Date(value) => ValueKey::Date(*value),
+            Value::Time(value) => ValueKey::Time(*value),
+            Value::Duration(value) => ValueKey::Duration(*value),
+            Value::Uuid(value) => ValueKey::Uuid(*value),
+        };
+        Some(key)
+    }
+}
+
+/// The number of rows that hold each value of a column, nulls and NaN are not counted.
+#[derive(Clone, Default)]
+pub(crate) struct ValueCounts {
+    counts: ChunkedMap<ValueKey, usize>,
+}
+
+impl ValueCounts {
+    pub(crate) fn new(column_store: &ColumnStorage) -> Self {
+        let mut value_counts = ValueCounts::default();
+        for index in 0..column_store.get_length() {
+            value_counts.add(&column_store.get_value(index));
+        }
+        value_counts
+    }
+
+    pub(crate) fn add(&mut self, value: &Option<Value>) {
+        if let Some(key) = value.as_ref().and_then(ValueKey::new) {
+            match self.counts.get_mut(&key) {
+                Some(count) => *count += 1,
+                None => {
+                    self.counts.insert(key, 1);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, value: &Option<Value>) {
+        if let Some(key) = value.as_ref().and_then(ValueKey::new) {
+            if self.counts.get(&key) == Some(&1) {
+                self.counts.remove(&key);
+            } else if let Some(count) = self.counts.get_mut(&key) {
+                *count -= 1;
+            }
+        }
+    }
+
+    fn count(&self, key: &ValueKey) -> usize {
+        self.counts.get(key).copied().unwrap_or(0)
+    }
+}
+
+pub trait Validator: Send + Sync {
+    /// returns the reason when the value is rejected
+    fn validate(&self, value: &Option<Value>, context: &ValidationContext) -> Result<(), String>;
+
+    /// a short description of the rule, used as the expected value of a rejected field
+    fn describe(&self) -> String;
+
+    /// called when the validator is attached so that it can reject unsuitable columns
+    fn check_data_type(&self, _data_type: &ColumnStorageDataType) -> Result<(), CacheError> {
+        Ok(())
+    }
+
+    /// when true the cache keeps a count of each value of the column so that
+    /// ValidationContext::count_other does not scan the rows
+    fn counts_values(&self) -> bool {
+        false
+    }
+}
+
+fn check_data_types(
+    data_type: &ColumnStorageDataType,
+    supported: &[ColumnStorageDataType],
+) -> Result<(), CacheError> {
+    if supported.contains(data_type) {
+        Ok(())
+    } else {
+        Err(CacheError::UnsupportedType(format!("{:?}", data_type)))
+    }
+}
+
+fn describe_bounds<T: ToString>(min: &Option<T>, max: &Option<T>) -> String {
+    match (min, max) {
+        (Some(min), Some(max)) => format!("between {} and {}", min.to_string(), max.to_string()),
+        (Some(min), None) => format!("at least {}", min.to_string()),
+        (None, Some(max)) => format!("at most {}", max.to_string()),
+        (None, None) => "any value".to_string(),
+    }
+}
+
+fn check_bounds<T: PartialOrd>(value: T, min: &Option<T>, max: &Option<T>) -> bool {
+    min.as_ref().is_none_or(|min| value >= *min) && max.as_ref().is_none_or(|max| value <= *max)
+}
+
+/// Inclusive bounds on a F64 or I64 column.
+pub struct MinMax {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Validator for MinMax {
+    fn validate(&self, value: &Option<Value>, _context: &ValidationContext) -> Result<(), String> {
+        let number = match value {
+            Some(Value::F64(number)) => *number,
+            Some(Value::I64(number)) => *number as f64,
+            _ => return Ok(()),
+        };
+
+        if check_bounds(number, &self.min, &self.max) {
+            Ok(())
+        } else {
+            Err(format!("{} is not {}", number, self.describe()))
+        }
+    }
+
+    fn describe(&self) -> String {
+        describe_bounds(&self.min, &self.max)
+    }
+
+    fn check_data_type(&self, data_type: &ColumnStorageDataType) -> Result<(), CacheError> {
+        check_data_types(
+            data_type,
+            &[ColumnStorageDataType::F64, ColumnStorageDataType::I64],
+        )
+    }
+}
+
+/// Inclusive limits on the number of characters of a String column.
+pub struct Length {
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+}
+
+impl Validator for Length {
+    fn validate(&self, value: &Option<Value>, _context: &ValidationContext) -> Result<(), String> {
+        let length = match value {
+            Some(Value::String(value)) => value.chars().count(),
+            _ => return Ok(()),
+        };
+
+        if check_bounds(length, &self.min, &self.max) {
+            Ok(())
+        } else {
+            Err(format!("length {} is not {}", length, self.describe()))
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("length {}", describe_bounds(&self.min, &self.max))
+    }
+
+    fn check_data_type(&self, data_type: &ColumnStorageDataType) -> Result<(), CacheError> {
+        check_data_types(data_type, &[ColumnStorageDataType::String])
+    }
+}
+
+/// String values must match the regular expression. Use ^ and $ to match the whole value.
+pub struct Pattern {
+    regex: Regex,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Pattern, CacheError> {
+        let regex = Regex::new(pattern).map_err(|error| CacheError::ParseError(Box::new(error)))?;
+        Ok(Pattern { regex })
+    }
+}
+
+impl Validator for Pattern {
+    fn validate(&self, value: &Option<Value>, _context: &ValidationContext) -> Result<(), String> {
+        match value {
+            Some(Value::String(value)) if !self.regex.is_match(value) => {
+                Err(format!("{} does not match {}", value, self.regex))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("matching {}", self.regex)
+    }
+
+    fn check_data_type(&self, data_type: &ColumnStorageDataType) -> Result<(), CacheError> {
+        check_data_types(data_type, &[ColumnStorageDataType::String])
+    }
+}
+
+/// The value must not be null, checked after the default value has been applied.
+pub struct Required;
+
+impl Validator for Required {
+    fn validate(&self, value: &Option<Value>, _context: &ValidationContext) -> Result<(), String> {
+        match value {
+            Some(_) => Ok(()),
+            None => Err("a value is required".to_string()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        "a value".to_string()
+    }
+}
+
+/// No two rows may hold the same value, nulls are not compared. Values are compared like
+/// Value's PartialEq, so -0.0 is the same as 0.0 and NaN is always unique. The cache keeps a
+/// count of each value of the column so a check does not depend on the number of rows.
+pub struct Unique;
+
+impl Validator for Unique {
+    fn validate(&self, value: &Option<Value>, context: &ValidationContext) -> Result<(), String> {
+        match value {
+            Some(value) if context.count_other(value) > 0 => {
+                Err(format!("{} is not unique", value))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        "a unique value".to_string()
+    }
+
+    fn counts_values(&self) -> bool {
+        true
+    }
+}
+
+/// Inclusive bounds on a TimeDate column.
+pub struct TimeRange {
+    pub start: Option<NaiveDateTime>,
+    pub end: Option<NaiveDateTime>,
+}
+
+impl Validator for TimeRange {
+    fn validate(&self, value: &Option<Value>, _context: &ValidationContext) -> Result<(), String> {
+        match value {
+            Some(Value::TimeDate(time_date))
+                if !check_bounds(*time_date, &self.start, &self.end) =>
+            {
+                Err(format!("{} is not {}", time_date, self.describe()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        describe_bounds(&self.start, &self.end)
+    }
+
+    fn check_data_type(&self, data_type: &ColumnStorageDataType) -> Result<(), CacheError> {
+        check_data_types(data_type, &[ColumnStorageDataType::TimeDate])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::chunked::Chunked;
+    use crate::api::column::Column;
+
+    fn create_storage(values: &[&str]) -> ColumnStorage {
+        let mut column_store = ColumnStorage::StringStorage {
+            column: Column::new("name", "Name", ""),
+            data: Chunked::new(),
+            column_type: ColumnStorageDataType::String,
+        };
+        for value in values {
+            column_store.add_value(value).unwrap();
+        }
+        column_store
+    }
+
+    fn string(value: &str) -> Option<Value> {
+        Some(Value::String(value.to_string()))
+    }
+
+    #[test]
+    fn test_describe_bounds() {
+        assert_eq!(describe_bounds(&Some(1), &Some(5)), "between 1 and 5");
+        assert_eq!(describe_bounds(&Some(1), &None), "at least 1");
+        assert_eq!(describe_bounds::<i32>(&None, &Some(5)), "at most 5");
+    }
+
+    #[test]
+    fn test_unique_skips_replaced_row() {
+        let column_store = create_storage(&["fred", "wilma", "fred"]);
+        let context = ValidationContext {
+            column_store: &column_store,
+            row_index: 2,
+            replacing: None,
+            value_counts: None,
+        };
+        assert!(Unique.validate(&string("fred"), &context).is_err());
+
+        let context = ValidationContext {
+            column_store: &column_store,
+            row_index: 2,
+            replacing: Some(0),
+            value_counts: None,
+        };
+        assert!(Unique.validate(&string("fred"), &context).is_ok());
+        assert!(Unique.validate(&None, &context).is_ok());
+    }
+
+    #[test]
+    fn test_unique_with_value_counts() {
+        // the new row is the last one and is not counted yet
+        let column_store = create_storage(&["fred", "wilma", "fred"]);
+        let mut value_counts = ValueCounts::new(&create_storage(&["fred", "wilma"]));
+        let context = ValidationContext {
+            column_store: &column_store,
+            row_index: 2,
+            replacing: Some(0),
+            value_counts: Some(&value_counts),
+        };
+        assert!(Unique.validate(&string("fred"), &context).is_ok());
+        assert!(Unique.validate(&string("wilma"), &context).is_err());
+
+        value_counts.remove(&string("wilma"));
+        value_counts.add(&string("fred"));
+        let context = ValidationContext {
+            column_store: &column_store,
+            row_index: 2,
+            replacing: Some(0),
+            value_counts: Some(&value_counts),
+        };
+        assert!(Unique.validate(&string("wilma"), &context).is_ok());
+        assert!(Unique.validate(&string("fred"), &context).is_err());
+    }
+
+    #[test]
+    fn test_unique_compares_like_values() {
+        let mut column_store = ColumnStorage::F64Storage {
+            column: Column::new("score", "Score", ""),
+            data: Chunked::new(),
+            column_type: ColumnStorageDataType::F64,
+        };
+        for value in ["0", "NaN"] {
+            column_store.add_value(value).unwrap();
+        }
+        let value_counts = ValueCounts::new(&column_store);
+        let negative_zero = Some(Value::F64(-0.0));
+        let nan = Some(Value::F64(f64::NAN));
+
+        // counting and scanning agree, -0.0 equals 0.0 and no NaN equals another NaN
+        for value_counts in [None, Some(&value_counts)] {
+            let context = ValidationContext {
+                column_store: &column_store,
+                row_index: 2,
+                replacing: Some(0),
+                value_counts,
+            };
+            assert!(Unique.validate(&negative_zero, &context).is_ok());
+            assert!(Unique.validate(&nan, &context).is_ok());
+
+            let context = ValidationContext {
+                column_store: &column_store,
+                row_index: 2,
+                replacing: None,
+                value_counts,
+            };
+            assert!(Unique.validate(&negative_zero, &context).is_err());
+            assert!(Unique.validate(&nan, &context).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_length_counts_characters() {
+        let column_store = create_storage(&[]);
+        let context = ValidationContext {
+            column_store: &column_store,
+            row_index: 0,
+            replacing: None,
+            value_counts: None,
+        };
+        let length = Length {
+            min: None,
+            max: Some(4),
+        };
+        assert!(length.validate(&string("café"), &context).is_ok());
+        assert!(length.validate(&string("cafés"), &context).is_err());
+    }
+}
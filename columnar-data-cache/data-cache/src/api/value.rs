@@ -0,0 +1,90 @@
+use crate::api::column_storage::ColumnStorageDataType;
+use crate::api::parsers::format_duration;
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use uuid::Uuid;
+
+/// A single typed cell value read out of a column storage.
+/// Nulls are represented by wrapping the value in an Option.
+#[derive(Debug, PartialEq, PartialOrd, Clone)]
+pub enum Value {
+    String(String),
+    Boolean(bool),
+    F64(f64),
+    TimeDate(NaiveDateTime),
+    Enumerated(String),
+    I64(i64),
+    Decimal(Decimal),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Duration(Duration),
+    Uuid(Uuid),
+}
+
+impl Value {
+    pub fn data_type(&self) -> ColumnStorageDataType {
+        match self {
+            Value::String(_) => ColumnStorageDataType::String,
+            Value::Boolean(_) => ColumnStorageDataType::Boolean,
+            Value::F64(_) => ColumnStorageDataType::F64,
+            Value::TimeDate(_) => ColumnStorageDataType::TimeDate,
+            Value::Enumerated(_) => ColumnStorageDataType::Enumerated,
+            Value::I64(_) => ColumnStorageDataType::I64,
+            Value::Decimal(_) => ColumnStorageDataType::Decimal,
+            Value::Date(_) => ColumnStorageDataType::Date,
+            Value::Time(_) => ColumnStorageDataType::Time,
+            Value::Duration(_) => ColumnStorageDataType::Duration,
+            Value::Uuid(_) => ColumnStorageDataType::Uuid,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Value::String(value) => write!(formatter, "{}", value),
+            Value::Boolean(value) => write!(formatter, "{}", value),
+            Value::F64(value) => write!(formatter, "{}", value),
+            Value::TimeDate(value) => write!(formatter, "{}", value),
+            Value::Enumerated(value) => write!(formatter, "{}", value),
+            Value::I64(value) => write!(formatter, "{}", value),
+            Value::Decimal(value) => write!(formatter, "{}", value),
+            Value::Date(value) => write!(formatter, "{}", value),
+            Value::Time(value) => write!(formatter, "{}", value),
+            Value::Duration(value) => write!(formatter, "{}", format_duration(value)),
+            Value::Uuid(value) => write!(formatter, "{}", value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Value::String("fred".to_string()).to_string(), "fred");
+        assert_eq!(Value::Boolean(true).to_string(), "true");
+        assert_eq!(Value::F64(1.0).to_string(), "1");
+        assert_eq!(
+            Value::Duration(Duration::seconds(3661)).to_string(),
+            "1:01:01"
+        );
+        assert_eq!(
+            Value::TimeDate(
+                NaiveDateTime::parse_from_str("2020-01-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap()
+            )
+            .to_string(),
+            "2020-01-01 00:00:00"
+        );
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(Value::F64(1.0) < Value::F64(2.0));
+        assert!(Value::Enumerated("a".to_string()) < Value::Enumerated("b".to_string()));
+        assert!(Some(Value::F64(-100.0)) > None);
+    }
+}
@@ -0,0 +1,8 @@
+use data_cache::api::concurrent_cache::ConcurrentCache;
+use rocket::launch;
+
+// starts with an empty cache, columns are added through the /api/columns route
+#[launch]
+fn rocket() -> _ {
+    data_cache::server::rocket(ConcurrentCache::default())
+}
@@ -1 +1,3 @@
 pub mod api;
+#[cfg(feature = "server")]
+pub mod server;
@@ -0,0 +1,86 @@
+//! A REST service over a cache, built with rocket. Rows are read from the latest published
+//! snapshot of a ConcurrentCache so reads never wait for a write to finish.
+//!
+//! All routes are mounted under /api and exchange JSON, rows use the same objects as
+//! Cache::to_json. Errors are returned as {"error": message} with a status for the kind of error.
+
+use crate::api::cache::CacheError;
+use crate::api::concurrent_cache::ConcurrentCache;
+use rocket::http::Status;
+use rocket::response::Responder;
+use rocket::serde::json::{json, Json};
+use rocket::{catch, catchers, routes, Build, Request, Rocket};
+use uuid::Uuid;
+
+mod columns;
+mod query;
+mod rows;
+
+pub fn rocket(cache: ConcurrentCache) -> Rocket<Build> {
+    rocket::build()
+        .manage(cache)
+        .mount(
+            "/api",
+            routes![
+                columns::get_metadata,
+                columns::add_column,
+                rows::create_row,
+                rows::get_row,
+                rows::update_row,
+                rows::delete_row,
+                query::query,
+            ],
+        )
+        .register("/api", catchers![default_catcher])
+}
+
+/// A cache error returned from a route, the status depends on the kind of error.
+#[derive(Debug)]
+pub struct ApiError(pub CacheError);
+
+impl From<CacheError> for ApiError {
+    fn from(error: CacheError) -> Self {
+        ApiError(error)
+    }
+}
+
+impl ApiError {
+    pub fn status(&self) -> Status {
+        match self.0 {
+            CacheError::GuidNotFound(..) | CacheError::ColumnNotFound(..) => Status::NotFound,
+            CacheError::DuplicateColumn(..) | CacheError::DuplicateGuid(..) => Status::Conflict,
+            CacheError::ParseError(..)
+            | CacheError::NotAllowed(..)
+            | CacheError::UnsupportedType(..)
+            | CacheError::ValidationFailed(..)
+            | CacheError::InvalidRow(..)
+            | CacheError::FieldCount { .. }
+            | CacheError::TypeMismatch { .. }
+            | CacheError::InvalidColumnData { .. }
+            | CacheError::SchemaMismatch { .. } => Status::BadRequest,
+            CacheError::IllegalState
+            | CacheError::RollbackFailed { .. }
+            | CacheError::IoError(..)
+            | CacheError::InvalidSnapshot(..) => Status::InternalServerError,
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let body = json!({ "error": self.0.to_string() });
+        (self.status(), Json(body)).respond_to(request)
+    }
+}
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+pub(crate) fn parse_guid(guid: &str) -> Result<Uuid, CacheError> {
+    Uuid::parse_str(guid).map_err(|error| CacheError::ParseError(Box::new(error)))
+}
+
+// requests that do not reach a route, such as bodies that are not JSON, get a JSON error too
+#[catch(default)]
+fn default_catcher(status: Status, _request: &Request) -> Json<rocket::serde::json::Value> {
+    Json(json!({ "error": status.to_string() }))
+}
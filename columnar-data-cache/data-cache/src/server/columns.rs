@@ -0,0 +1,98 @@
+use crate::api::cache::CacheError;
+use crate::api::concurrent_cache::ConcurrentCache;
+use crate::api::migration::ColumnType;
+use crate::server::ApiResult;
+use rocket::http::Status;
+use rocket::serde::json::{json, Json, Value};
+use rocket::serde::Deserialize;
+use rocket::{get, post, State};
+
+/// A column to add, data type is one of the ColumnStorageDataType names such as "TimeDate".
+/// Format applies to TimeDate, Date and Time columns, allowed values to Enumerated columns
+/// and scale to Decimal columns.
+#[derive(Deserialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct ColumnRequest {
+    name: String,
+    display_name: Option<String>,
+    data_type: String,
+    #[serde(default)]
+    default_value: String,
+    #[serde(default)]
+    format: String,
+    #[serde(default)]
+    allowed_values: Vec<String>,
+    #[serde(default)]
+    scale: u32,
+}
+
+impl ColumnRequest {
+    fn column_type(&self) -> Result<ColumnType, CacheError> {
+        let column_type = match self.data_type.as_str() {
+            "String" => ColumnType::String,
+            "Boolean" => ColumnType::Boolean,
+            "F64" => ColumnType::F64,
+            "TimeDate" => ColumnType::TimeDate {
+                format: self.format.clone(),
+            },
+            "Enumerated" => ColumnType::Enumerated {
+                allowed_values: self.allowed_values.clone(),
+            },
+            "I64" => ColumnType::I64,
+            "Decimal" => ColumnType::Decimal { scale: self.scale },
+            "Date" => ColumnType::Date {
+                format: self.format.clone(),
+            },
+            "Time" => ColumnType::Time {
+                format: self.format.clone(),
+            },
+            "Duration" => ColumnType::Duration,
+            "Uuid" => ColumnType::Uuid,
+            data_type => return Err(CacheError::UnsupportedType(data_type.to_string())),
+        };
+        Ok(column_type)
+    }
+}
+
+#[get("/metadata")]
+pub fn get_metadata(cache: &State<ConcurrentCache>) -> Json<Value> {
+    let cache = cache.snapshot();
+    let columns: Vec<Value> = cache
+        .get_metadata()
+        .iter()
+        .map(|metadata| {
+            json!({
+                "name": metadata.name,
+                "display_name": metadata.display_name,
+                "default_value": metadata.default_value,
+                "data_type": format!("{:?}", metadata.data_type),
+                "format": metadata.format,
+                "allowed_values": metadata.allowed_values,
+                "scale": metadata.scale,
+                "derived": metadata.derived,
+            })
+        })
+        .collect();
+
+    Json(json!({ "row_count": cache.row_count(), "columns": columns }))
+}
+
+/// existing rows get the default value of the new column
+#[post("/columns", data = "<column>")]
+pub fn add_column(
+    cache: &State<ConcurrentCache>,
+    column: Json<ColumnRequest>,
+) -> ApiResult<Status> {
+    let column_type = column.column_type()?;
+    let display_name = column.display_name.as_ref().unwrap_or(&column.name);
+    cache.write(|cache| {
+        cache.add_column(
+            &column.name,
+            display_name,
+            column_type,
+            &column.default_value,
+            vec![],
+        )
+    })?;
+    Ok(Status::Created)
+}
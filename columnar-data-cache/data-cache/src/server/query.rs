@@ -0,0 +1,94 @@
+use crate::api::concurrent_cache::ConcurrentCache;
+use crate::api::query::{Predicate, Query, SortDirection};
+use crate::server::ApiResult;
+use rocket::serde::json::{Json, Value};
+use rocket::serde::Deserialize;
+use rocket::{post, State};
+
+/// The JSON form of a Predicate, for example {"op": "range", "column": "age", "min": "18"}.
+#[derive(Deserialize, Debug)]
+#[serde(crate = "rocket::serde", tag = "op", rename_all = "snake_case")]
+pub enum PredicateRequest {
+    Equals {
+        column: String,
+        value: String,
+    },
+    Range {
+        column: String,
+        #[serde(default)]
+        min: String,
+        #[serde(default)]
+        max: String,
+    },
+    In {
+        column: String,
+        values: Vec<String>,
+    },
+    IsNull {
+        column: String,
+    },
+    IsNotNull {
+        column: String,
+    },
+}
+
+impl From<&PredicateRequest> for Predicate {
+    fn from(predicate: &PredicateRequest) -> Self {
+        match predicate {
+            PredicateRequest::Equals { column, value } => Predicate::equals(column, value),
+            PredicateRequest::Range { column, min, max } => Predicate::range(column, min, max),
+            PredicateRequest::In { column, values } => {
+                let values: Vec<&str> = values.iter().map(|value| value.as_str()).collect();
+                Predicate::one_of(column, &values)
+            }
+            PredicateRequest::IsNull { column } => Predicate::is_null(column),
+            PredicateRequest::IsNotNull { column } => Predicate::is_not_null(column),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct SortRequest {
+    column: String,
+    #[serde(default)]
+    descending: bool,
+}
+
+/// every part is optional, an empty query returns all columns of every row
+#[derive(Deserialize, Debug, Default)]
+#[serde(crate = "rocket::serde", default)]
+pub struct QueryRequest {
+    filters: Vec<PredicateRequest>,
+    sort: Vec<SortRequest>,
+    columns: Vec<String>,
+}
+
+impl From<&QueryRequest> for Query {
+    fn from(request: &QueryRequest) -> Self {
+        let mut query = Query::new();
+        for predicate in &request.filters {
+            query = query.filter(predicate.into());
+        }
+        for sort in &request.sort {
+            let direction = if sort.descending {
+                SortDirection::Descending
+            } else {
+                SortDirection::Ascending
+            };
+            query = query.sort_by(&sort.column, direction);
+        }
+        let columns: Vec<&str> = request
+            .columns
+            .iter()
+            .map(|column| column.as_str())
+            .collect();
+        query.select(&columns)
+    }
+}
+
+#[post("/query", data = "<query>")]
+pub fn query(cache: &State<ConcurrentCache>, query: Json<QueryRequest>) -> ApiResult<Json<Value>> {
+    let query = Query::from(&*query);
+    Ok(Json(cache.snapshot().query_to_json(&query)?))
+}
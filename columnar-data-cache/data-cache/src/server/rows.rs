@@ -0,0 +1,46 @@
+use crate::api::concurrent_cache::ConcurrentCache;
+use crate::server::{parse_guid, ApiResult};
+use rocket::http::Status;
+use rocket::serde::json::{Json, Value};
+use rocket::{delete, get, post, put, State};
+
+/// the body is a JSON object keyed by column name, missing columns get their default value
+#[post("/rows", data = "<row>")]
+pub fn create_row(
+    cache: &State<ConcurrentCache>,
+    row: Json<Value>,
+) -> ApiResult<(Status, Json<Value>)> {
+    let row = cache.write(|cache| {
+        let guid = cache.create_row_from_json(&row)?;
+        cache.json_for_guid(&guid)
+    })?;
+    Ok((Status::Created, Json(row)))
+}
+
+#[get("/rows/<guid>")]
+pub fn get_row(cache: &State<ConcurrentCache>, guid: &str) -> ApiResult<Json<Value>> {
+    let guid = parse_guid(guid)?;
+    Ok(Json(cache.snapshot().json_for_guid(&guid)?))
+}
+
+/// replaces every value of the row, the same as Cache::update_row_from_json
+#[put("/rows/<guid>", data = "<row>")]
+pub fn update_row(
+    cache: &State<ConcurrentCache>,
+    guid: &str,
+    row: Json<Value>,
+) -> ApiResult<Json<Value>> {
+    let guid = parse_guid(guid)?;
+    let row = cache.write(|cache| {
+        cache.update_row_from_json(&guid, &row)?;
+        cache.json_for_guid(&guid)
+    })?;
+    Ok(Json(row))
+}
+
+#[delete("/rows/<guid>")]
+pub fn delete_row(cache: &State<ConcurrentCache>, guid: &str) -> ApiResult<Status> {
+    let guid = parse_guid(guid)?;
+    cache.write(|cache| cache.delete_row(&guid))?;
+    Ok(Status::NoContent)
+}
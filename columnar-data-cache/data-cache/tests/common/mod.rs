@@ -0,0 +1,33 @@
+use data_cache::api::cache::Cache;
+use data_cache::api::query::Query;
+use uuid::Uuid;
+
+pub fn create_flavors() -> Vec<String> {
+    vec![
+        "vanilla".to_string(),
+        "chocolate".to_string(),
+        "strawberry".to_string(),
+    ]
+}
+
+#[allow(dead_code)]
+pub fn all_guids(cache: &Cache) -> Vec<Uuid> {
+    cache
+        .query(&Query::new())
+        .unwrap()
+        .map(|row| row.guid)
+        .collect()
+}
+
+/// the caches have the same columns and the same rows in the same order
+#[allow(dead_code)]
+pub fn assert_same(left: &Cache, right: &Cache) {
+    assert_eq!(left.get_metadata(), right.get_metadata());
+    assert_eq!(all_guids(left), all_guids(right));
+    for guid in all_guids(left) {
+        assert_eq!(
+            left.csv_for_guid(&guid).unwrap(),
+            right.csv_for_guid(&guid).unwrap()
+        );
+    }
+}
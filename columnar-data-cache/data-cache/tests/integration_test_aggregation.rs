@@ -0,0 +1,124 @@
+mod common;
+
+use common::create_flavors;
+use data_cache::api::aggregation::Aggregate;
+use data_cache::api::cache::{Cache, CacheError};
+use data_cache::api::value::Value;
+
+fn create_cache() -> Result<Cache, CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "")?;
+    cache.add_boolean_column("verified", "Verified", "")?;
+    cache.add_f64_column("weight", "Weight", "")?;
+    cache.add_enumerated_column("flavor", "Flavor", "", create_flavors())?;
+
+    cache.create_row("fred,true,200,chocolate")?;
+    cache.create_row("wilma,true,120,vanilla")?;
+    cache.create_row("barney,false,180,chocolate")?;
+    cache.create_row("betty,false,,vanilla")?;
+    cache.create_row("pebbles,,30,")?;
+    cache.create_row("bamm-bamm,,40,vanilla")?;
+
+    Ok(cache)
+}
+
+#[test]
+fn test_aggregate() {
+    let cache = create_cache().unwrap();
+    assert_eq!(
+        cache.aggregate("weight").unwrap(),
+        Aggregate {
+            row_count: 6,
+            count: 5,
+            sum: Some(570.0),
+            min: Some(30.0),
+            max: Some(200.0),
+            mean: Some(114.0),
+        }
+    );
+}
+
+#[test]
+fn test_aggregate_by_enumerated() {
+    let cache = create_cache().unwrap();
+    let table = cache.aggregate_by("weight", "flavor").unwrap();
+
+    assert_eq!(table.column, "weight");
+    assert_eq!(table.group_column, "flavor");
+    let groups: Vec<Option<Value>> = table.rows.iter().map(|row| row.group.clone()).collect();
+    assert_eq!(
+        groups,
+        vec![
+            None,
+            Some(Value::Enumerated("chocolate".to_string())),
+            Some(Value::Enumerated("vanilla".to_string())),
+        ]
+    );
+
+    let vanilla = table
+        .get(Some(&Value::Enumerated("vanilla".to_string())))
+        .unwrap();
+    assert_eq!(vanilla.row_count, 3);
+    assert_eq!(vanilla.count, 2);
+    assert_eq!(vanilla.mean, Some(80.0));
+
+    let chocolate = table
+        .get(Some(&Value::Enumerated("chocolate".to_string())))
+        .unwrap();
+    assert_eq!(chocolate.mean, Some(190.0));
+    assert_eq!(table.get(None).unwrap().sum, Some(30.0));
+    assert_eq!(
+        table.get(Some(&Value::Enumerated("strawberry".to_string()))),
+        None
+    );
+}
+
+#[test]
+fn test_aggregate_by_boolean() {
+    let cache = create_cache().unwrap();
+    let table = cache.aggregate_by("weight", "verified").unwrap();
+
+    assert_eq!(table.rows.len(), 3);
+    assert_eq!(
+        table.get(Some(&Value::Boolean(true))).unwrap().max,
+        Some(200.0)
+    );
+    assert_eq!(
+        table.get(Some(&Value::Boolean(false))).unwrap().min,
+        Some(180.0)
+    );
+    assert_eq!(table.get(None).unwrap().count, 2);
+}
+
+#[test]
+fn test_aggregate_empty_cache() {
+    let mut cache = Cache::new();
+    cache.add_f64_column("weight", "Weight", "").unwrap();
+    cache
+        .add_boolean_column("verified", "Verified", "")
+        .unwrap();
+
+    assert_eq!(cache.aggregate("weight").unwrap(), Aggregate::default());
+    assert!(cache
+        .aggregate_by("weight", "verified")
+        .unwrap()
+        .rows
+        .is_empty());
+}
+
+#[test]
+fn test_aggregate_errors() {
+    let cache = create_cache().unwrap();
+    assert_eq!(
+        cache.aggregate("height"),
+        Err(CacheError::ColumnNotFound("height".to_string()))
+    );
+    assert_eq!(
+        cache.aggregate("name"),
+        Err(CacheError::UnsupportedType("String".to_string()))
+    );
+    assert_eq!(
+        cache.aggregate_by("weight", "name"),
+        Err(CacheError::UnsupportedType("String".to_string()))
+    );
+}
@@ -0,0 +1,269 @@
+mod common;
+
+use arrow_array::types::Int32Type;
+use arrow_array::{
+    ArrayRef, BinaryArray, Date32Array, DictionaryArray, FixedSizeBinaryArray, Int64Array,
+    RecordBatch, StringArray, Time64NanosecondArray, TimestampSecondArray,
+};
+use arrow_schema::{DataType, TimeUnit};
+use common::{assert_same, create_flavors};
+use data_cache::api::cache::{Cache, CacheError};
+use data_cache::api::column_storage::ColumnStorageDataType;
+use data_cache::api::query::Query;
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn create_cache() -> Result<Cache, CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "unknown")?;
+    cache.add_boolean_column("verified", "Verified", "")?;
+    cache.add_f64_column("age", "Age", "0")?;
+    cache.add_time_date_column("start_time", "Start Time", "%Y-%m-%d %H:%M:%S", "")?;
+    cache.add_enumerated_column("flavor", "Flavor", "", create_flavors())?;
+
+    cache.create_row("fred,true, 1.5, 2019-01-01 00:00:00,chocolate")?;
+    cache.create_row(",,,,")?;
+    cache.create_row("wilma,false, -2, 2020-02-29 23:59:59,strawberry")?;
+
+    Ok(cache)
+}
+
+#[test]
+fn test_record_batch_types() {
+    let cache = create_cache().unwrap();
+    let batch = cache.to_record_batch().unwrap();
+    let schema = batch.schema();
+
+    assert_eq!(batch.num_rows(), 3);
+    assert_eq!(batch.num_columns(), 6);
+    assert_eq!(schema.field(0).name(), "_guid");
+    assert_eq!(schema.field(1).data_type(), &DataType::Utf8);
+    assert_eq!(schema.field(2).data_type(), &DataType::Boolean);
+    assert_eq!(schema.field(3).data_type(), &DataType::Float64);
+    assert_eq!(
+        schema.field(4).data_type(),
+        &DataType::Timestamp(TimeUnit::Microsecond, None)
+    );
+    assert_eq!(
+        schema.field(5).data_type(),
+        &DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+    );
+    assert_eq!(batch.column(2).null_count(), 1);
+    assert_eq!(batch.column(5).null_count(), 1);
+}
+
+#[test]
+fn test_record_batch_round_trip() {
+    let cache = create_cache().unwrap();
+    let batch = cache.to_record_batch().unwrap();
+    let rebuilt = Cache::from_record_batches(&[batch.clone(), batch.slice(0, 0)]).unwrap();
+    assert_same(&cache, &rebuilt);
+}
+
+#[test]
+fn test_parquet_round_trip() {
+    let cache = create_cache().unwrap();
+    let path = std::env::temp_dir().join(format!("data-cache-{}.parquet", Uuid::new_v4()));
+    cache.write_parquet_file(&path).unwrap();
+    let rebuilt = Cache::read_parquet_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_same(&cache, &rebuilt);
+}
+
+#[test]
+fn test_inferred_columns() {
+    let flavors = DictionaryArray::<Int32Type>::from_iter(["mint", "mint", "lime"]);
+    let batch = RecordBatch::try_from_iter(vec![
+        (
+            "name",
+            Arc::new(StringArray::from(vec![Some("fred"), None, Some("wilma")])) as ArrayRef,
+        ),
+        (
+            "count",
+            Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef,
+        ),
+        (
+            "seen",
+            Arc::new(TimestampSecondArray::from(vec![Some(0), None, Some(86400)])) as ArrayRef,
+        ),
+        ("flavor", Arc::new(flavors) as ArrayRef),
+    ])
+    .unwrap();
+
+    let mut cache = Cache::from_record_batches(&[batch]).unwrap();
+    let metadata = cache.get_metadata();
+    assert_eq!(cache.row_len(), 3);
+    assert_eq!(metadata[0].display_name, "name");
+    assert_eq!(metadata[1].data_type, ColumnStorageDataType::I64);
+    assert_eq!(metadata[2].data_type, ColumnStorageDataType::TimeDate);
+    assert_eq!(metadata[3].allowed_values, vec!["mint", "lime"]);
+
+    let guid = cache
+        .create_row("barney,4,1970-01-03 00:00:00,lime")
+        .unwrap();
+    assert_eq!(
+        cache.csv_for_guid(&guid).unwrap(),
+        "barney,4,1970-01-03 00:00:00,lime"
+    );
+    let rows: Vec<String> = cache
+        .query(&Query::new())
+        .unwrap()
+        .map(|row| cache.csv_for_guid(&row.guid).unwrap())
+        .collect();
+    assert_eq!(rows[1], ",2,,mint");
+    assert_eq!(rows[2], "wilma,3,1970-01-02 00:00:00,lime");
+}
+
+#[test]
+fn test_unsupported_type() {
+    let batch = RecordBatch::try_from_iter(vec![(
+        "blob",
+        Arc::new(BinaryArray::from(vec![b"abc".as_ref()])) as ArrayRef,
+    )])
+    .unwrap();
+    assert_eq!(
+        Cache::from_record_batches(&[batch]).err(),
+        Some(CacheError::UnsupportedType("Binary".to_string()))
+    );
+}
+
+#[test]
+fn test_duplicates_in_record_batch() {
+    let guid = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+    let batch = RecordBatch::try_from_iter(vec![
+        (
+            "_guid",
+            Arc::new(StringArray::from(vec![guid, guid])) as ArrayRef,
+        ),
+        (
+            "name",
+            Arc::new(StringArray::from(vec!["fred", "wilma"])) as ArrayRef,
+        ),
+    ])
+    .unwrap();
+    assert_eq!(
+        Cache::from_record_batches(&[batch]).err(),
+        Some(CacheError::DuplicateGuid(Uuid::parse_str(guid).unwrap()))
+    );
+
+    let batch = RecordBatch::try_from_iter(vec![
+        (
+            "name",
+            Arc::new(StringArray::from(vec!["fred"])) as ArrayRef,
+        ),
+        (
+            "name",
+            Arc::new(StringArray::from(vec!["flintstone"])) as ArrayRef,
+        ),
+    ])
+    .unwrap();
+    assert_eq!(
+        Cache::from_record_batches(&[batch]).err(),
+        Some(CacheError::DuplicateColumn("name".to_string()))
+    );
+}
+
+fn schema_mismatch(batch: usize, reason: &str) -> Option<CacheError> {
+    Some(CacheError::SchemaMismatch {
+        batch,
+        reason: reason.to_string(),
+    })
+}
+
+#[test]
+fn test_record_batches_with_different_schemas() {
+    let batch = |first: (&str, ArrayRef), second: (&str, ArrayRef)| {
+        RecordBatch::try_from_iter(vec![first, second]).unwrap()
+    };
+    let first_name = || Arc::new(StringArray::from(vec!["fred"])) as ArrayRef;
+    let last_name = || Arc::new(StringArray::from(vec!["flintstone"])) as ArrayRef;
+    let first = batch(("first_name", first_name()), ("last_name", last_name()));
+
+    // the same types in swapped order would otherwise be merged into the wrong columns
+    let swapped = batch(("last_name", last_name()), ("first_name", first_name()));
+    assert_eq!(
+        Cache::from_record_batches(&[first.clone(), swapped]).err(),
+        schema_mismatch(1, "field 0 is last_name, expected first_name")
+    );
+
+    let retyped = batch(
+        ("first_name", first_name()),
+        ("last_name", Arc::new(Int64Array::from(vec![1])) as ArrayRef),
+    );
+    assert_eq!(
+        Cache::from_record_batches(&[first.clone(), first.clone(), retyped]).err(),
+        schema_mismatch(2, "last_name is Int64, expected Utf8")
+    );
+
+    let shorter = RecordBatch::try_from_iter(vec![("first_name", first_name())]).unwrap();
+    assert_eq!(
+        Cache::from_record_batches(&[first, shorter]).err(),
+        schema_mismatch(1, "1 fields, expected 2")
+    );
+
+    let relabelled = |display_name: &str| {
+        let mut cache = Cache::new();
+        cache.add_string_column("name", display_name, "").unwrap();
+        cache.create_row("fred").unwrap();
+        cache.to_record_batch().unwrap()
+    };
+    assert_eq!(
+        Cache::from_record_batches(&[relabelled("Name"), relabelled("Full Name")]).err(),
+        schema_mismatch(1, "name has a different display_name")
+    );
+}
+
+fn invalid_data(column: &str, reason: &str) -> Option<CacheError> {
+    Some(CacheError::InvalidColumnData {
+        column: column.to_string(),
+        reason: reason.to_string(),
+    })
+}
+
+#[test]
+fn test_invalid_data_in_record_batch() {
+    let batch = RecordBatch::try_from_iter(vec![(
+        "born",
+        Arc::new(Date32Array::from(vec![Some(0), Some(i32::MAX)])) as ArrayRef,
+    )])
+    .unwrap();
+    assert_eq!(
+        Cache::from_record_batches(&[batch]).err(),
+        invalid_data("born", "2147483647 days is not a date")
+    );
+
+    for nanoseconds in [-1, 86_400_000_000_000, i64::MAX] {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "alarm",
+            Arc::new(Time64NanosecondArray::from(vec![nanoseconds])) as ArrayRef,
+        )])
+        .unwrap();
+        assert_eq!(
+            Cache::from_record_batches(&[batch]).err(),
+            invalid_data(
+                "alarm",
+                &format!("{} nanoseconds is not a time of day", nanoseconds)
+            )
+        );
+    }
+
+    let batch = RecordBatch::try_from_iter(vec![(
+        "order_id",
+        Arc::new(FixedSizeBinaryArray::from(vec![b"12345678".as_ref()])) as ArrayRef,
+    )])
+    .unwrap();
+    assert_eq!(
+        Cache::from_record_batches(&[batch]).err(),
+        invalid_data("order_id", "8 byte values are not uuids")
+    );
+
+    let batch = RecordBatch::try_from_iter(vec![(
+        "seen",
+        Arc::new(TimestampSecondArray::from(vec![None, Some(i64::MAX)])) as ArrayRef,
+    )])
+    .unwrap();
+    assert_eq!(
+        Cache::from_record_batches(&[batch]).err(),
+        invalid_data("seen", "row 1 is not a date and time")
+    );
+}
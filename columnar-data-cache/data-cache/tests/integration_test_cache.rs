@@ -1,15 +1,12 @@
-use data_cache::api::cache::{Cache, CacheError};
+mod common;
+
+use common::create_flavors;
+use data_cache::api::cache::{Cache, CacheError, Expected};
 use data_cache::api::column_storage::ColumnStorageDataType;
+use data_cache::api::events::CacheEvent;
+use data_cache::api::query::Predicate;
 use uuid::Uuid;
 
-fn create_flavors() -> Vec<String> {
-    vec![
-        "vanilla".to_string(),
-        "chocolate".to_string(),
-        "strawberry".to_string(),
-    ]
-}
-
 fn create_cache() -> Result<Cache, CacheError> {
     let mut cache = Cache::new();
     cache.add_string_column("name", "Name", "unknown")?;
@@ -221,6 +218,59 @@ fn test_update_row_invalid() {
     );
 }
 
+#[test]
+fn test_padded_values_are_trimmed() {
+    let mut cache = create_cache().unwrap();
+
+    let guid = cache
+        .create_row(" fred , true, 1, 2019-01-01 00:00:00, chocolate ")
+        .unwrap();
+    assert_eq!(
+        cache.csv_for_guid(&guid).unwrap(),
+        "fred,true,1,2019-01-01 00:00:00,chocolate"
+    );
+
+    cache
+        .update_row(&guid, "wilma, false, 2, , vanilla")
+        .unwrap();
+    let row = cache.get_row(&guid).unwrap();
+    assert_eq!(row.get_string("name"), Ok(Some("wilma".to_string())));
+    assert_eq!(
+        row.get_enumerated("flavor"),
+        Ok(Some("vanilla".to_string()))
+    );
+}
+
+#[test]
+fn test_quoted_values_round_trip() {
+    let mut cache = create_cache().unwrap();
+
+    let guid = cache
+        .create_row("\"Flintstone, Fred\",true,1,,chocolate")
+        .unwrap();
+    let row = cache.get_row(&guid).unwrap();
+    assert_eq!(
+        row.get_string("name"),
+        Ok(Some("Flintstone, Fred".to_string()))
+    );
+
+    let csv = cache.csv_for_guid(&guid).unwrap();
+    assert_eq!(csv, "\"Flintstone, Fred\",true,1,,chocolate");
+    let copy = cache.create_row(&csv).unwrap();
+    assert_eq!(cache.csv_for_guid(&copy).unwrap(), csv);
+    cache.update_row(&copy, &csv).unwrap();
+    assert_eq!(cache.csv_for_guid(&copy).unwrap(), csv);
+
+    // quoted values are trimmed like unquoted ones
+    let guid = cache.create_row("\" Rubble \",false,2,,").unwrap();
+    let csv = cache.csv_for_guid(&guid).unwrap();
+    assert_eq!(csv, "Rubble,false,2,,vanilla");
+    let copy = cache
+        .transaction(|transaction| transaction.create_row(&csv))
+        .unwrap();
+    assert_eq!(cache.csv_for_guid(&copy).unwrap(), csv);
+}
+
 #[test]
 fn test_missing_guid() {
     let mut cache = create_cache().unwrap();
@@ -309,11 +359,66 @@ fn test_invalid_row_format() {
     if let Err(error) = result {
         assert_eq!(
             error.to_string(),
-            "ParseError: provided string was not `true` or `false`"
+            "Invalid row: verified at position 1 has value 'invalid boolean', expected Boolean \
+             (ParseError: provided string was not `true` or `false`)"
         );
     }
 }
 
+#[test]
+fn test_all_field_errors() {
+    let mut cache = create_cache().unwrap();
+    let guid = cache
+        .create_row("wilma,true,5,2020-01-01 00:00:00,vanilla")
+        .unwrap();
+
+    let result = cache.update_row(&guid, "wilma,maybe,5,2020-01-01 00:00:00,mint");
+    let field_errors = match result {
+        Err(CacheError::InvalidRow(field_errors)) => field_errors,
+        _ => panic!("expected an invalid row"),
+    };
+    assert_eq!(field_errors.len(), 2);
+
+    assert_eq!(field_errors[0].column, "verified");
+    assert_eq!(field_errors[0].position, 1);
+    assert_eq!(field_errors[0].value, "maybe");
+    assert_eq!(
+        field_errors[0].expected,
+        Expected::DataType(ColumnStorageDataType::Boolean)
+    );
+
+    assert_eq!(field_errors[1].column, "flavor");
+    assert_eq!(field_errors[1].position, 4);
+    assert_eq!(
+        field_errors[1].expected,
+        Expected::AllowedValues(create_flavors())
+    );
+    assert_eq!(
+        *field_errors[1].error,
+        CacheError::NotAllowed("mint".to_string())
+    );
+
+    // the rejected update leaves the row as it was
+    assert_eq!(
+        cache.csv_for_guid(&guid).unwrap(),
+        "wilma,true,5,2020-01-01 00:00:00,vanilla"
+    );
+    assert_eq!(cache.row_count(), 1);
+}
+
+#[test]
+fn test_wrong_field_count() {
+    let mut cache = create_cache().unwrap();
+    assert_eq!(
+        cache.create_row("wilma,true"),
+        Err(CacheError::FieldCount {
+            expected: 5,
+            actual: 2
+        })
+    );
+    assert_eq!(cache.row_count(), 0);
+}
+
 #[test]
 fn test_common_column_data() {
     let cache = create_cache().unwrap();
@@ -324,6 +429,8 @@ fn test_common_column_data() {
     assert_eq!(metadata[2].default_value, "0".to_string());
     assert_eq!(metadata[3].data_type, ColumnStorageDataType::TimeDate);
     assert_eq!(metadata[3].format, "%Y-%m-%d %H:%M:%S".to_string());
+    assert_eq!(metadata[4].allowed_values, create_flavors());
+    assert!(metadata[0].allowed_values.is_empty());
 }
 
 #[test]
@@ -341,3 +448,150 @@ fn test_cache_lengths() {
     assert_eq!(cache.row_len(), 1);
     assert_eq!(cache.column_len(), 5);
 }
+
+#[test]
+fn test_update_many_rows() {
+    let mut cache = create_cache().unwrap();
+
+    let guids: Vec<_> = (0..100)
+        .map(|index| {
+            cache
+                .create_row(format!("name {},true,{},,vanilla", index, index).as_str())
+                .unwrap()
+        })
+        .collect();
+
+    for (index, guid) in guids.iter().enumerate().step_by(3) {
+        cache
+            .update_row(
+                guid,
+                format!("updated {},false,{},,chocolate", index, index).as_str(),
+            )
+            .unwrap();
+    }
+
+    assert_eq!(cache.row_len(), 100);
+    for (index, guid) in guids.iter().enumerate() {
+        let expected = if index % 3 == 0 {
+            format!("updated {},false,{},,chocolate", index, index)
+        } else {
+            format!("name {},true,{},,vanilla", index, index)
+        };
+        assert_eq!(cache.csv_for_guid(guid).unwrap(), expected);
+    }
+}
+
+fn create_many_rows(cache: &mut Cache) -> Vec<Uuid> {
+    (0..100)
+        .map(|index| {
+            cache
+                .create_row(
+                    format!(
+                        "name {},true,{},,{}",
+                        index,
+                        index,
+                        create_flavors()[index % 3]
+                    )
+                    .as_str(),
+                )
+                .unwrap()
+        })
+        .collect()
+}
+
+// every remaining row must still have its own values in every column
+fn assert_rows_aligned(cache: &Cache, guids: &[Uuid], deleted: impl Fn(usize) -> bool) {
+    for (index, guid) in guids.iter().enumerate() {
+        if deleted(index) {
+            assert_eq!(
+                cache.csv_for_guid(guid),
+                Err(CacheError::GuidNotFound(*guid))
+            );
+        } else {
+            assert_eq!(
+                cache.csv_for_guid(guid).unwrap(),
+                format!(
+                    "name {},true,{},,{}",
+                    index,
+                    index,
+                    create_flavors()[index % 3]
+                )
+            );
+        }
+    }
+}
+
+#[test]
+fn test_delete_row() {
+    let mut cache = create_cache().unwrap();
+    let guids = create_many_rows(&mut cache);
+    let (_, receiver) = cache.subscribe();
+
+    cache.delete_row(&guids[10]).unwrap();
+    cache.delete_row(&guids[99]).unwrap();
+    cache.delete_row(&guids[0]).unwrap();
+
+    assert_eq!(cache.row_len(), 97);
+    assert_rows_aligned(&cache, &guids, |index| [0, 10, 99].contains(&index));
+    assert_eq!(
+        cache.delete_row(&guids[10]),
+        Err(CacheError::GuidNotFound(guids[10]))
+    );
+    assert_eq!(
+        receiver.try_recv().unwrap(),
+        CacheEvent::RowRemoved { guid: guids[10] }
+    );
+}
+
+#[test]
+fn test_delete_rows() {
+    let mut cache = create_cache().unwrap();
+    let guids = create_many_rows(&mut cache);
+
+    let to_delete: Vec<Uuid> = guids.iter().step_by(7).copied().collect();
+    assert_eq!(cache.delete_rows(&to_delete), Ok(15));
+    assert_eq!(cache.row_len(), 85);
+    assert_rows_aligned(&cache, &guids, |index| index % 7 == 0);
+
+    // nothing is deleted when one of the guids is missing
+    let missing = Uuid::new_v4();
+    assert_eq!(
+        cache.delete_rows(&[guids[1], missing]),
+        Err(CacheError::GuidNotFound(missing))
+    );
+    assert_eq!(cache.row_len(), 85);
+
+    assert_eq!(cache.delete_rows(&[guids[1], guids[1]]), Ok(1));
+    assert_eq!(cache.row_len(), 84);
+}
+
+#[test]
+fn test_delete_where() {
+    let mut cache = create_cache().unwrap();
+    let guids = create_many_rows(&mut cache);
+
+    let deleted = cache
+        .delete_where(&[Predicate::equals("flavor", "chocolate")])
+        .unwrap();
+    assert_eq!(deleted.len(), 33);
+    assert_eq!(cache.row_len(), 67);
+    assert_rows_aligned(&cache, &guids, |index| index % 3 == 1);
+
+    let deleted = cache
+        .delete_where(&[
+            Predicate::equals("flavor", "vanilla"),
+            Predicate::range("age", "50", ""),
+        ])
+        .unwrap();
+    assert_eq!(deleted.len(), 17);
+    assert_rows_aligned(&cache, &guids, |index| {
+        index % 3 == 1 || (index % 3 == 0 && index >= 50)
+    });
+
+    assert_eq!(
+        cache.delete_where(&[Predicate::equals("color", "red")]),
+        Err(CacheError::ColumnNotFound("color".to_string()))
+    );
+    assert_eq!(cache.delete_where(&[]).unwrap().len(), 50);
+    assert_eq!(cache.row_len(), 0);
+}
@@ -0,0 +1,236 @@
+use arrow_array::{ArrayRef, Decimal128Array, DurationSecondArray, RecordBatch};
+use chrono::{Duration, NaiveDate, NaiveTime};
+use data_cache::api::cache::{Cache, CacheError};
+use data_cache::api::query::{Predicate, Query, SortDirection};
+use data_cache::api::value::Value;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const ORDER_ID: &str = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+
+fn create_cache() -> Result<Cache, CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "")?;
+    cache.add_i64_column("quantity", "Quantity", "0")?;
+    cache.add_decimal_column("price", "Price", 2, "")?;
+    cache.add_date_column("shipped", "Shipped", "%Y-%m-%d", "")?;
+    cache.add_time_column("delivery", "Delivery", "%H:%M", "")?;
+    cache.add_duration_column("transit", "Transit", "")?;
+    cache.add_uuid_column("order", "Order", "")?;
+
+    cache.create_row(&format!(
+        "fred,3,19.999,2024-02-29,09:30,26:00:00,{}",
+        ORDER_ID
+    ))?;
+    cache.create_row("wilma,,5,2024-03-01,17:45,-0:00:01.5,")?;
+    cache.create_row("barney,-12,,,,90,")?;
+
+    Ok(cache)
+}
+
+fn values(cache: &Cache, column: &str) -> Vec<Option<Value>> {
+    cache
+        .query(&Query::new().select(&[column]))
+        .unwrap()
+        .map(|row| row.values[0].clone())
+        .collect()
+}
+
+#[test]
+fn test_csv_round_trip() {
+    let cache = create_cache().unwrap();
+    let rows: Vec<String> = cache
+        .query(&Query::new())
+        .unwrap()
+        .map(|row| cache.csv_for_guid(&row.guid).unwrap())
+        .collect();
+
+    assert_eq!(
+        rows,
+        vec![
+            format!("fred,3,20.00,2024-02-29,09:30,26:00:00,{}", ORDER_ID),
+            "wilma,0,5.00,2024-03-01,17:45,-0:00:01.5,".to_string(),
+            "barney,-12,,,,0:01:30,".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_typed_values() {
+    let cache = create_cache().unwrap();
+    assert_eq!(
+        values(&cache, "quantity"),
+        vec![
+            Some(Value::I64(3)),
+            Some(Value::I64(0)),
+            Some(Value::I64(-12))
+        ]
+    );
+    assert_eq!(
+        values(&cache, "price")[0],
+        Some(Value::Decimal(Decimal::from_str("20.00").unwrap()))
+    );
+    assert_eq!(
+        values(&cache, "shipped")[0],
+        Some(Value::Date(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()))
+    );
+    assert_eq!(
+        values(&cache, "delivery")[1],
+        Some(Value::Time(NaiveTime::from_hms_opt(17, 45, 0).unwrap()))
+    );
+    assert_eq!(
+        values(&cache, "transit")[2],
+        Some(Value::Duration(Duration::seconds(90)))
+    );
+    assert_eq!(
+        values(&cache, "order")[0],
+        Some(Value::Uuid(Uuid::parse_str(ORDER_ID).unwrap()))
+    );
+}
+
+#[test]
+fn test_invalid_values() {
+    let mut cache = create_cache().unwrap();
+    assert!(cache.create_row("dino,1.5,,,,,").is_err());
+    assert!(cache.create_row("dino,,abc,,,,").is_err());
+    assert!(cache.create_row("dino,,,2024-02-30,,,").is_err());
+    assert!(cache.create_row("dino,,,,25:00,,").is_err());
+    assert!(cache.create_row("dino,,,,,1:99:00,").is_err());
+    assert!(cache
+        .create_row("dino,,,,,79228162514264337593543950335,")
+        .is_err());
+    assert!(cache.create_row("dino,,,,,,not-a-uuid").is_err());
+    assert_eq!(cache.row_len(), 3);
+}
+
+#[test]
+fn test_invalid_default_values() {
+    let mut cache = create_cache().unwrap();
+    assert!(cache.add_i64_column("count", "Count", "1.5").is_err());
+    assert!(cache.add_decimal_column("cost", "Cost", 2, "abc").is_err());
+    assert!(cache
+        .add_date_column("due", "Due", "%Y-%m-%d", "2024-02-30")
+        .is_err());
+    assert!(cache
+        .add_time_column("alarm", "Alarm", "%H:%M", "25:00")
+        .is_err());
+    assert!(cache
+        .add_duration_column("wait", "Wait", "1:99:00")
+        .is_err());
+    assert!(cache
+        .add_uuid_column("parcel", "Parcel", "not-a-uuid")
+        .is_err());
+    assert_eq!(cache.column_len(), 7);
+    assert_eq!(cache.row_len(), 3);
+}
+
+#[test]
+fn test_decimal_scale() {
+    let mut cache = Cache::new();
+    assert!(matches!(
+        cache.add_decimal_column("price", "Price", 29, ""),
+        Err(CacheError::UnsupportedType(_))
+    ));
+    assert_eq!(cache.column_len(), 0);
+
+    // 28 decimal places leave room for a single digit before the point
+    let largest = "7.9228162514264337593543950335";
+    cache.add_decimal_column("ratio", "Ratio", 28, "").unwrap();
+    let guid = cache.create_row(largest).unwrap();
+    assert!(matches!(
+        cache.create_row("1000"),
+        Err(CacheError::InvalidRow(_))
+    ));
+    assert_eq!(cache.row_len(), 1);
+
+    let loaded = Cache::from_record_batches(&[cache.to_record_batch().unwrap()]).unwrap();
+    assert_eq!(loaded.csv_for_guid(&guid).unwrap(), largest);
+}
+
+#[test]
+fn test_query_new_types() {
+    let cache = create_cache().unwrap();
+    let names: Vec<Option<Value>> = cache
+        .query(
+            &Query::new()
+                .filter(Predicate::range("transit", "0", ""))
+                .sort_by("quantity", SortDirection::Descending)
+                .select(&["name"]),
+        )
+        .unwrap()
+        .map(|row| row.values[0].clone())
+        .collect();
+
+    assert_eq!(
+        names,
+        vec![
+            Some(Value::String("fred".to_string())),
+            Some(Value::String("barney".to_string()))
+        ]
+    );
+}
+
+#[test]
+fn test_snapshot_round_trip() {
+    let cache = create_cache().unwrap();
+    let mut buffer = vec![];
+    cache.save_snapshot(&mut buffer).unwrap();
+
+    let loaded = Cache::load_snapshot(&mut buffer.as_slice()).unwrap();
+    assert_eq!(loaded.get_metadata(), cache.get_metadata());
+    for column in [
+        "quantity", "price", "shipped", "delivery", "transit", "order",
+    ] {
+        assert_eq!(values(&loaded, column), values(&cache, column));
+    }
+}
+
+#[test]
+fn test_record_batch_round_trip() {
+    let cache = create_cache().unwrap();
+    let batch = cache.to_record_batch().unwrap();
+
+    let loaded = Cache::from_record_batches(&[batch]).unwrap();
+    assert_eq!(loaded.get_metadata(), cache.get_metadata());
+    for column in [
+        "quantity", "price", "shipped", "delivery", "transit", "order",
+    ] {
+        assert_eq!(values(&loaded, column), values(&cache, column));
+    }
+}
+
+#[test]
+fn test_record_batch_values_out_of_range() {
+    // a Decimal128 holds 128 bits but a Decimal only 96
+    let too_large = 1_i128 << 96;
+    let largest = Decimal128Array::from(vec![too_large - 1])
+        .with_precision_and_scale(38, 2)
+        .unwrap();
+    let batch = RecordBatch::try_from_iter(vec![("price", Arc::new(largest) as ArrayRef)]).unwrap();
+    assert_eq!(Cache::from_record_batches(&[batch]).unwrap().row_len(), 1);
+
+    let prices = Decimal128Array::from(vec![Some(1), None, Some(too_large)])
+        .with_precision_and_scale(38, 2)
+        .unwrap();
+    let batch = RecordBatch::try_from_iter(vec![("price", Arc::new(prices) as ArrayRef)]).unwrap();
+    assert_eq!(
+        Cache::from_record_batches(&[batch]).err(),
+        Some(CacheError::InvalidColumnData {
+            column: "price".to_string(),
+            reason: format!("{} with scale 2 is too large for a decimal", too_large),
+        })
+    );
+
+    let transits = DurationSecondArray::from(vec![Some(90), Some(i64::MAX)]);
+    let batch =
+        RecordBatch::try_from_iter(vec![("transit", Arc::new(transits) as ArrayRef)]).unwrap();
+    assert_eq!(
+        Cache::from_record_batches(&[batch]).err(),
+        Some(CacheError::InvalidColumnData {
+            column: "transit".to_string(),
+            reason: format!("{} seconds is not a duration", i64::MAX),
+        })
+    );
+}
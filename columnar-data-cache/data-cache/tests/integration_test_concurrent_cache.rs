@@ -0,0 +1,119 @@
+use data_cache::api::cache::Cache;
+use data_cache::api::concurrent_cache::ConcurrentCache;
+use data_cache::api::query::Query;
+use data_cache::api::value::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use uuid::Uuid;
+
+const ROW_COUNT: usize = 200;
+const BATCH_COUNT: usize = 100;
+const READER_COUNT: usize = 4;
+
+fn create_cache() -> (Cache, Vec<Uuid>) {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "").unwrap();
+    cache.add_f64_column("left", "Left", "").unwrap();
+    cache.add_f64_column("right", "Right", "").unwrap();
+
+    let guids = (0..ROW_COUNT)
+        .map(|index| cache.create_row(&format!("row {},0,0", index)).unwrap())
+        .collect();
+    (cache, guids)
+}
+
+fn get_f64(value: &Option<Value>) -> f64 {
+    match value {
+        Some(Value::F64(value)) => *value,
+        _ => panic!("expected a f64 value"),
+    }
+}
+
+#[test]
+fn test_no_torn_rows_or_batches() {
+    let (cache, guids) = create_cache();
+    let concurrent_cache = ConcurrentCache::new(cache);
+    let done = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        for _ in 0..READER_COUNT {
+            scope.spawn(|| {
+                let mut last_version = 0.0;
+                let mut snapshot_count = 0;
+                while !done.load(Ordering::Acquire) || snapshot_count == 0 {
+                    let snapshot = concurrent_cache.snapshot();
+                    let rows: Vec<_> = snapshot
+                        .query(&Query::new().select(&["left", "right"]))
+                        .unwrap()
+                        .collect();
+                    assert_eq!(rows.len(), ROW_COUNT);
+
+                    let version = get_f64(&rows[0].values[0]);
+                    for row in &rows {
+                        // both cells of a row and every row of a batch are written together
+                        assert_eq!(get_f64(&row.values[0]), get_f64(&row.values[1]));
+                        assert_eq!(get_f64(&row.values[0]), version);
+                    }
+                    assert!(version >= last_version);
+                    last_version = version;
+                    snapshot_count += 1;
+                }
+            });
+        }
+
+        scope.spawn(|| {
+            for version in 1..=BATCH_COUNT {
+                concurrent_cache.write(|cache| {
+                    for (index, guid) in guids.iter().enumerate() {
+                        cache
+                            .update_row(guid, &format!("row {},{},{}", index, version, version))
+                            .unwrap();
+                    }
+                });
+            }
+            done.store(true, Ordering::Release);
+        });
+    });
+
+    let snapshot = concurrent_cache.snapshot();
+    assert_eq!(
+        snapshot.csv_for_guid(&guids[0]).unwrap(),
+        format!("row 0,{},{}", BATCH_COUNT, BATCH_COUNT)
+    );
+}
+
+#[test]
+fn test_appends_are_published_per_batch() {
+    let concurrent_cache = ConcurrentCache::default();
+    concurrent_cache.write(|cache| cache.add_string_column("name", "Name", "").unwrap());
+    let done = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        for _ in 0..READER_COUNT {
+            scope.spawn(|| {
+                while !done.load(Ordering::Acquire) {
+                    let snapshot = concurrent_cache.snapshot();
+                    assert_eq!(snapshot.row_len() % 10, 0);
+                    for row in snapshot.query(&Query::new()).unwrap() {
+                        assert!(snapshot.csv_for_guid(&row.guid).is_ok());
+                    }
+                }
+            });
+        }
+
+        scope.spawn(|| {
+            for batch in 0..BATCH_COUNT {
+                concurrent_cache.write(|cache| {
+                    for index in 0..10 {
+                        cache
+                            .create_row(&format!("batch {} row {}", batch, index))
+                            .unwrap();
+                    }
+                });
+            }
+            done.store(true, Ordering::Release);
+        });
+    });
+
+    assert_eq!(concurrent_cache.snapshot().row_len(), BATCH_COUNT * 10);
+}
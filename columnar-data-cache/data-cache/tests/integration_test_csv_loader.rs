@@ -0,0 +1,190 @@
+mod common;
+
+use common::create_flavors;
+use data_cache::api::cache::{Cache, CacheError, Expected, FieldError};
+use data_cache::api::csv_loader::LineError;
+
+fn create_cache() -> Result<Cache, CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "unknown")?;
+    cache.add_boolean_column("verified", "Verified", "false")?;
+    cache.add_f64_column("age", "Age", "0")?;
+    cache.add_time_date_column("start_time", "Start Time", "%Y-%m-%d %H:%M:%S", "")?;
+    cache.add_enumerated_column("flavor", "Flavor", "vanilla", create_flavors())?;
+    cache.add_string_column("address", "Address", "bedrock")?;
+
+    Ok(cache)
+}
+
+#[test]
+fn test_load_csv_file() {
+    let mut cache = create_cache().unwrap();
+    let report = cache.load_csv_file("tests/data/people.csv").unwrap();
+
+    assert!(!report.is_ok());
+    assert_eq!(report.created.len(), 4);
+    assert_eq!(cache.row_len(), 4);
+    assert_eq!(report.ignored_headers, vec!["shoe_size".to_string()]);
+    assert_eq!(report.missing_columns, vec!["address".to_string()]);
+
+    assert_eq!(
+        cache.csv_for_guid(&report.created[0]).unwrap(),
+        "fred,true,40,2019-01-01 00:00:00,chocolate,bedrock"
+    );
+    assert_eq!(
+        cache.csv_for_guid(&report.created[2]).unwrap(),
+        "\"Rubble, Barney\",false,41,2020-01-01 00:00:00,strawberry,bedrock"
+    );
+    assert_eq!(
+        cache.csv_for_guid(&report.created[3]).unwrap(),
+        "bamm-bamm,false,3,,vanilla,bedrock"
+    );
+
+    let failed_lines: Vec<u64> = report.errors.iter().map(|error| error.line).collect();
+    assert_eq!(failed_lines, vec![5, 6]);
+    assert!(report.errors[0]
+        .error
+        .to_string()
+        .ends_with("(ParseError: invalid float literal)"));
+}
+
+#[test]
+fn test_load_csv_reader() {
+    let mut cache = create_cache().unwrap();
+    let data = "address,name\n\"1 Main St, Bedrock\",fred\n,wilma\n";
+    let report = cache.load_csv(data.as_bytes()).unwrap();
+
+    assert!(report.is_ok());
+    assert!(report.ignored_headers.is_empty());
+    assert_eq!(
+        cache.csv_for_guid(&report.created[0]).unwrap(),
+        "fred,false,0,,vanilla,\"1 Main St, Bedrock\""
+    );
+    assert_eq!(
+        cache.csv_for_guid(&report.created[1]).unwrap(),
+        "wilma,false,0,,vanilla,bedrock"
+    );
+}
+
+#[test]
+fn test_load_csv_uneven_record() {
+    let mut cache = create_cache().unwrap();
+    let data = "name,age\nfred,1\nwilma\nbarney,3\n";
+    let report = cache.load_csv(data.as_bytes()).unwrap();
+
+    assert_eq!(report.created.len(), 2);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].line, 3);
+}
+
+#[test]
+fn test_load_csv_missing_file() {
+    let mut cache = create_cache().unwrap();
+    let result = cache.load_csv_file("tests/data/no_such_file.csv");
+    assert!(matches!(result, Err(CacheError::IoError(..))));
+}
+
+#[test]
+fn test_line_error() {
+    let mut cache = create_cache().unwrap();
+    let report = cache
+        .load_csv("name,flavor\nfred,mint\n".as_bytes())
+        .unwrap();
+    assert_eq!(
+        report.errors,
+        vec![LineError {
+            line: 2,
+            error: CacheError::InvalidRow(vec![FieldError {
+                column: "flavor".to_string(),
+                position: 4,
+                value: "mint".to_string(),
+                expected: Expected::AllowedValues(create_flavors()),
+                error: Box::new(CacheError::NotAllowed("mint".to_string())),
+            }])
+        }]
+    );
+}
+
+#[test]
+fn test_csv_round_trip() {
+    let mut cache = create_cache().unwrap();
+    let header = "name,verified,age,start_time,flavor,address";
+    let data = format!(
+        "{}\n\" Rubble \",true,41,,strawberry,\"Rubble, Barney said \"\"yabba\"\"\"\n",
+        header
+    );
+    let report = cache.load_csv(data.as_bytes()).unwrap();
+    assert!(report.is_ok());
+
+    let row = cache.get_row(&report.created[0]).unwrap();
+    assert_eq!(row.get_string("name"), Ok(Some("Rubble".to_string())));
+    assert_eq!(
+        row.get_string("address"),
+        Ok(Some("Rubble, Barney said \"yabba\"".to_string()))
+    );
+
+    let csv = cache.csv_for_guid(&report.created[0]).unwrap();
+    let mut copy = create_cache().unwrap();
+    let report = copy
+        .load_csv(format!("{}\n{}\n", header, csv).as_bytes())
+        .unwrap();
+    assert!(report.is_ok());
+    assert_eq!(copy.csv_for_guid(&report.created[0]).unwrap(), csv);
+    assert_eq!(
+        copy.get_row(&report.created[0])
+            .unwrap()
+            .get_string("address"),
+        Ok(Some("Rubble, Barney said \"yabba\"".to_string()))
+    );
+}
+
+#[test]
+fn test_csv_for_guid_updates_the_same_row() {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "").unwrap();
+    cache
+        .add_boolean_column("verified", "Verified", "")
+        .unwrap();
+    cache.add_f64_column("age", "Age", "").unwrap();
+    cache
+        .add_time_date_column("start_time", "Start Time", "%m/%d/%Y %H:%M", "")
+        .unwrap();
+    cache
+        .add_enumerated_column("flavor", "Flavor", "", create_flavors())
+        .unwrap();
+    cache.add_i64_column("count", "Count", "").unwrap();
+    cache.add_decimal_column("price", "Price", 2, "").unwrap();
+    cache
+        .add_date_column("shipped", "Shipped", "%d.%m.%Y", "")
+        .unwrap();
+    cache
+        .add_time_column("delivery", "Delivery", "%I:%M %p", "")
+        .unwrap();
+    cache.add_duration_column("transit", "Transit", "").unwrap();
+    cache.add_uuid_column("order", "Order", "").unwrap();
+
+    let rows = [
+        "\" Flintstone, \"\"Fred\"\" \",true, 1.5,01/02/2024 10:30,chocolate,-3,19.999,\
+         29.02.2024,09:30 PM,-26:00:00.5,67e55044-10b1-426f-9247-bb680e5fe0c8",
+        ",,,,,,,,,,",
+    ];
+    let mut csvs = vec![];
+    for row in rows {
+        let guid = cache.create_row(row).unwrap();
+        let csv = cache.csv_for_guid(&guid).unwrap();
+        let values = cache.get_row(&guid).unwrap().values;
+
+        cache.update_row(&guid, &csv).unwrap();
+        assert_eq!(cache.csv_for_guid(&guid).unwrap(), csv);
+        assert_eq!(cache.get_row(&guid).unwrap().values, values);
+        csvs.push(csv);
+    }
+    assert_eq!(
+        csvs,
+        vec![
+            "\"Flintstone, \"\"Fred\"\"\",true,1.5,01/02/2024 10:30,chocolate,-3,20.00,\
+             29.02.2024,09:30 PM,-26:00:00.5,67e55044-10b1-426f-9247-bb680e5fe0c8",
+            ",,,,,,,,,,",
+        ]
+    );
+}
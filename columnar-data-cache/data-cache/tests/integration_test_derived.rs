@@ -0,0 +1,231 @@
+use chrono::NaiveDateTime;
+use data_cache::api::cache::{Cache, CacheError};
+use data_cache::api::migration::ColumnType;
+use data_cache::api::query::{Query, SortDirection};
+use data_cache::api::value::Value;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+fn body_mass_index(values: &[Option<Value>]) -> Option<Value> {
+    match values {
+        [Some(Value::F64(weight)), Some(Value::F64(height))] if *height > 0.0 => {
+            let metres = height / 100.0;
+            Some(Value::F64(
+                (weight / (metres * metres) * 10.0).round() / 10.0,
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn age_in_days(values: &[Option<Value>]) -> Option<Value> {
+    let today = NaiveDateTime::parse_from_str("2020-01-31 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+    match values {
+        [Some(Value::TimeDate(born))] => Some(Value::I64((today - *born).num_days())),
+        _ => None,
+    }
+}
+
+fn create_cache() -> Result<Cache, CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "")?;
+    cache.add_f64_column("weight", "Weight", "")?;
+    cache.add_f64_column("height", "Height", "")?;
+    cache.add_time_date_column("born", "Born", "%Y-%m-%d %H:%M:%S", "")?;
+    Ok(cache)
+}
+
+#[test]
+fn test_derived_values() {
+    let mut cache = create_cache().unwrap();
+    let fred = cache.create_row("fred,80,200,2020-01-01 00:00:00").unwrap();
+
+    cache
+        .add_derived_column(
+            "bmi",
+            "BMI",
+            ColumnType::F64,
+            &["weight", "height"],
+            body_mass_index,
+        )
+        .unwrap();
+    cache
+        .add_derived_column("age", "Age", ColumnType::I64, &["born"], age_in_days)
+        .unwrap();
+    assert_eq!(
+        cache.csv_for_guid(&fred).unwrap(),
+        "fred,80,200,2020-01-01 00:00:00,20,30"
+    );
+
+    // values supplied for derived columns are ignored
+    let wilma = cache
+        .create_row("wilma,60,,2020-01-30 00:00:00,99,99")
+        .unwrap();
+    assert_eq!(
+        cache.csv_for_guid(&wilma).unwrap(),
+        "wilma,60,,2020-01-30 00:00:00,,1"
+    );
+
+    cache
+        .update_row(&wilma, "wilma,60,150,2020-01-29 00:00:00,,")
+        .unwrap();
+    let row = cache.get_row(&wilma).unwrap();
+    assert_eq!(row.get_f64("bmi"), Ok(Some(26.7)));
+    assert_eq!(row.get_i64("age"), Ok(Some(2)));
+
+    let query = Query::new()
+        .sort_by("bmi", SortDirection::Descending)
+        .select(&["name"]);
+    let names: Vec<Option<Value>> = cache
+        .query(&query)
+        .unwrap()
+        .map(|row| row.values[0].clone())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            Some(Value::String("wilma".to_string())),
+            Some(Value::String("fred".to_string()))
+        ]
+    );
+}
+
+#[test]
+fn test_derived_metadata() {
+    let mut cache = create_cache().unwrap();
+    cache
+        .add_derived_column(
+            "bmi",
+            "BMI",
+            ColumnType::F64,
+            &["weight", "height"],
+            body_mass_index,
+        )
+        .unwrap();
+
+    let metadata = cache.get_metadata();
+    assert!(!metadata[1].derived);
+    assert!(metadata[4].derived);
+    assert_eq!(metadata[4].name, "bmi");
+}
+
+#[test]
+fn test_wrong_computed_type() {
+    let mut cache = create_cache().unwrap();
+    cache.create_row("fred,80,200,").unwrap();
+
+    assert!(matches!(
+        cache.add_derived_column(
+            "bmi",
+            "BMI",
+            ColumnType::String,
+            &["weight", "height"],
+            body_mass_index
+        ),
+        Err(CacheError::TypeMismatch { .. })
+    ));
+    assert_eq!(cache.column_len(), 4);
+
+    cache
+        .add_derived_column(
+            "label",
+            "Label",
+            ColumnType::String,
+            &["weight"],
+            |values| match values {
+                [Some(Value::F64(weight))] if *weight > 100.0 => Some(Value::F64(*weight)),
+                _ => Some(Value::String("light".to_string())),
+            },
+        )
+        .unwrap();
+    match cache.create_row("wilma,120,150,,") {
+        Err(CacheError::InvalidRow(field_errors)) => {
+            assert_eq!(field_errors.len(), 1);
+            assert_eq!(field_errors[0].column, "label");
+        }
+        result => panic!("expected an invalid row, got {:?}", result),
+    }
+    assert_eq!(cache.row_count(), 1);
+}
+
+#[test]
+fn test_source_columns() {
+    let mut cache = create_cache().unwrap();
+    assert_eq!(
+        cache.add_derived_column(
+            "bmi",
+            "BMI",
+            ColumnType::F64,
+            &["weight", "size"],
+            body_mass_index
+        ),
+        Err(CacheError::ColumnNotFound("size".to_string()))
+    );
+
+    cache
+        .add_derived_column(
+            "bmi",
+            "BMI",
+            ColumnType::F64,
+            &["weight", "height"],
+            body_mass_index,
+        )
+        .unwrap();
+    assert!(matches!(
+        cache.remove_column("height"),
+        Err(CacheError::NotAllowed(_))
+    ));
+    assert!(matches!(
+        cache.migrate_column("weight", ColumnType::I64, ""),
+        Err(CacheError::NotAllowed(_))
+    ));
+    assert!(matches!(
+        cache.migrate_column("bmi", ColumnType::String, ""),
+        Err(CacheError::NotAllowed(_))
+    ));
+
+    // the derivation follows a renamed source
+    cache.rename_column("height", "height_cm").unwrap();
+    let guid = cache.create_row("fred,80,200,,").unwrap();
+    assert_eq!(cache.get_row(&guid).unwrap().get_f64("bmi"), Ok(Some(20.0)));
+
+    cache.remove_column("bmi").unwrap();
+    cache.remove_column("height_cm").unwrap();
+    assert_eq!(cache.column_len(), 3);
+}
+
+#[test]
+fn test_derived_decimal_is_rounded_to_scale() {
+    let mut cache = Cache::new();
+    cache.add_decimal_column("total", "Total", 2, "").unwrap();
+    let guid = cache.create_row("1.00").unwrap();
+    cache
+        .add_derived_column(
+            "share",
+            "Share",
+            ColumnType::Decimal { scale: 2 },
+            &["total"],
+            |values| match values {
+                [Some(Value::Decimal(total))] => Some(Value::Decimal(total / Decimal::from(3))),
+                _ => None,
+            },
+        )
+        .unwrap();
+
+    // every output path has the same rounded value
+    assert_eq!(cache.csv_for_guid(&guid).unwrap(), "1.00,0.33");
+    let loaded = Cache::from_record_batches(&[cache.to_record_batch().unwrap()]).unwrap();
+    assert_eq!(loaded.csv_for_guid(&guid).unwrap(), "1.00,0.33");
+
+    // a value too large for the scale is rejected
+    cache
+        .add_derived_column(
+            "huge",
+            "Huge",
+            ColumnType::Decimal { scale: 28 },
+            &["total"],
+            |_| Some(Value::Decimal(Decimal::from_str("1000").unwrap())),
+        )
+        .unwrap_err();
+    assert_eq!(cache.column_len(), 2);
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use arrow_array::types::Int32Type;
+use arrow_array::{ArrayRef, DictionaryArray, RecordBatch};
+use common::create_flavors;
+use data_cache::api::cache::{Cache, CacheError};
+use data_cache::api::column_storage::ColumnStorageDataType;
+use data_cache::api::query::{Predicate, Query, SortDirection};
+use data_cache::api::value::Value;
+use std::sync::Arc;
+
+fn create_cache() -> Result<Cache, CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "")?;
+    cache.add_enumerated_column("flavor", "Flavor", "", create_flavors())?;
+
+    cache.create_row("fred,strawberry")?;
+    cache.create_row("wilma,vanilla")?;
+    cache.create_row("barney,")?;
+    cache.create_row("betty,chocolate")?;
+    cache.create_row("pebbles,vanilla")?;
+
+    Ok(cache)
+}
+
+fn names(cache: &Cache, query: Query) -> Vec<String> {
+    cache
+        .query(&query.select(&["name"]))
+        .unwrap()
+        .map(|row| row.values[0].as_ref().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_filter_on_codes() {
+    let cache = create_cache().unwrap();
+    assert_eq!(
+        names(
+            &cache,
+            Query::new().filter(Predicate::equals("flavor", "vanilla"))
+        ),
+        vec!["wilma", "pebbles"]
+    );
+    assert_eq!(
+        names(&cache, Query::new().filter(Predicate::equals("flavor", ""))),
+        vec!["barney"]
+    );
+    assert_eq!(
+        names(
+            &cache,
+            Query::new().filter(Predicate::one_of("flavor", &["chocolate", "mint", ""]))
+        ),
+        vec!["betty"]
+    );
+    assert!(names(
+        &cache,
+        Query::new().filter(Predicate::equals("flavor", "mint"))
+    )
+    .is_empty());
+}
+
+#[test]
+fn test_sort_by_value_not_code() {
+    let cache = create_cache().unwrap();
+    assert_eq!(
+        names(
+            &cache,
+            Query::new()
+                .sort_by("flavor", SortDirection::Ascending)
+                .sort_by("name", SortDirection::Ascending)
+        ),
+        vec!["barney", "betty", "fred", "pebbles", "wilma"]
+    );
+    assert_eq!(
+        names(
+            &cache,
+            Query::new().filter(Predicate::range("flavor", "d", "w"))
+        ),
+        vec!["fred", "wilma", "pebbles"]
+    );
+}
+
+#[test]
+fn test_values_survive_row_removal() {
+    let mut cache = create_cache().unwrap();
+    let guid = cache.create_row("dino,chocolate").unwrap();
+    let updated = cache.update_row(&guid, "dino,strawberry").unwrap();
+    assert_eq!(cache.csv_for_guid(&updated).unwrap(), "dino,strawberry");
+
+    let flavors: Vec<Option<Value>> = cache
+        .query(&Query::new().select(&["flavor"]))
+        .unwrap()
+        .map(|row| row.values[0].clone())
+        .collect();
+    assert_eq!(flavors[2], None);
+    assert_eq!(flavors[3], Some(Value::Enumerated("chocolate".to_string())));
+}
+
+#[test]
+fn test_batches_with_different_dictionaries() {
+    let first = RecordBatch::try_from_iter(vec![(
+        "flavor",
+        Arc::new(DictionaryArray::<Int32Type>::from_iter(["mint", "lime"])) as ArrayRef,
+    )])
+    .unwrap();
+    let second = RecordBatch::try_from_iter(vec![(
+        "flavor",
+        Arc::new(DictionaryArray::<Int32Type>::from_iter([
+            "peach", "lime", "mint",
+        ])) as ArrayRef,
+    )])
+    .unwrap();
+
+    let cache = Cache::from_record_batches(&[first, second]).unwrap();
+    assert_eq!(
+        cache.get_metadata()[0].allowed_values,
+        vec!["mint", "lime", "peach"]
+    );
+    let rows: Vec<String> = cache
+        .query(&Query::new())
+        .unwrap()
+        .map(|row| cache.csv_for_guid(&row.guid).unwrap())
+        .collect();
+    assert_eq!(rows, vec!["mint", "lime", "peach", "lime", "mint"]);
+}
+
+#[test]
+fn test_memory_usage() {
+    let mut cache = Cache::new();
+    cache.add_string_column("plain", "Plain", "").unwrap();
+    cache
+        .add_enumerated_column("flavor", "Flavor", "", create_flavors())
+        .unwrap();
+    let flavors = create_flavors();
+    for index in 0..1000 {
+        let flavor = &flavors[index % flavors.len()];
+        cache.create_row(&format!("{},{}", flavor, flavor)).unwrap();
+    }
+
+    let report = cache.memory_usage();
+    assert_eq!(report.columns.len(), 2);
+    assert_eq!(report.columns[1].name, "flavor");
+    assert_eq!(
+        report.columns[1].data_type,
+        ColumnStorageDataType::Enumerated
+    );
+    assert!(report.columns[1].total_bytes() * 5 < report.columns[0].total_bytes());
+    assert!(report.guid_bytes >= 1000 * 16);
+    assert!(report.total_bytes() > report.guid_bytes);
+}
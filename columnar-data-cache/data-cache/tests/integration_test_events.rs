@@ -0,0 +1,105 @@
+use data_cache::api::cache::{Cache, CacheError};
+use data_cache::api::events::{CacheEvent, CellChange};
+use data_cache::api::value::Value;
+use std::sync::{Arc, Mutex};
+
+fn create_cache() -> Result<Cache, CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "unknown")?;
+    cache.add_f64_column("age", "Age", "0")?;
+    Ok(cache)
+}
+
+#[test]
+fn test_channel_events() {
+    let mut cache = create_cache().unwrap();
+    let (_, receiver) = cache.subscribe();
+
+    let guid = cache.create_row("fred,1").unwrap();
+    cache.update_row(&guid, "fred,2").unwrap();
+    cache
+        .add_boolean_column("verified", "Verified", "false")
+        .unwrap();
+
+    let events: Vec<CacheEvent> = receiver.try_iter().collect();
+    assert_eq!(
+        events,
+        vec![
+            CacheEvent::RowCreated { guid },
+            CacheEvent::RowUpdated {
+                guid,
+                changes: vec![CellChange {
+                    column: "age".to_string(),
+                    before: Some(Value::F64(1.0)),
+                    after: Some(Value::F64(2.0)),
+                }]
+            },
+            CacheEvent::ColumnAdded {
+                name: "verified".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_callback_events() {
+    let mut cache = create_cache().unwrap();
+    let created = Arc::new(Mutex::new(vec![]));
+    let observed = created.clone();
+    let id = cache.add_observer(move |event: &CacheEvent| {
+        if let CacheEvent::RowCreated { guid } = event {
+            observed.lock().unwrap().push(*guid);
+        }
+    });
+
+    let fred_guid = cache.create_row("fred,1").unwrap();
+    let wilma_guid = cache.create_row("wilma,2").unwrap();
+    assert!(cache.remove_observer(id));
+    cache.create_row("barney,3").unwrap();
+
+    assert_eq!(*created.lock().unwrap(), vec![fred_guid, wilma_guid]);
+}
+
+#[test]
+fn test_no_events_for_failures() {
+    let mut cache = create_cache().unwrap();
+    let guid = cache.create_row("fred,1").unwrap();
+    let (_, receiver) = cache.subscribe();
+
+    assert!(cache.create_row("wilma,old").is_err());
+    assert!(cache.update_row(&guid, "fred,old").is_err());
+    assert!(cache.add_string_column("name", "Name", "").is_err());
+    assert_eq!(receiver.try_iter().count(), 0);
+}
+
+#[test]
+fn test_unchanged_update() {
+    let mut cache = create_cache().unwrap();
+    let guid = cache.create_row("fred,1").unwrap();
+    let (_, receiver) = cache.subscribe();
+
+    cache.update_row(&guid, "fred,1").unwrap();
+    assert_eq!(
+        receiver.try_recv(),
+        Ok(CacheEvent::RowUpdated {
+            guid,
+            changes: vec![]
+        })
+    );
+}
+
+#[test]
+fn test_csv_load_events() {
+    let mut cache = create_cache().unwrap();
+    let (_, receiver) = cache.subscribe();
+    let report = cache
+        .load_csv("name,age\nfred,1\nwilma,x\nbarney,3\n".as_bytes())
+        .unwrap();
+
+    let created: Vec<CacheEvent> = report
+        .created
+        .iter()
+        .map(|guid| CacheEvent::RowCreated { guid: *guid })
+        .collect();
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), created);
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use chrono::NaiveDateTime;
+use common::create_flavors;
+use data_cache::api::cache::{Cache, CacheError};
+use data_cache::api::column_storage::ColumnStorageDataType;
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::str::FromStr;
+
+fn create_cache() -> Result<Cache, CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "")?;
+    cache.add_boolean_column("verified", "Verified", "")?;
+    cache.add_f64_column("weight", "Weight", "")?;
+    cache.add_time_date_column("start_time", "Start Time", "%Y-%m-%d %H:%M:%S", "")?;
+    cache.add_enumerated_column("flavor", "Flavor", "vanilla", create_flavors())?;
+    cache.add_decimal_column("balance", "Balance", 2, "")?;
+    Ok(cache)
+}
+
+#[test]
+fn test_typed_row() {
+    let mut cache = create_cache().unwrap();
+    let guid = cache
+        .create_row("fred,true,200.5,2020-01-01 10:00:00,chocolate,10.5")
+        .unwrap();
+
+    let row = cache.get_row(&guid).unwrap();
+    assert_eq!(row.guid, guid);
+    assert_eq!(row.get_string("name"), Ok(Some("fred".to_string())));
+    assert_eq!(row.get_bool("verified"), Ok(Some(true)));
+    assert_eq!(row.get_f64("weight"), Ok(Some(200.5)));
+    assert_eq!(
+        row.get_datetime("start_time"),
+        Ok(Some(
+            NaiveDateTime::parse_from_str("2020-01-01 10:00:00", "%Y-%m-%d %H:%M:%S").unwrap()
+        ))
+    );
+    assert_eq!(
+        row.get_enumerated("flavor"),
+        Ok(Some("chocolate".to_string()))
+    );
+    assert_eq!(
+        row.get_decimal("balance"),
+        Ok(Some(Decimal::from_str("10.50").unwrap()))
+    );
+    assert_eq!(
+        row.get_bool("weight"),
+        Err(CacheError::TypeMismatch {
+            column: "weight".to_string(),
+            expected: ColumnStorageDataType::Boolean,
+            actual: ColumnStorageDataType::F64,
+        })
+    );
+}
+
+#[test]
+fn test_row_to_json() {
+    let mut cache = create_cache().unwrap();
+    let guid = cache
+        .create_row("fred,true,200.5,2020-01-01 10:00:00,,10.5")
+        .unwrap();
+    let empty_guid = cache.create_row(",,,,,").unwrap();
+
+    assert_eq!(
+        cache.json_for_guid(&guid).unwrap(),
+        json!({
+            "_guid": guid.to_string(),
+            "name": "fred",
+            "verified": true,
+            "weight": 200.5,
+            "start_time": "2020-01-01 10:00:00",
+            "flavor": "vanilla",
+            "balance": "10.50",
+        })
+    );
+    assert_eq!(
+        cache.json_for_guid(&empty_guid).unwrap(),
+        json!({
+            "_guid": empty_guid.to_string(),
+            "name": null,
+            "verified": null,
+            "weight": null,
+            "start_time": null,
+            "flavor": "vanilla",
+            "balance": null,
+        })
+    );
+
+    let all = cache.to_json().unwrap();
+    assert_eq!(all.as_array().unwrap().len(), 2);
+    assert_eq!(all[1]["_guid"], json!(empty_guid.to_string()));
+}
+
+#[test]
+fn test_create_and_update_from_json() {
+    let mut cache = create_cache().unwrap();
+    let guid = cache
+        .create_row_from_json(&json!({
+            "name": "Rubble, Barney",
+            "verified": false,
+            "weight": 180,
+            "balance": "-3.333",
+        }))
+        .unwrap();
+    assert_eq!(
+        cache.csv_for_guid(&guid).unwrap(),
+        "\"Rubble, Barney\",false,180,,vanilla,-3.33"
+    );
+
+    cache
+        .update_row_from_json(
+            &guid,
+            &json!({"_guid": guid.to_string(), "name": "barney", "flavor": "strawberry"}),
+        )
+        .unwrap();
+    assert_eq!(cache.csv_for_guid(&guid).unwrap(), "barney,,,,strawberry,");
+
+    // an explicit null is kept while a missing key gets the default
+    cache
+        .update_row_from_json(&guid, &json!({"name": "barney", "flavor": null}))
+        .unwrap();
+    assert_eq!(cache.csv_for_guid(&guid).unwrap(), "barney,,,,,");
+    let other_guid = cache.create_row_from_json(&json!({"name": null})).unwrap();
+    assert_eq!(cache.csv_for_guid(&other_guid).unwrap(), ",,,,vanilla,");
+
+    // the json output can be read back in
+    let json = cache.json_for_guid(&guid).unwrap();
+    let copy = cache.create_row_from_json(&json).unwrap();
+    assert_eq!(
+        cache.get_row(&copy).unwrap().values,
+        cache.get_row(&guid).unwrap().values
+    );
+}
+
+#[test]
+fn test_json_uses_the_time_date_format() {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "").unwrap();
+    cache
+        .add_time_date_column("start_time", "Start Time", "%m/%d/%Y %H:%M", "")
+        .unwrap();
+    let guid = cache.create_row("fred,01/02/2024 10:30").unwrap();
+
+    let json = cache.json_for_guid(&guid).unwrap();
+    assert_eq!(json["start_time"], json!("01/02/2024 10:30"));
+    cache.update_row_from_json(&guid, &json).unwrap();
+    assert_eq!(cache.json_for_guid(&guid).unwrap(), json);
+}
+
+#[test]
+fn test_invalid_json_rows() {
+    let mut cache = create_cache().unwrap();
+    assert_eq!(
+        cache.create_row_from_json(&json!({"height": 3})),
+        Err(CacheError::ColumnNotFound("height".to_string()))
+    );
+    assert!(matches!(
+        cache.create_row_from_json(&json!(["fred"])),
+        Err(CacheError::ParseError(_))
+    ));
+    assert!(matches!(
+        cache.create_row_from_json(&json!({"name": ["fred"]})),
+        Err(CacheError::ParseError(_))
+    ));
+    assert!(matches!(
+        cache.create_row_from_json(&json!({"verified": "maybe"})),
+        Err(CacheError::InvalidRow(_))
+    ));
+    assert_eq!(cache.row_count(), 0);
+}
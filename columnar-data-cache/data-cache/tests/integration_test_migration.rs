@@ -0,0 +1,177 @@
+use data_cache::api::cache::{Cache, CacheError};
+use data_cache::api::column_storage::ColumnStorageDataType;
+use data_cache::api::events::CacheEvent;
+use data_cache::api::migration::ColumnType;
+use data_cache::api::query::{Predicate, Query};
+use data_cache::api::value::Value;
+
+fn create_cache() -> Result<Cache, CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "")?;
+    cache.add_string_column("flavor", "Flavor", "")?;
+    cache.add_string_column("scoops", "Scoops", "")?;
+
+    cache.create_row("fred,chocolate,2")?;
+    cache.create_row("wilma,vanilla,1")?;
+    cache.create_row("barney,chocolate,3")?;
+    cache.create_row("betty,,")?;
+
+    Ok(cache)
+}
+
+fn all_rows(cache: &Cache) -> Vec<String> {
+    cache
+        .query(&Query::new())
+        .unwrap()
+        .map(|row| cache.csv_for_guid(&row.guid).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_remove_column() {
+    let mut cache = create_cache().unwrap();
+    cache.remove_column("flavor").unwrap();
+
+    assert_eq!(cache.column_len(), 2);
+    assert_eq!(cache.row_len(), 4);
+    assert_eq!(all_rows(&cache)[0], "fred,2");
+    assert_eq!(
+        cache.remove_column("flavor"),
+        Err(CacheError::ColumnNotFound("flavor".to_string()))
+    );
+
+    let guid = cache.create_row("pebbles,1").unwrap();
+    assert_eq!(cache.csv_for_guid(&guid).unwrap(), "pebbles,1");
+}
+
+#[test]
+fn test_rename_column() {
+    let mut cache = create_cache().unwrap();
+    cache.rename_column("scoops", "count").unwrap();
+    cache.set_display_name("count", "Scoop Count").unwrap();
+
+    let metadata = cache.get_metadata();
+    assert_eq!(metadata[2].name, "count");
+    assert_eq!(metadata[2].display_name, "Scoop Count");
+    assert_eq!(
+        cache.rename_column("count", "name"),
+        Err(CacheError::DuplicateColumn("name".to_string()))
+    );
+    assert!(cache.rename_column("scoops", "other").is_err());
+
+    let matches = cache
+        .query(&Query::new().filter(Predicate::equals("count", "3")))
+        .unwrap()
+        .count();
+    assert_eq!(matches, 1);
+}
+
+#[test]
+fn test_set_default_value() {
+    let mut cache = create_cache().unwrap();
+    cache.migrate_column("scoops", ColumnType::I64, "").unwrap();
+    assert!(cache.set_default_value("scoops", "lots").is_err());
+    cache.set_default_value("scoops", "1").unwrap();
+
+    // existing rows keep their values
+    assert_eq!(all_rows(&cache)[3], "betty,,");
+    let guid = cache.create_row("pebbles,,").unwrap();
+    assert_eq!(cache.csv_for_guid(&guid).unwrap(), "pebbles,,1");
+}
+
+#[test]
+fn test_promote_to_enumerated() {
+    let mut cache = create_cache().unwrap();
+    let flavors = cache.distinct_values("flavor").unwrap();
+    assert_eq!(flavors, vec!["chocolate", "vanilla"]);
+
+    let report = cache
+        .migrate_column(
+            "flavor",
+            ColumnType::Enumerated {
+                allowed_values: flavors,
+            },
+            "",
+        )
+        .unwrap();
+    assert!(report.is_ok());
+    assert_eq!(report.converted, 4);
+    assert_eq!(
+        cache.get_metadata()[1].allowed_values,
+        vec!["chocolate", "vanilla"]
+    );
+    assert!(cache.create_row("pebbles,mint,1").is_err());
+
+    let values: Vec<Option<Value>> = cache
+        .query(&Query::new().select(&["flavor"]))
+        .unwrap()
+        .map(|row| row.values[0].clone())
+        .collect();
+    assert_eq!(values[1], Some(Value::Enumerated("vanilla".to_string())));
+    assert_eq!(values[3], None);
+}
+
+#[test]
+fn test_failed_migration_leaves_column() {
+    let mut cache = create_cache().unwrap();
+    let before = all_rows(&cache);
+
+    let report = cache.migrate_column("name", ColumnType::F64, "").unwrap();
+    assert!(!report.is_ok());
+    assert_eq!(report.converted, 0);
+    assert_eq!(report.errors.len(), 4);
+    assert_eq!(report.errors[1].value, "wilma");
+    assert_eq!(
+        report.errors[1].guid,
+        cache.query(&Query::new()).unwrap().nth(1).unwrap().guid
+    );
+
+    let report = cache
+        .migrate_column(
+            "flavor",
+            ColumnType::Enumerated {
+                allowed_values: vec!["vanilla".to_string()],
+            },
+            "",
+        )
+        .unwrap();
+    assert_eq!(report.converted, 2);
+    assert_eq!(report.errors.len(), 2);
+    assert_eq!(
+        report.errors[0].error,
+        CacheError::NotAllowed("chocolate".to_string())
+    );
+
+    assert_eq!(all_rows(&cache), before);
+    assert_eq!(
+        cache.get_metadata()[1].data_type,
+        ColumnStorageDataType::String
+    );
+}
+
+#[test]
+fn test_migration_events() {
+    let mut cache = create_cache().unwrap();
+    let (_, receiver) = cache.subscribe();
+
+    cache.rename_column("scoops", "count").unwrap();
+    cache.migrate_column("count", ColumnType::I64, "").unwrap();
+    cache.remove_column("count").unwrap();
+
+    let events: Vec<CacheEvent> = receiver.try_iter().collect();
+    assert_eq!(
+        events,
+        vec![
+            CacheEvent::ColumnRenamed {
+                name: "scoops".to_string(),
+                new_name: "count".to_string()
+            },
+            CacheEvent::ColumnChanged {
+                name: "count".to_string()
+            },
+            CacheEvent::ColumnRemoved {
+                name: "count".to_string()
+            },
+        ]
+    );
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use common::create_flavors;
+use data_cache::api::cache::{Cache, CacheError};
+use data_cache::api::query::{Predicate, Query, SortDirection};
+use data_cache::api::value::Value;
+
+fn create_cache() -> Result<Cache, CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "")?;
+    cache.add_boolean_column("verified", "Verified", "false")?;
+    cache.add_f64_column("age", "Age", "")?;
+    cache.add_time_date_column("start_time", "Start Time", "%Y-%m-%d %H:%M:%S", "")?;
+    cache.add_enumerated_column("flavor", "Flavor", "", create_flavors())?;
+
+    cache.create_row("fred,true,40,2019-01-01 00:00:00,chocolate")?;
+    cache.create_row("wilma,true,38,2019-06-01 00:00:00,vanilla")?;
+    cache.create_row("barney,false,41,2020-01-01 00:00:00,strawberry")?;
+    cache.create_row("betty,false,,2021-01-01 00:00:00,vanilla")?;
+    cache.create_row("pebbles,false,2,,")?;
+
+    Ok(cache)
+}
+
+fn names(cache: &Cache, query: &Query) -> Vec<String> {
+    cache
+        .query(&query.clone().select(&["name"]))
+        .unwrap()
+        .map(|row| row.values[0].as_ref().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_no_predicates() {
+    let cache = create_cache().unwrap();
+    let rows: Vec<_> = cache.query(&Query::new()).unwrap().collect();
+    assert_eq!(rows.len(), 5);
+    assert_eq!(rows[0].values.len(), 5);
+    assert_eq!(
+        cache.csv_for_guid(&rows[0].guid).unwrap(),
+        "fred,true,40,2019-01-01 00:00:00,chocolate"
+    );
+}
+
+#[test]
+fn test_equals() {
+    let cache = create_cache().unwrap();
+    assert_eq!(
+        names(
+            &cache,
+            &Query::new().filter(Predicate::equals("verified", "1"))
+        ),
+        vec!["fred", "wilma"]
+    );
+    assert_eq!(
+        names(&cache, &Query::new().filter(Predicate::equals("age", ""))),
+        vec!["betty"]
+    );
+}
+
+#[test]
+fn test_ranges() {
+    let cache = create_cache().unwrap();
+    assert_eq!(
+        names(
+            &cache,
+            &Query::new().filter(Predicate::range("age", "38", "40"))
+        ),
+        vec!["fred", "wilma"]
+    );
+    assert_eq!(
+        names(
+            &cache,
+            &Query::new().filter(Predicate::range("age", "", "10"))
+        ),
+        vec!["pebbles"]
+    );
+    assert_eq!(
+        names(
+            &cache,
+            &Query::new().filter(Predicate::range(
+                "start_time",
+                "2019-03-01 00:00:00",
+                "2020-01-01 00:00:00"
+            ))
+        ),
+        vec!["wilma", "barney"]
+    );
+}
+
+#[test]
+fn test_membership_and_nulls() {
+    let cache = create_cache().unwrap();
+    assert_eq!(
+        names(
+            &cache,
+            &Query::new().filter(Predicate::one_of("flavor", &["vanilla", "strawberry"]))
+        ),
+        vec!["wilma", "barney", "betty"]
+    );
+    assert_eq!(
+        names(&cache, &Query::new().filter(Predicate::is_null("flavor"))),
+        vec!["pebbles"]
+    );
+    assert_eq!(
+        names(
+            &cache,
+            &Query::new()
+                .filter(Predicate::is_not_null("age"))
+                .filter(Predicate::equals("verified", "false"))
+        ),
+        vec!["barney", "pebbles"]
+    );
+}
+
+#[test]
+fn test_sort_and_project() {
+    let cache = create_cache().unwrap();
+    let query = Query::new()
+        .sort_by("flavor", SortDirection::Ascending)
+        .sort_by("age", SortDirection::Descending)
+        .select(&["age", "name"]);
+    let rows: Vec<_> = cache.query(&query).unwrap().map(|row| row.values).collect();
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                Some(Value::F64(2.0)),
+                Some(Value::String("pebbles".to_string()))
+            ],
+            vec![
+                Some(Value::F64(40.0)),
+                Some(Value::String("fred".to_string()))
+            ],
+            vec![
+                Some(Value::F64(41.0)),
+                Some(Value::String("barney".to_string()))
+            ],
+            vec![
+                Some(Value::F64(38.0)),
+                Some(Value::String("wilma".to_string()))
+            ],
+            vec![None, Some(Value::String("betty".to_string()))],
+        ]
+    );
+}
+
+#[test]
+fn test_nulls_sort_lowest() {
+    let cache = create_cache().unwrap();
+    assert_eq!(
+        names(
+            &cache,
+            &Query::new().sort_by("age", SortDirection::Ascending)
+        ),
+        vec!["betty", "pebbles", "wilma", "fred", "barney"]
+    );
+    assert_eq!(
+        names(
+            &cache,
+            &Query::new().sort_by("age", SortDirection::Descending)
+        ),
+        vec!["barney", "fred", "wilma", "pebbles", "betty"]
+    );
+}
+
+#[test]
+fn test_query_errors() {
+    let cache = create_cache().unwrap();
+    assert_eq!(
+        cache
+            .query(&Query::new().filter(Predicate::equals("color", "red")))
+            .err(),
+        Some(CacheError::ColumnNotFound("color".to_string()))
+    );
+    assert!(cache
+        .query(&Query::new().filter(Predicate::range("age", "old", "")))
+        .is_err());
+    assert!(cache
+        .query(&Query::new().sort_by("color", SortDirection::Ascending))
+        .is_err());
+    assert!(cache.query(&Query::new().select(&["color"])).is_err());
+}
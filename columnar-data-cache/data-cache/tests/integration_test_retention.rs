@@ -0,0 +1,140 @@
+use chrono::{Duration, NaiveDateTime};
+use data_cache::api::cache::{Cache, CacheError};
+use data_cache::api::events::CacheEvent;
+use data_cache::api::retention::{EvictionReport, RetentionPolicy};
+use uuid::Uuid;
+
+fn time(value: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+fn create_cache() -> Result<(Cache, Vec<Uuid>), CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "")?;
+    cache.add_time_date_column("received", "Received", "%Y-%m-%d %H:%M:%S", "")?;
+    let guids = vec![
+        cache.create_row("fred,2020-01-01 08:00:00")?,
+        cache.create_row("wilma,2020-01-01 11:00:00")?,
+        cache.create_row("barney,2020-01-01 09:00:00")?,
+        cache.create_row("betty,")?,
+        cache.create_row("dino,2020-01-01 10:00:00")?,
+    ];
+    Ok((cache, guids))
+}
+
+#[test]
+fn test_evict_by_age() {
+    let (mut cache, guids) = create_cache().unwrap();
+    cache
+        .set_retention_policy(RetentionPolicy::new("received").max_age(Duration::hours(2)))
+        .unwrap();
+
+    let report = cache.evict_expired(time("2020-01-01 11:30:00")).unwrap();
+    assert_eq!(
+        report,
+        EvictionReport {
+            evicted: vec![guids[0], guids[2]],
+            expired_by_age: 2,
+            expired_by_count: 0,
+        }
+    );
+    assert_eq!(cache.row_count(), 3);
+    assert!(cache.get_row(&guids[0]).is_err());
+    for guid in [guids[1], guids[3], guids[4]] {
+        assert_eq!(cache.get_row(&guid).unwrap().guid, guid);
+    }
+
+    // nothing more to evict at the same time
+    let report = cache.evict_expired(time("2020-01-01 11:30:00")).unwrap();
+    assert!(report.evicted.is_empty());
+}
+
+#[test]
+fn test_max_age_before_earliest_time() {
+    let (mut cache, _) = create_cache().unwrap();
+    cache
+        .set_retention_policy(
+            RetentionPolicy::new("received").max_age(Duration::days(365 * 300_000)),
+        )
+        .unwrap();
+
+    let report = cache.evict_expired(time("2020-01-01 11:30:00")).unwrap();
+    assert_eq!(report, EvictionReport::default());
+    assert_eq!(cache.row_count(), 5);
+}
+
+#[test]
+fn test_evict_by_count() {
+    let (mut cache, guids) = create_cache().unwrap();
+    cache
+        .set_retention_policy(RetentionPolicy::new("received").max_rows(2))
+        .unwrap();
+
+    let report = cache.evict_expired(time("2020-01-01 11:30:00")).unwrap();
+    assert_eq!(report.expired_by_count, 3);
+    assert_eq!(report.evicted, vec![guids[0], guids[2], guids[3]]);
+    assert_eq!(cache.row_count(), 2);
+    assert_eq!(
+        cache.get_row(&guids[1]).unwrap().get_string("name"),
+        Ok(Some("wilma".to_string()))
+    );
+    assert_eq!(
+        cache.get_row(&guids[4]).unwrap().get_string("name"),
+        Ok(Some("dino".to_string()))
+    );
+}
+
+#[test]
+fn test_eviction_events() {
+    let (mut cache, guids) = create_cache().unwrap();
+    cache
+        .set_retention_policy(RetentionPolicy::new("received").max_age(Duration::hours(3)))
+        .unwrap();
+    let (_, receiver) = cache.subscribe();
+
+    cache.evict_expired(time("2020-01-01 11:30:00")).unwrap();
+    assert_eq!(
+        receiver.try_recv().unwrap(),
+        CacheEvent::RowRemoved { guid: guids[0] }
+    );
+    assert!(receiver.try_recv().is_err());
+}
+
+#[test]
+fn test_policy_follows_column() {
+    let (mut cache, _) = create_cache().unwrap();
+    assert_eq!(
+        cache.set_retention_policy(RetentionPolicy::new("name")),
+        Err(CacheError::UnsupportedType("String".to_string()))
+    );
+    assert_eq!(
+        cache.set_retention_policy(RetentionPolicy::new("created")),
+        Err(CacheError::ColumnNotFound("created".to_string()))
+    );
+
+    cache
+        .set_retention_policy(RetentionPolicy::new("received").max_rows(1))
+        .unwrap();
+    cache.rename_column("received", "arrived").unwrap();
+    assert_eq!(
+        cache.get_retention_policy().unwrap().get_time_column(),
+        "arrived"
+    );
+    assert_eq!(
+        cache
+            .clone()
+            .evict_expired(time("2020-01-01 11:30:00"))
+            .unwrap()
+            .evicted
+            .len(),
+        4
+    );
+
+    cache.remove_column("arrived").unwrap();
+    assert_eq!(cache.get_retention_policy(), None);
+    assert_eq!(
+        cache.evict_expired(time("2020-01-01 11:30:00")),
+        Ok(EvictionReport::default())
+    );
+    assert_eq!(cache.row_count(), 5);
+}
@@ -0,0 +1,141 @@
+use data_cache::api::cache::{Cache, CacheError};
+use data_cache::api::migration::ColumnType;
+use data_cache::api::query::Predicate;
+use data_cache::api::search::Search;
+use uuid::Uuid;
+
+fn create_cache() -> Result<(Cache, Vec<Uuid>), CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "")?;
+    cache.add_f64_column("age", "Age", "")?;
+    let guids = vec![
+        cache.create_row("Fred Flintstone,42")?,
+        cache.create_row("Wilma Flintstone,38")?,
+        cache.create_row("Barney Rubble,40")?,
+        cache.create_row(",1")?,
+    ];
+    cache.add_search_index("name")?;
+    Ok((cache, guids))
+}
+
+#[test]
+fn test_search() {
+    let (cache, guids) = create_cache().unwrap();
+
+    assert_eq!(
+        cache.search("name", &Search::token("flintstone")),
+        Ok(vec![guids[0], guids[1]])
+    );
+    assert_eq!(
+        cache.search("name", &Search::prefix("FL")),
+        Ok(vec![guids[0], guids[1]])
+    );
+    assert_eq!(
+        cache.search("name", &Search::prefix("bar rub")),
+        Ok(vec![guids[2]])
+    );
+    assert_eq!(
+        cache.search("name", &Search::substring("ubb")),
+        Ok(vec![guids[2]])
+    );
+    assert_eq!(
+        cache.search("name", &Search::substring("ma flint")),
+        Ok(vec![guids[1]])
+    );
+    assert_eq!(cache.search("name", &Search::token("flint")), Ok(vec![]));
+}
+
+#[test]
+fn test_index_follows_rows() {
+    let (mut cache, guids) = create_cache().unwrap();
+
+    let pebbles = cache.create_row("Pebbles Flintstone,2").unwrap();
+    cache.update_row(&guids[0], "Fred Rubble,42").unwrap();
+    cache.delete_row(&guids[1]).unwrap();
+    assert_eq!(
+        cache.search("name", &Search::token("flintstone")),
+        Ok(vec![pebbles])
+    );
+
+    let mut rubbles = cache.search("name", &Search::token("rubble")).unwrap();
+    rubbles.sort();
+    let mut expected = vec![guids[0], guids[2]];
+    expected.sort();
+    assert_eq!(rubbles, expected);
+
+    cache
+        .delete_where(&[Predicate::range("age", "", "10")])
+        .unwrap();
+    assert_eq!(cache.search("name", &Search::prefix("pebb")), Ok(vec![]));
+}
+
+#[test]
+fn test_index_follows_undo() {
+    let (mut cache, guids) = create_cache().unwrap();
+
+    cache
+        .transaction(|transaction| {
+            transaction.update_row(&guids[2], "Betty Rubble,39")?;
+            transaction.delete_row(&guids[0])
+        })
+        .unwrap();
+    assert_eq!(cache.search("name", &Search::token("fred")), Ok(vec![]));
+    assert_eq!(
+        cache.search("name", &Search::token("betty")),
+        Ok(vec![guids[2]])
+    );
+
+    cache.undo().unwrap();
+    assert_eq!(
+        cache.search("name", &Search::token("fred")),
+        Ok(vec![guids[0]])
+    );
+    assert_eq!(cache.search("name", &Search::token("betty")), Ok(vec![]));
+    assert_eq!(
+        cache.search("name", &Search::token("barney")),
+        Ok(vec![guids[2]])
+    );
+}
+
+#[test]
+fn test_search_index_columns() {
+    let (mut cache, _) = create_cache().unwrap();
+
+    assert_eq!(
+        cache.add_search_index("age"),
+        Err(CacheError::UnsupportedType("F64".to_string()))
+    );
+    assert_eq!(
+        cache.add_search_index("color"),
+        Err(CacheError::ColumnNotFound("color".to_string()))
+    );
+    assert!(matches!(
+        cache.search("age", &Search::token("42")),
+        Err(CacheError::NotAllowed(_))
+    ));
+
+    // the index follows a renamed column and goes when the column is no longer a string
+    cache.rename_column("name", "full_name").unwrap();
+    assert_eq!(
+        cache
+            .search("full_name", &Search::token("wilma"))
+            .unwrap()
+            .len(),
+        1
+    );
+    cache
+        .migrate_column(
+            "full_name",
+            ColumnType::Enumerated {
+                allowed_values: cache.distinct_values("full_name").unwrap(),
+            },
+            "",
+        )
+        .unwrap();
+    assert!(cache.search("full_name", &Search::token("wilma")).is_err());
+
+    cache.add_string_column("nickname", "Nickname", "").unwrap();
+    cache.add_search_index("nickname").unwrap();
+    assert_eq!(cache.remove_search_index("nickname"), Ok(true));
+    assert_eq!(cache.remove_search_index("nickname"), Ok(false));
+}
@@ -0,0 +1,175 @@
+#![cfg(feature = "server")]
+
+mod common;
+
+use common::create_flavors;
+use data_cache::api::cache::{Cache, CacheError};
+use data_cache::api::concurrent_cache::ConcurrentCache;
+use data_cache::server;
+use rocket::http::{ContentType, Status};
+use rocket::local::blocking::Client;
+use serde_json::{json, Value};
+
+fn create_cache() -> Result<Cache, CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "")?;
+    cache.add_f64_column("age", "Age", "")?;
+    cache.add_enumerated_column("flavor", "Flavor", "vanilla", create_flavors())?;
+    cache.create_row("fred,42,chocolate")?;
+    cache.create_row("wilma,38,strawberry")?;
+    cache.create_row("barney,40,")?;
+    Ok(cache)
+}
+
+fn create_client() -> Client {
+    let cache = ConcurrentCache::new(create_cache().unwrap());
+    Client::tracked(server::rocket(cache)).unwrap()
+}
+
+fn post(client: &Client, uri: &str, body: Value) -> (Status, Value) {
+    let response = client
+        .post(uri)
+        .header(ContentType::JSON)
+        .body(body.to_string())
+        .dispatch();
+    let status = response.status();
+    (status, response.into_json().unwrap_or(Value::Null))
+}
+
+fn get(client: &Client, uri: &str) -> (Status, Value) {
+    let response = client.get(uri).dispatch();
+    let status = response.status();
+    (status, response.into_json().unwrap_or(Value::Null))
+}
+
+#[test]
+fn test_metadata_and_add_column() {
+    let client = create_client();
+
+    let (status, metadata) = get(&client, "/api/metadata");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(metadata["row_count"], json!(3));
+    assert_eq!(metadata["columns"][2]["data_type"], json!("Enumerated"));
+    assert_eq!(
+        metadata["columns"][2]["allowed_values"],
+        json!(create_flavors())
+    );
+
+    let column = json!({
+        "name": "joined",
+        "data_type": "Date",
+        "format": "%Y-%m-%d",
+        "default_value": "2020-01-01",
+    });
+    let (status, _) = post(&client, "/api/columns", column.clone());
+    assert_eq!(status, Status::Created);
+    let (status, error) = post(&client, "/api/columns", column);
+    assert_eq!(status, Status::Conflict);
+    assert!(error["error"].as_str().unwrap().contains("joined"));
+
+    let (status, _) = post(
+        &client,
+        "/api/columns",
+        json!({"name": "size", "data_type": "Huge"}),
+    );
+    assert_eq!(status, Status::BadRequest);
+
+    let (_, metadata) = get(&client, "/api/metadata");
+    assert_eq!(metadata["columns"][3]["name"], json!("joined"));
+    assert_eq!(metadata["columns"][3]["display_name"], json!("joined"));
+}
+
+#[test]
+fn test_row_lifecycle() {
+    let client = create_client();
+
+    let (status, row) = post(&client, "/api/rows", json!({"name": "betty", "age": 35}));
+    assert_eq!(status, Status::Created);
+    assert_eq!(row["flavor"], json!("vanilla"));
+    let guid = row["_guid"].as_str().unwrap().to_string();
+    let uri = format!("/api/rows/{}", guid);
+
+    let (status, fetched) = get(&client, &uri);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(fetched, row);
+
+    let response = client
+        .put(&uri)
+        .header(ContentType::JSON)
+        .body(json!({"name": "betty", "age": 36, "flavor": "chocolate"}).to_string())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let updated: Value = response.into_json().unwrap();
+    assert_eq!(updated["age"], json!(36.0));
+    assert_eq!(updated["_guid"], json!(guid));
+
+    assert_eq!(client.delete(&uri).dispatch().status(), Status::NoContent);
+    assert_eq!(get(&client, &uri).0, Status::NotFound);
+    assert_eq!(client.delete(&uri).dispatch().status(), Status::NotFound);
+}
+
+#[test]
+fn test_invalid_rows() {
+    let client = create_client();
+
+    let (status, error) = post(
+        &client,
+        "/api/rows",
+        json!({"name": "dino", "age": "old", "flavor": "mint"}),
+    );
+    assert_eq!(status, Status::BadRequest);
+    let message = error["error"].as_str().unwrap();
+    assert!(message.contains("age"));
+    assert!(message.contains("flavor"));
+
+    let (status, _) = post(&client, "/api/rows", json!({"color": "red"}));
+    assert_eq!(status, Status::NotFound);
+
+    let (status, _) = get(&client, "/api/rows/not-a-guid");
+    assert_eq!(status, Status::BadRequest);
+
+    let response = client
+        .post("/api/rows")
+        .header(ContentType::JSON)
+        .body("{not json")
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    let error: Value = response.into_json().unwrap();
+    assert!(error["error"].is_string());
+}
+
+#[test]
+fn test_query() {
+    let client = create_client();
+
+    let (status, rows) = post(
+        &client,
+        "/api/query",
+        json!({
+            "filters": [{"op": "range", "column": "age", "min": "39"}],
+            "sort": [{"column": "age", "descending": true}],
+            "columns": ["name"],
+        }),
+    );
+    assert_eq!(status, Status::Ok);
+    let names: Vec<&Value> = rows
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|row| &row["name"])
+        .collect();
+    assert_eq!(names, vec![&json!("fred"), &json!("barney")]);
+    assert!(rows[0].get("age").is_none());
+    assert!(rows[0]["_guid"].is_string());
+
+    let (status, rows) = post(&client, "/api/query", json!({}));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(rows.as_array().unwrap().len(), 3);
+
+    let (status, _) = post(
+        &client,
+        "/api/query",
+        json!({"filters": [{"op": "in", "column": "color", "values": ["red"]}]}),
+    );
+    assert_eq!(status, Status::NotFound);
+}
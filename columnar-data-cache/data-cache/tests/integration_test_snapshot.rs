@@ -0,0 +1,132 @@
+mod common;
+
+use common::{all_guids, assert_same, create_flavors};
+use data_cache::api::cache::{Cache, CacheError};
+use uuid::Uuid;
+
+fn create_cache() -> Result<Cache, CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "unknown")?;
+    cache.add_boolean_column("verified", "Verified", "false")?;
+    cache.add_f64_column("age", "Age", "0")?;
+    cache.add_time_date_column("start_time", "Start Time", "%Y-%m-%d %H:%M:%S", "")?;
+    cache.add_enumerated_column("flavor", "Flavor", "fudge ripple", create_flavors())?;
+    cache.add_string_column("nickname", "Nickname", "")?;
+
+    cache.create_row("fred,true, 1.5, 2019-01-01 00:00:00,chocolate,")?;
+    cache.create_row(",,,,,")?;
+    cache.create_row("wilma,false, -2, 2020-02-29 23:59:59,strawberry,willy")?;
+
+    Ok(cache)
+}
+
+#[test]
+fn test_round_trip() {
+    let cache = create_cache().unwrap();
+    let mut buffer = vec![];
+    cache.save_snapshot(&mut buffer).unwrap();
+
+    let loaded = Cache::load_snapshot(&mut buffer.as_slice()).unwrap();
+    assert_same(&cache, &loaded);
+    assert_eq!(loaded.row_len(), 3);
+    assert_eq!(loaded.get_metadata()[4].allowed_values.len(), 4);
+}
+
+#[test]
+fn test_loaded_cache_is_usable() {
+    let cache = create_cache().unwrap();
+    let guids = all_guids(&cache);
+    let mut buffer = vec![];
+    cache.save_snapshot(&mut buffer).unwrap();
+
+    let mut loaded = Cache::load_snapshot(&mut buffer.as_slice()).unwrap();
+    loaded
+        .update_row(&guids[1], "barney,true,3,2021-01-01 00:00:00,vanilla,")
+        .unwrap();
+    assert_eq!(
+        loaded.csv_for_guid(&guids[1]).unwrap(),
+        "barney,true,3,2021-01-01 00:00:00,vanilla,"
+    );
+    let guid = loaded.create_row("betty,,,,,").unwrap();
+    assert_eq!(
+        loaded.csv_for_guid(&guid).unwrap(),
+        "betty,false,0,,fudge ripple,"
+    );
+}
+
+#[test]
+fn test_empty_cache() {
+    let cache = Cache::new();
+    let mut buffer = vec![];
+    cache.save_snapshot(&mut buffer).unwrap();
+    let loaded = Cache::load_snapshot(&mut buffer.as_slice()).unwrap();
+    assert_eq!(loaded.row_len(), 0);
+    assert_eq!(loaded.column_len(), 0);
+}
+
+#[test]
+fn test_file_round_trip() {
+    let cache = create_cache().unwrap();
+    let path = std::env::temp_dir().join(format!("data-cache-{}.snapshot", Uuid::new_v4()));
+    cache.save_snapshot_file(&path).unwrap();
+    let loaded = Cache::load_snapshot_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_same(&cache, &loaded);
+}
+
+#[test]
+fn test_invalid_snapshots() {
+    let cache = create_cache().unwrap();
+    let mut buffer = vec![];
+    cache.save_snapshot(&mut buffer).unwrap();
+
+    let result = Cache::load_snapshot(&mut "not a snapshot".as_bytes());
+    assert_eq!(
+        result.err(),
+        Some(CacheError::InvalidSnapshot("not a snapshot".to_string()))
+    );
+
+    let mut future_version = buffer.clone();
+    future_version[6] = 99;
+    let result = Cache::load_snapshot(&mut future_version.as_slice());
+    assert_eq!(
+        result.err().unwrap().to_string(),
+        "Invalid snapshot: unsupported version 99"
+    );
+
+    let truncated = &buffer[..buffer.len() - 5];
+    assert!(Cache::load_snapshot(&mut &truncated[..]).is_err());
+}
+
+#[test]
+fn test_duplicates_in_snapshot() {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "").unwrap();
+    cache.add_string_column("nick", "Nick", "").unwrap();
+    cache.create_row("fred,freddy").unwrap();
+    cache.create_row("wilma,willy").unwrap();
+    let mut buffer = vec![];
+    cache.save_snapshot(&mut buffer).unwrap();
+
+    // the guids are the last bytes of a snapshot
+    let mut duplicate_guid = buffer.clone();
+    let length = duplicate_guid.len();
+    duplicate_guid.copy_within(length - 16..length, length - 32);
+    assert!(matches!(
+        Cache::load_snapshot(&mut duplicate_guid.as_slice()),
+        Err(CacheError::InvalidSnapshot(reason)) if reason.starts_with("duplicate guid")
+    ));
+
+    let mut duplicate_column = buffer.clone();
+    let position = duplicate_column
+        .windows(4)
+        .position(|window| window == b"nick")
+        .unwrap();
+    duplicate_column[position..position + 4].copy_from_slice(b"name");
+    assert_eq!(
+        Cache::load_snapshot(&mut duplicate_column.as_slice()).err(),
+        Some(CacheError::InvalidSnapshot(
+            "duplicate column name".to_string()
+        ))
+    );
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use common::create_flavors;
+use data_cache::api::cache::{Cache, CacheError};
+use data_cache::api::events::CacheEvent;
+use data_cache::api::migration::ColumnType;
+use data_cache::api::validation::Unique;
+use uuid::Uuid;
+
+fn create_cache() -> Result<(Cache, Vec<Uuid>), CacheError> {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "")?;
+    cache.add_f64_column("weight", "Weight", "")?;
+    cache.add_enumerated_column("flavor", "Flavor", "vanilla", create_flavors())?;
+    let guids = vec![
+        cache.create_row("fred,200.5,chocolate")?,
+        cache.create_row("wilma,,strawberry")?,
+        cache.create_row("barney,180.0,")?,
+    ];
+    Ok((cache, guids))
+}
+
+fn all_rows(cache: &Cache) -> serde_json::Value {
+    cache.to_json().unwrap()
+}
+
+#[test]
+fn test_commit() {
+    let (mut cache, guids) = create_cache().unwrap();
+
+    let guid = cache
+        .transaction(|transaction| {
+            transaction.update_row(&guids[0], "fred,190.0,vanilla")?;
+            transaction.delete_row(&guids[1])?;
+            transaction.create_row("betty,120.0,chocolate")
+        })
+        .unwrap();
+
+    assert_eq!(cache.row_count(), 3);
+    assert_eq!(
+        cache.csv_for_guid(&guids[0]).unwrap(),
+        "fred,190,vanilla".to_string()
+    );
+    assert_eq!(
+        cache.csv_for_guid(&guids[1]),
+        Err(CacheError::GuidNotFound(guids[1]))
+    );
+    assert_eq!(
+        cache.csv_for_guid(&guid).unwrap(),
+        "betty,120,chocolate".to_string()
+    );
+    assert!(cache.can_undo());
+    assert!(!cache.can_redo());
+}
+
+#[test]
+fn test_rollback() {
+    let (mut cache, guids) = create_cache().unwrap();
+    let before = all_rows(&cache);
+
+    let result = cache.transaction(|transaction| {
+        transaction.delete_row(&guids[0])?;
+        transaction.update_row(&guids[2], "barney,170.0,strawberry")?;
+        transaction.create_row("betty,120.0,chocolate")?;
+        transaction.create_row("dino,heavy,")
+    });
+
+    assert!(matches!(result, Err(CacheError::InvalidRow(_))));
+    assert_eq!(all_rows(&cache), before);
+    assert!(!cache.can_undo());
+}
+
+#[test]
+fn test_rollback_returns_edit_error() {
+    let (mut cache, guids) = create_cache().unwrap();
+    let before = all_rows(&cache);
+    let missing = Uuid::new_v4();
+
+    let result = cache.transaction(|transaction| {
+        transaction.update_row(&guids[0], "fred,190.0,vanilla")?;
+        transaction.delete_row(&guids[1])?;
+        transaction.delete_row(&missing)?;
+        transaction.create_row("betty,120.0,chocolate")
+    });
+
+    assert_eq!(result, Err(CacheError::GuidNotFound(missing)));
+    assert_eq!(all_rows(&cache), before);
+    assert!(!cache.can_undo());
+}
+
+#[test]
+fn test_undo_redo() {
+    let (mut cache, guids) = create_cache().unwrap();
+    let before = all_rows(&cache);
+
+    cache
+        .transaction(|transaction| {
+            transaction.delete_row(&guids[0])?;
+            transaction.update_row(&guids[2], "barney,170.0,strawberry")?;
+            transaction.create_row("betty,,")
+        })
+        .unwrap();
+    let after = all_rows(&cache);
+    assert_ne!(after, before);
+
+    assert_eq!(cache.undo(), Ok(true));
+    assert_eq!(all_rows(&cache), before);
+    assert!(!cache.can_undo());
+    assert!(cache.can_redo());
+
+    assert_eq!(cache.redo(), Ok(true));
+    assert_eq!(all_rows(&cache), after);
+    assert_eq!(cache.redo(), Ok(false));
+
+    assert_eq!(cache.undo(), Ok(true));
+    assert_eq!(cache.undo(), Ok(false));
+    assert_eq!(all_rows(&cache), before);
+}
+
+#[test]
+fn test_new_transaction_clears_redo() {
+    let (mut cache, guids) = create_cache().unwrap();
+
+    cache
+        .transaction(|transaction| transaction.delete_row(&guids[0]))
+        .unwrap();
+    cache.undo().unwrap();
+    assert!(cache.can_redo());
+
+    cache
+        .transaction(|transaction| transaction.delete_row(&guids[1]))
+        .unwrap();
+    assert!(!cache.can_redo());
+
+    cache.clear_history();
+    assert!(!cache.can_undo());
+}
+
+#[test]
+fn test_failed_undo_leaves_cache_unchanged() {
+    let (mut cache, guids) = create_cache().unwrap();
+
+    cache
+        .transaction(|transaction| {
+            transaction.delete_row(&guids[0])?;
+            transaction.update_row(&guids[1], "pebbles,,strawberry")
+        })
+        .unwrap();
+
+    // restoring fred would break the validator, after the update has already been undone
+    let guid = cache.create_row("fred,190.0,vanilla").unwrap();
+    cache.add_validator("name", Box::new(Unique)).unwrap();
+    let before = all_rows(&cache);
+
+    assert!(matches!(cache.undo(), Err(CacheError::InvalidRow(..))));
+    assert_eq!(all_rows(&cache), before);
+    assert!(cache.can_undo());
+    assert!(cache.get_row(&guid).is_ok());
+}
+
+#[test]
+fn test_column_changes_clear_history() {
+    let mut cache = Cache::new();
+    cache.add_string_column("name", "Name", "").unwrap();
+    cache.add_string_column("nick", "Nick", "").unwrap();
+    let guid = cache.create_row("fred,freddie").unwrap();
+
+    cache
+        .transaction(|transaction| transaction.update_row(&guid, "fred,flintstone"))
+        .unwrap();
+    cache.remove_column("name").unwrap();
+    cache.add_string_column("city", "City", "").unwrap();
+
+    assert!(!cache.can_undo());
+    assert_eq!(cache.undo(), Ok(false));
+    assert_eq!(cache.csv_for_guid(&guid).unwrap(), "flintstone,");
+
+    cache
+        .transaction(|transaction| transaction.update_row(&guid, "flintstone,bedrock"))
+        .unwrap();
+    cache
+        .migrate_column("city", ColumnType::String, "")
+        .unwrap();
+    assert!(!cache.can_undo());
+}
+
+#[test]
+fn test_transaction_events() {
+    let (mut cache, guids) = create_cache().unwrap();
+    let (_, receiver) = cache.subscribe();
+
+    cache
+        .transaction(|transaction| transaction.delete_row(&guids[1]))
+        .unwrap();
+    assert_eq!(
+        receiver.try_recv().unwrap(),
+        CacheEvent::RowRemoved { guid: guids[1] }
+    );
+
+    cache.undo().unwrap();
+    assert_eq!(
+        receiver.try_recv().unwrap(),
+        CacheEvent::RowCreated { guid: guids[1] }
+    );
+    assert!(receiver.try_recv().is_err());
+}
@@ -0,0 +1,201 @@
+use chrono::NaiveDateTime;
+use data_cache::api::cache::{Cache, CacheError, Expected};
+use data_cache::api::migration::ColumnType;
+use data_cache::api::validation::{
+    Length, MinMax, Pattern, Required, TimeRange, Unique, ValidationContext, Validator,
+};
+use data_cache::api::value::Value;
+
+fn time_date(value: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+fn create_cache() -> Result<Cache, CacheError> {
+    let mut cache = Cache::new();
+    cache.add_column(
+        "name",
+        "Name",
+        ColumnType::String,
+        "",
+        vec![
+            Box::new(Required),
+            Box::new(Unique),
+            Box::new(Length {
+                min: Some(2),
+                max: Some(10),
+            }),
+        ],
+    )?;
+    cache.add_column(
+        "email",
+        "Email",
+        ColumnType::String,
+        "",
+        vec![Box::new(Pattern::new("^[^@]+@[^@]+$")?)],
+    )?;
+    cache.add_column(
+        "age",
+        "Age",
+        ColumnType::F64,
+        "",
+        vec![Box::new(MinMax {
+            min: Some(0.0),
+            max: Some(150.0),
+        })],
+    )?;
+    cache.add_column(
+        "joined",
+        "Joined",
+        ColumnType::TimeDate {
+            format: "%Y-%m-%d %H:%M:%S".to_string(),
+        },
+        "",
+        vec![Box::new(TimeRange {
+            start: Some(time_date("2000-01-01 00:00:00")),
+            end: None,
+        })],
+    )?;
+
+    cache.create_row("fred,fred@bedrock.com,40,2020-01-01 00:00:00")?;
+    cache.create_row("wilma,,38,")?;
+
+    Ok(cache)
+}
+
+fn rejected_columns(result: Result<uuid::Uuid, CacheError>) -> Vec<String> {
+    match result {
+        Err(CacheError::InvalidRow(field_errors)) => field_errors
+            .into_iter()
+            .map(|field_error| field_error.column)
+            .collect(),
+        _ => panic!("expected an invalid row"),
+    }
+}
+
+#[test]
+fn test_valid_rows() {
+    let cache = create_cache().unwrap();
+    assert_eq!(cache.row_count(), 2);
+}
+
+#[test]
+fn test_rejected_rows() {
+    let mut cache = create_cache().unwrap();
+    assert_eq!(rejected_columns(cache.create_row(",,,")), vec!["name"]);
+    assert_eq!(rejected_columns(cache.create_row("fred,,,")), vec!["name"]);
+    assert_eq!(
+        rejected_columns(cache.create_row("barney rubble jr,barney,151,1999-12-31 23:59:59")),
+        vec!["name", "email", "age", "joined"]
+    );
+    assert_eq!(cache.row_count(), 2);
+}
+
+#[test]
+fn test_field_error_details() {
+    let mut cache = create_cache().unwrap();
+    let field_errors = match cache.create_row("barney,,-1,") {
+        Err(CacheError::InvalidRow(field_errors)) => field_errors,
+        _ => panic!("expected an invalid row"),
+    };
+
+    assert_eq!(field_errors.len(), 1);
+    assert_eq!(field_errors[0].position, 2);
+    assert_eq!(field_errors[0].value, "-1");
+    assert_eq!(
+        field_errors[0].expected,
+        Expected::Rule("between 0 and 150".to_string())
+    );
+    assert_eq!(
+        *field_errors[0].error,
+        CacheError::ValidationFailed("-1 is not between 0 and 150".to_string())
+    );
+}
+
+#[test]
+fn test_update_keeps_unique_value() {
+    let mut cache = create_cache().unwrap();
+    let guid = cache.create_row("barney,,41,").unwrap();
+    cache.update_row(&guid, "barney,,42,").unwrap();
+    assert_eq!(
+        rejected_columns(cache.update_row(&guid, "wilma,,42,")),
+        vec!["name"]
+    );
+    assert_eq!(cache.csv_for_guid(&guid).unwrap(), "barney,,42,");
+}
+
+#[test]
+fn test_unique_after_delete_and_add_validator() {
+    let mut cache = create_cache().unwrap();
+    let guid = cache.create_row("barney,,41,").unwrap();
+    cache.delete_row(&guid).unwrap();
+    cache.create_row("barney,,41,").unwrap();
+    assert_eq!(
+        rejected_columns(cache.create_row("barney,,41,")),
+        vec!["name"]
+    );
+
+    // the existing rows are counted when the validator is added
+    cache.add_validator("email", Box::new(Unique)).unwrap();
+    assert_eq!(
+        rejected_columns(cache.create_row("betty,fred@bedrock.com,39,")),
+        vec!["email"]
+    );
+    cache.create_row("betty,betty@bedrock.com,39,").unwrap();
+}
+
+#[test]
+fn test_unsuitable_column_type() {
+    let mut cache = create_cache().unwrap();
+    assert!(matches!(
+        cache.add_validator(
+            "name",
+            Box::new(MinMax {
+                min: None,
+                max: Some(1.0)
+            })
+        ),
+        Err(CacheError::UnsupportedType(_))
+    ));
+    assert!(matches!(
+        cache.migrate_column("age", ColumnType::String, ""),
+        Err(CacheError::UnsupportedType(_))
+    ));
+    assert!(matches!(
+        cache.add_validator("missing", Box::new(Required)),
+        Err(CacheError::ColumnNotFound(_))
+    ));
+}
+
+struct EvenNumber;
+
+impl Validator for EvenNumber {
+    fn validate(&self, value: &Option<Value>, _context: &ValidationContext) -> Result<(), String> {
+        match value {
+            Some(Value::F64(number)) if number % 2.0 != 0.0 => Err(format!("{} is odd", number)),
+            _ => Ok(()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        "an even number".to_string()
+    }
+}
+
+#[test]
+fn test_custom_validator() {
+    let mut cache = create_cache().unwrap();
+    cache.add_validator("age", Box::new(EvenNumber)).unwrap();
+
+    assert!(cache.create_row("barney,,40,").is_ok());
+    assert_eq!(
+        rejected_columns(cache.create_row("betty,,41,")),
+        vec!["age"]
+    );
+
+    // validators are kept when the cache is cloned
+    let mut clone = cache.clone();
+    assert_eq!(
+        rejected_columns(clone.create_row("pebbles,,1,")),
+        vec!["age"]
+    );
+}
@@ -0,0 +1,17 @@
+-- materialized_only = false makes this a real-time aggregate, the minutes the policy has not
+-- materialized yet are read from the measurements so recent data is not left out
+CREATE MATERIALIZED VIEW "measurements_by_minute"
+WITH (timescaledb.continuous, timescaledb.materialized_only = false) AS
+SELECT
+    time_bucket(INTERVAL '1 minute', measured_at) AS bucket,
+    flavor,
+    color,
+    COUNT(*) AS measurement_count,
+    SUM(sqrt(x_velocity * x_velocity + y_velocity * y_velocity + z_velocity * z_velocity)) AS velocity_sum
+FROM measurements
+GROUP BY bucket, flavor, color
+WITH NO DATA;
+SELECT add_continuous_aggregate_policy('measurements_by_minute',
+    start_offset => INTERVAL '1 hour',
+    end_offset => INTERVAL '1 minute',
+    schedule_interval => INTERVAL '1 minute');
@@ -0,0 +1 @@
+CREATE INDEX measurements_object_uuid_measured_at_idx ON measurements (object_uuid, measured_at DESC);
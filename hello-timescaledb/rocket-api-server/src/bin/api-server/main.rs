@@ -1,7 +1,10 @@
+use crate::routes::aggregate_measurements::aggregate_measurements;
 use crate::routes::find_measurements::find_measurements;
 use crate::routes::get_diagnostics::get_diagnostics;
+use crate::routes::get_measurement::get_measurement;
 use crate::routes::get_path::get_path;
 use crate::routes::insert_measurement::insert_measurement;
+use crate::routes::insert_measurements::insert_measurements;
 
 use rocket::{launch, routes};
 use rocket_db_pools::Database;
@@ -18,7 +21,10 @@ fn rocket() -> _ {
         "/api",
         routes![
             insert_measurement,
+            insert_measurements,
+            get_measurement,
             find_measurements,
+            aggregate_measurements,
             get_diagnostics,
             get_path
         ],
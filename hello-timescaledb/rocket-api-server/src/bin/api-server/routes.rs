@@ -1,4 +1,31 @@
+use rocket::Responder;
+
+pub(crate) mod aggregate_measurements;
 pub(crate) mod find_measurements;
 pub(crate) mod get_diagnostics;
+pub(crate) mod get_measurement;
 pub(crate) mod get_path;
 pub(crate) mod insert_measurement;
+pub(crate) mod insert_measurements;
+
+/// The error of a route that can reject its parameters, a bad request is answered with a 400
+/// and the reason, anything else with a 500
+#[derive(Responder)]
+pub(crate) enum RouteError {
+    #[response(status = 400)]
+    BadRequest(String),
+    #[response(status = 500)]
+    Internal(rocket::response::Debug<anyhow::Error>),
+}
+
+impl From<anyhow::Error> for RouteError {
+    fn from(error: anyhow::Error) -> Self {
+        RouteError::Internal(rocket::response::Debug(error))
+    }
+}
+
+impl From<rocket::response::Debug<anyhow::Error>> for RouteError {
+    fn from(error: rocket::response::Debug<anyhow::Error>) -> Self {
+        RouteError::Internal(error)
+    }
+}
@@ -0,0 +1,127 @@
+use crate::routes::RouteError;
+use crate::RocketApiDatabase;
+use rocket::get;
+use rocket::serde::json::Json;
+use rocket_api_server::{
+    is_whole_minute, parse_bucket_width, parse_datetime, InstrumentedResponse, MeasurementBucket,
+    Times,
+};
+use rocket_db_pools::Connection;
+
+/// Summarize the measurements in a time range into buckets of bucket_width, e.g. "5 minutes"
+/// The buckets are optionally also grouped by flavor and by color
+/// With continuous the buckets are rolled up from the measurements_by_minute continuous
+/// aggregate instead of the measurements, which is much faster over long time ranges but
+/// needs a bucket_width, start and end in whole minutes and can't count distinct objects
+/// A bad start, end or bucket_width is a 400
+#[get("/aggregate_measurements?<start>&<end>&<bucket_width>&<by_flavor>&<by_color>&<continuous>")]
+pub async fn aggregate_measurements(
+    mut db: Connection<RocketApiDatabase>,
+    start: &str,
+    end: &str,
+    bucket_width: &str,
+    by_flavor: Option<bool>,
+    by_color: Option<bool>,
+    continuous: Option<bool>,
+) -> Result<Json<InstrumentedResponse<Vec<MeasurementBucket>>>, RouteError> {
+    let start = parse_datetime(&start)
+        .map_err(|e| RouteError::BadRequest(format!("invalid start: {}", e)))?;
+    let end =
+        parse_datetime(&end).map_err(|e| RouteError::BadRequest(format!("invalid end: {}", e)))?;
+    let by_flavor = by_flavor.unwrap_or(false);
+    let by_color = by_color.unwrap_or(false);
+    let continuous = continuous.unwrap_or(false);
+
+    // checked here so that a bad width is a 400 rather than an error from the query
+    let bucket_seconds = parse_bucket_width(bucket_width).map_err(RouteError::BadRequest)?;
+    if continuous && bucket_seconds % 60 != 0 {
+        return Err(RouteError::BadRequest(format!(
+            "a continuous bucket_width must be whole minutes, not {:?}",
+            bucket_width
+        )));
+    }
+    // the continuous aggregate only has whole minutes, so a start or end part way through a
+    // minute would drop or add measurements compared to the raw query
+    if continuous && !(is_whole_minute(&start) && is_whole_minute(&end)) {
+        return Err(RouteError::BadRequest(
+            "a continuous start and end must be whole minutes".to_string(),
+        ));
+    }
+
+    let query_start = chrono::Utc::now().naive_utc();
+    let mut buckets: Vec<MeasurementBucket> = vec![];
+    if continuous {
+        // The average velocity is rebuilt from the per minute sums so that it is weighted by
+        // the number of measurements in each minute
+        let query_results = sqlx::query!(
+            "SELECT time_bucket($3::text::interval, mbm.bucket)::timestamp AS \"bucket!\", \
+            CASE WHEN $4 THEN mbm.flavor END AS flavor, CASE WHEN $5 THEN mbm.color END AS color, \
+            SUM(mbm.measurement_count)::bigint AS \"measurement_count!\", \
+            SUM(mbm.velocity_sum) / SUM(mbm.measurement_count)::float8 AS average_velocity \
+            FROM measurements_by_minute mbm WHERE mbm.bucket >= $1 AND mbm.bucket < $2 \
+            GROUP BY 1, 2, 3 ORDER BY 1, 2, 3",
+            start,
+            end,
+            bucket_width,
+            by_flavor,
+            by_color,
+        )
+            .fetch_all(&mut **db)
+            .await
+            .map_err(|e| rocket::response::Debug(anyhow::Error::from(e)))?;
+
+        for record in query_results {
+            buckets.push(MeasurementBucket {
+                bucket: record.bucket,
+                flavor: record.flavor,
+                color: record.color,
+                measurement_count: record.measurement_count as usize,
+                average_velocity: record.average_velocity,
+                object_count: None,
+            });
+        }
+    } else {
+        let query_results = sqlx::query!(
+            "SELECT time_bucket($3::text::interval, m.measured_at)::timestamp AS \"bucket!\", \
+            CASE WHEN $4 THEN m.flavor END AS flavor, CASE WHEN $5 THEN m.color END AS color, \
+            COUNT(*) AS \"measurement_count!\", \
+            AVG(sqrt(m.x_velocity * m.x_velocity + m.y_velocity * m.y_velocity + m.z_velocity * m.z_velocity)) AS average_velocity, \
+            COUNT(DISTINCT m.object_uuid) AS \"object_count!\" \
+            FROM measurements m WHERE m.measured_at >= $1 AND m.measured_at < $2 \
+            GROUP BY 1, 2, 3 ORDER BY 1, 2, 3",
+            start,
+            end,
+            bucket_width,
+            by_flavor,
+            by_color,
+        )
+            .fetch_all(&mut **db)
+            .await
+            .map_err(|e| rocket::response::Debug(anyhow::Error::from(e)))?;
+
+        for record in query_results {
+            buckets.push(MeasurementBucket {
+                bucket: record.bucket,
+                flavor: record.flavor,
+                color: record.color,
+                measurement_count: record.measurement_count as usize,
+                average_velocity: record.average_velocity,
+                object_count: Some(record.object_count as usize),
+            });
+        }
+    }
+    let query_complete = chrono::Utc::now().naive_utc();
+
+    let times = Times {
+        request_sent_at: Default::default(),
+        query_start,
+        query_complete,
+        data_mangling_complete: chrono::Utc::now().naive_utc(),
+        response_received_at: Default::default(),
+    };
+
+    Ok(Json(InstrumentedResponse {
+        payload: buckets,
+        times,
+    }))
+}
@@ -1,33 +1,93 @@
+use crate::routes::RouteError;
 use crate::RocketApiDatabase;
 use rocket::get;
 use rocket::serde::json::Json;
 use rocket_api_server::{
-    convert_to_uuid, parse_datetime, InstrumentedResponse, Measurement, Times,
+    convert_to_sqlx_uuid, convert_to_uuid, decode_cursor, encode_cursor, parse_datetime,
+    BoundingBox, Circle, InstrumentedResponse, Measurement, MeasurementPage, Times,
 };
 use rocket_db_pools::Connection;
 
-#[get("/find_measurements?<start>&<end>&<flavor>&<page_index>&<page_size>")]
+/// Find the most recent measurement of each object in a time range
+/// The measurements can also be limited to a bounding box given by all of
+/// min_latitude, max_latitude, left_longitude and right_longitude, where a left_longitude
+/// greater than the right_longitude crosses the antimeridian, and to a circle given by all of
+/// center_latitude, center_longitude and radius_meters
+/// Pages are ordered by object_uuid. Passing the next_cursor of a page as the cursor gets the
+/// page after it, which stays fast deep into the results and doesn't skip or repeat objects
+/// while measurements are being inserted. page_index is ignored when a cursor is given and
+/// without one it falls back to offset paging
+/// A bad start, end or cursor, or only some of the parameters of a bounding box or circle,
+/// is a 400
+#[allow(clippy::too_many_arguments)]
+#[get("/find_measurements?<start>&<end>&<flavor>&<page_index>&<page_size>&<min_latitude>&<max_latitude>&<left_longitude>&<right_longitude>&<center_latitude>&<center_longitude>&<radius_meters>&<cursor>")]
 pub async fn find_measurements(
     mut db: Connection<RocketApiDatabase>,
     start: &str,
     end: &str,
-    page_index: i64,
+    page_index: Option<i64>,
     page_size: i64,
     flavor: String,
-) -> Result<Json<InstrumentedResponse<Vec<Measurement>>>, rocket::response::Debug<anyhow::Error>> {
-    let start = parse_datetime(&start).map_err(anyhow::Error::from)?;
-    let end = parse_datetime(&end).map_err(anyhow::Error::from)?;
+    min_latitude: Option<f32>,
+    max_latitude: Option<f32>,
+    left_longitude: Option<f32>,
+    right_longitude: Option<f32>,
+    center_latitude: Option<f32>,
+    center_longitude: Option<f32>,
+    radius_meters: Option<f64>,
+    cursor: Option<&str>,
+) -> Result<Json<InstrumentedResponse<MeasurementPage>>, RouteError> {
+    let start = parse_datetime(&start)
+        .map_err(|e| RouteError::BadRequest(format!("invalid start: {}", e)))?;
+    let end =
+        parse_datetime(&end).map_err(|e| RouteError::BadRequest(format!("invalid end: {}", e)))?;
+
+    let bounding_box =
+        BoundingBox::from_params(min_latitude, max_latitude, left_longitude, right_longitude)
+            .map_err(RouteError::BadRequest)?;
+    let circle = Circle::from_params(center_latitude, center_longitude, radius_meters)
+        .map_err(RouteError::BadRequest)?;
+
+    // the cursor is the object_uuid of the last object on the previous page
+    let after_object_uuid = match cursor {
+        Some(cursor) => {
+            let object_uuid = decode_cursor(cursor)
+                .map_err(|e| RouteError::BadRequest(format!("invalid cursor: {}", e)))?;
+            Some(convert_to_sqlx_uuid(&object_uuid).map_err(anyhow::Error::from)?)
+        }
+        None => None,
+    };
+    let offset = match after_object_uuid {
+        Some(_) => 0,
+        None => page_index.unwrap_or(0) * page_size,
+    };
 
     let query_start = chrono::Utc::now().naive_utc();
     // Distinct on object_uuid and order by measured_at descending combine to give the most recent
     // measurement for each object
+    // A null area parameter switches its filter off, the longitude filter wraps around the
+    // antimeridian when the left edge is greater than the right edge and the radius filter is
+    // the haversine distance with the same earth radius as rocket_api_server::distance_meters
     let query_results = sqlx::query!(
-        "SELECT DISTINCT ON (object_uuid) * FROM measurements m WHERE m.measured_at >= $1 AND m.measured_at < $2 AND m.flavor LIKE $3 ORDER BY m.object_uuid, m.measured_at DESC LIMIT $4 OFFSET $5",
+        "SELECT DISTINCT ON (object_uuid) * FROM measurements m WHERE m.measured_at >= $1 AND m.measured_at < $2 AND m.flavor LIKE $3 \
+        AND ($6::real IS NULL OR m.latitude BETWEEN $6 AND $7::real) \
+        AND ($8::real IS NULL OR CASE WHEN $8 <= $9::real THEN m.longitude BETWEEN $8 AND $9 ELSE m.longitude >= $8 OR m.longitude <= $9 END) \
+        AND ($10::real IS NULL OR 2 * 6371000.0 * asin(least(1.0, sqrt(power(sin(radians(m.latitude - $10) / 2), 2) + cos(radians($10)) * cos(radians(m.latitude)) * power(sin(radians(m.longitude - $11::real) / 2), 2)))) <= $12::float8) \
+        AND ($13::uuid IS NULL OR m.object_uuid > $13) \
+        ORDER BY m.object_uuid, m.measured_at DESC LIMIT $4 OFFSET $5",
         start,
         end,
         flavor,
         page_size,
-        page_index * page_size,
+        offset,
+        bounding_box.map(|bounding_box| bounding_box.min_latitude),
+        bounding_box.map(|bounding_box| bounding_box.max_latitude),
+        bounding_box.map(|bounding_box| bounding_box.left_longitude),
+        bounding_box.map(|bounding_box| bounding_box.right_longitude),
+        circle.map(|circle| circle.latitude),
+        circle.map(|circle| circle.longitude),
+        circle.map(|circle| circle.radius_meters),
+        after_object_uuid,
     )
         .fetch_all(&mut **db)
         .await
@@ -68,6 +128,14 @@ pub async fn find_measurements(
         });
     }
 
+    // a short page is the last page
+    let next_cursor = match measurements.last() {
+        Some(measurement) if measurements.len() as i64 == page_size => {
+            Some(encode_cursor(&measurement.object_uuid))
+        }
+        _ => None,
+    };
+
     let data_mangling_complete = chrono::Utc::now().naive_utc();
 
     let times = Times {
@@ -79,7 +147,10 @@ pub async fn find_measurements(
     };
 
     let instrumented_response = InstrumentedResponse {
-        payload: measurements,
+        payload: MeasurementPage {
+            measurements,
+            next_cursor,
+        },
         times,
     };
     Ok(Json(instrumented_response))
@@ -0,0 +1,61 @@
+use crate::routes::RouteError;
+use crate::RocketApiDatabase;
+use rocket::get;
+use rocket::serde::json::Json;
+use rocket_api_server::{convert_to_uuid, Measurement};
+use rocket_db_pools::Connection;
+
+/// Get a single measurement by its measurement_uuid, 404 if there is no such measurement and
+/// 400 if the measurement_uuid is not a uuid
+#[get("/measurement/<measurement_uuid>")]
+pub async fn get_measurement(
+    mut db: Connection<RocketApiDatabase>,
+    measurement_uuid: &str,
+) -> Result<Option<Json<Measurement>>, RouteError> {
+    let sqlx_measurement_uuid = sqlx::types::Uuid::parse_str(measurement_uuid)
+        .map_err(|e| RouteError::BadRequest(format!("invalid measurement_uuid: {}", e)))?;
+
+    let query_result = sqlx::query!(
+        "SELECT * FROM measurements m WHERE m.measurement_uuid = $1",
+        sqlx_measurement_uuid,
+    )
+        .fetch_optional(&mut **db)
+        .await
+        .map_err(|e| rocket::response::Debug(anyhow::Error::from(e)))?;
+
+    let record = match query_result {
+        Some(record) => record,
+        None => return Ok(None),
+    };
+
+    let measurement_uuid =
+        convert_to_uuid(&record.measurement_uuid).map_err(anyhow::Error::from)?;
+
+    let sensor_uuid = convert_to_uuid(&record.sensor_uuid).map_err(anyhow::Error::from)?;
+
+    let object_uuid = convert_to_uuid(&record.object_uuid).map_err(anyhow::Error::from)?;
+
+    Ok(Some(Json(Measurement {
+        measurement_uuid: Some(measurement_uuid),
+        object_uuid,
+        sensor_uuid,
+        measured_at: record.measured_at,
+        recorded_at: Some(record.recorded_at),
+        latitude: record.latitude,
+        longitude: record.longitude,
+        altitude: record.altitude,
+        x_position: record.x_position,
+        y_position: record.y_position,
+        z_position: record.z_position,
+        x_velocity: record.x_velocity,
+        y_velocity: record.y_velocity,
+        z_velocity: record.z_velocity,
+        object_length: record.object_length,
+        object_width: record.object_width,
+        object_height: record.object_height,
+        flavor: record.flavor,
+        toppings: record.toppings,
+        color: record.color,
+        texture: record.texture,
+    })))
+}
@@ -0,0 +1,97 @@
+use crate::routes::RouteError;
+use crate::RocketApiDatabase;
+use rocket::data::{Data, Limits, ToByteUnit};
+use rocket::post;
+use rocket::serde::json::Json;
+use rocket_api_server::{convert_to_sqlx_uuid, parse_measurements};
+use rocket_db_pools::Connection;
+use uuid::Uuid;
+
+/// Insert a batch of measurements sent as a JSON array or as newline delimited JSON
+/// The whole batch is written with a single multi-row INSERT over UNNESTed arrays
+/// and the measurement uuids are returned in the order the measurements were sent
+/// A batch that can't be read or parsed, or is larger than the measurements limit, is a 400
+#[post("/measurements", data = "<data>")]
+pub async fn insert_measurements(
+    mut db: Connection<RocketApiDatabase>,
+    limits: &Limits,
+    data: Data<'_>,
+) -> Result<Json<Vec<Uuid>>, RouteError> {
+    let limit = limits.get("measurements").unwrap_or(16.mebibytes());
+    let body = data
+        .open(limit)
+        .into_string()
+        .await
+        .map_err(|e| RouteError::BadRequest(format!("could not read the batch: {}", e)))?;
+    if !body.is_complete() {
+        return Err(RouteError::BadRequest(format!(
+            "batch is larger than {}",
+            limit
+        )));
+    }
+    let measurements = parse_measurements(&body)
+        .map_err(|e| RouteError::BadRequest(format!("invalid measurements: {}", e)))?;
+    if measurements.is_empty() {
+        return Ok(Json(vec![]));
+    }
+
+    // the uuids are generated here rather than by the database so that they line up
+    // with the submitted measurements no matter which order the rows are returned in
+    let measurement_uuids: Vec<Uuid> = measurements.iter().map(|_| Uuid::new_v4()).collect();
+
+    let mut sqlx_measurement_uuids = vec![];
+    let mut measured_ats = vec![];
+    let mut object_uuids = vec![];
+    let mut sensor_uuids = vec![];
+    let mut latitudes = vec![];
+    let mut longitudes = vec![];
+    let mut altitudes = vec![];
+    let mut x_positions = vec![];
+    let mut y_positions = vec![];
+    let mut z_positions = vec![];
+    let mut x_velocities = vec![];
+    let mut y_velocities = vec![];
+    let mut z_velocities = vec![];
+    let mut object_lengths = vec![];
+    let mut object_widths = vec![];
+    let mut object_heights = vec![];
+    let mut flavors = vec![];
+    let mut toppings = vec![];
+    let mut colors = vec![];
+    let mut textures = vec![];
+    for (measurement_uuid, measurement) in measurement_uuids.iter().zip(measurements) {
+        sqlx_measurement_uuids
+            .push(convert_to_sqlx_uuid(measurement_uuid).map_err(anyhow::Error::from)?);
+        measured_ats.push(measurement.measured_at);
+        object_uuids
+            .push(convert_to_sqlx_uuid(&measurement.object_uuid).map_err(anyhow::Error::from)?);
+        sensor_uuids
+            .push(convert_to_sqlx_uuid(&measurement.sensor_uuid).map_err(anyhow::Error::from)?);
+        latitudes.push(measurement.latitude);
+        longitudes.push(measurement.longitude);
+        altitudes.push(measurement.altitude);
+        x_positions.push(measurement.x_position);
+        y_positions.push(measurement.y_position);
+        z_positions.push(measurement.z_position);
+        x_velocities.push(measurement.x_velocity);
+        y_velocities.push(measurement.y_velocity);
+        z_velocities.push(measurement.z_velocity);
+        object_lengths.push(measurement.object_length);
+        object_widths.push(measurement.object_width);
+        object_heights.push(measurement.object_height);
+        flavors.push(measurement.flavor);
+        toppings.push(measurement.toppings);
+        colors.push(measurement.color);
+        textures.push(measurement.texture);
+    }
+
+    sqlx::query!(
+            "INSERT INTO measurements (measurement_uuid, measured_at, object_uuid, sensor_uuid, latitude, longitude, altitude, x_position, y_position, z_position, x_velocity, y_velocity, z_velocity, object_length, object_width, object_height, flavor, toppings, color, texture) SELECT * FROM UNNEST($1::uuid[], $2::timestamp[], $3::uuid[], $4::uuid[], $5::real[], $6::real[], $7::real[], $8::real[], $9::real[], $10::real[], $11::real[], $12::real[], $13::real[], $14::real[], $15::real[], $16::real[], $17::text[], $18::text[], $19::text[], $20::text[])",
+            &sqlx_measurement_uuids, &measured_ats, &object_uuids, &sensor_uuids, &latitudes, &longitudes, &altitudes, &x_positions, &y_positions, &z_positions, &x_velocities, &y_velocities, &z_velocities, &object_lengths, &object_widths, &object_heights, &flavors, &toppings, &colors, &textures
+        )
+        .execute(&mut **db)
+        .await
+        .map_err(|e| rocket::response::Debug(anyhow::Error::from(e)))?;
+
+    Ok(Json(measurement_uuids))
+}
@@ -1,8 +1,23 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use rand::distributions::Uniform;
+use rand::prelude::ThreadRng;
 use rand::Rng;
 use rocket::tokio;
-use rocket_api_server::{Measurement, Path, Times, TIME_FORMAT};
+use rocket_api_server::{
+    wrap_longitude, BoundingBox, Circle, InstrumentedResponse, Measurement, MeasurementPage, Path,
+    Times, TIME_FORMAT,
+};
+
+/// Area of the earth to limit the measurements to
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Area {
+    /// no limit
+    None,
+    /// a randomly placed bounding box, which may cross the antimeridian
+    Box,
+    /// a randomly placed circle
+    Radius,
+}
 
 /// Simulate a client getting measurements from the API server
 #[derive(Parser)]
@@ -42,6 +57,23 @@ struct Args {
     /// flavor of measurements to get
     #[arg(short, long, default_value = "vanilla")]
     flavor: String,
+
+    /// area to limit the measurements to, placed randomly on each get
+    #[arg(long, value_enum, default_value_t = Area::None)]
+    area: Area,
+
+    /// width and height of the bounding box in degrees
+    #[arg(long, default_value_t = 20.0)]
+    box_degrees: f32,
+
+    /// radius of the circle in meters
+    #[arg(long, default_value_t = 1_000_000.0)]
+    radius_meters: f64,
+
+    /// walk all the pages of each get by following the next_cursor of each page
+    /// instead of getting a single page by page_index
+    #[arg(long, default_value_t = false)]
+    all_pages: bool,
 }
 
 //noinspection ALL
@@ -60,39 +92,104 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let start = end - chrono::Duration::seconds(args.window_seconds as i64);
         let page_index = rng.sample(page_index_range);
 
-        let url = format!(
-            "{}?start={}&end={}&flavor={}&page_index={}&page_size=100",
+        let mut url = format!(
+            "{}?start={}&end={}&flavor={}&page_size=100",
             args.server_url,
             start.format(TIME_FORMAT),
             end.format(TIME_FORMAT),
             args.flavor,
-            page_index,
         );
 
-        let request_sent_at = chrono::Utc::now().naive_utc();
-        let result = client.get(&url).send().await;
+        let mut bounding_box = None;
+        let mut circle = None;
+        match args.area {
+            Area::None => {}
+            Area::Box => {
+                let random_box = random_bounding_box(&mut rng, args.box_degrees);
+                url.push_str(&format!(
+                    "&min_latitude={}&max_latitude={}&left_longitude={}&right_longitude={}",
+                    random_box.min_latitude,
+                    random_box.max_latitude,
+                    random_box.left_longitude,
+                    random_box.right_longitude,
+                ));
+                bounding_box = Some(random_box);
+            }
+            Area::Radius => {
+                let random_circle = Circle {
+                    latitude: rng.gen_range(-80.0..80.0),
+                    longitude: rng.gen_range(-180.0..180.0),
+                    radius_meters: args.radius_meters,
+                };
+                url.push_str(&format!(
+                    "&center_latitude={}&center_longitude={}&radius_meters={}",
+                    random_circle.latitude, random_circle.longitude, random_circle.radius_meters,
+                ));
+                circle = Some(random_circle);
+            }
+        }
+
+        // the first page is got by page_index and the rest by following the cursor
+        let mut measurements: Vec<Measurement> = vec![];
+        let mut cursor: Option<String> = None;
+        loop {
+            let page_url = match &cursor {
+                Some(cursor) => format!("{}&cursor={}", url, cursor),
+                None => format!("{}&page_index={}", url, page_index),
+            };
+
+            let request_sent_at = chrono::Utc::now().naive_utc();
+            let result = client.get(&page_url).send().await;
+
+            if let Err(err) = result {
+                println!("Error: {}", err);
+                break;
+            }
+
+            let response = result.unwrap();
+            let instrumented_response: InstrumentedResponse<MeasurementPage> =
+                response.json().await?;
+            let page = instrumented_response.payload;
+            let times = Times {
+                request_sent_at,
+                response_received_at: chrono::Utc::now().naive_utc(),
+                ..instrumented_response.times
+            };
+            println!(
+                "{} -> n: {}, {}, {:?}",
+                &page_url,
+                page.measurements.len(),
+                times,
+                page.measurements
+            );
 
-        if let Err(err) = result {
-            println!("Error: {}", err);
-            continue;
+            measurements.extend(page.measurements);
+            cursor = page.next_cursor;
+            if !args.all_pages || cursor.is_none() {
+                break;
+            }
         }
 
-        let response = result.unwrap();
-        let instrumented_response: rocket_api_server::InstrumentedResponse<Vec<Measurement>> =
-            response.json().await?;
-        let measurements = instrumented_response.payload;
-        let times = Times {
-            request_sent_at,
-            response_received_at: chrono::Utc::now().naive_utc(),
-            ..instrumented_response.times
-        };
-        println!(
-            "{} -> n: {}, {}, {:?}",
-            &url,
-            measurements.len(),
-            times,
-            measurements
-        );
+        if args.all_pages {
+            println!("Got {} measurements from all pages", measurements.len());
+        }
+
+        // check the server kept to the area
+        let outside_count = measurements
+            .iter()
+            .filter(|measurement| {
+                let outside_box = bounding_box.is_some_and(|bounding_box| {
+                    !bounding_box.contains(measurement.latitude, measurement.longitude)
+                });
+                let outside_circle = circle.is_some_and(|circle| {
+                    !circle.contains(measurement.latitude, measurement.longitude)
+                });
+                outside_box || outside_circle
+            })
+            .count();
+        if outside_count > 0 {
+            println!("Error: {} measurements are outside the area", outside_count);
+        }
 
         if args.iterations != 0 {
             iteration_count += 1;
@@ -124,3 +221,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// a bounding box of the given size centered on a random point, the longitudes are wrapped
+/// so boxes near the antimeridian cross it
+fn random_bounding_box(rng: &mut ThreadRng, degrees: f32) -> BoundingBox {
+    let latitude: f32 = rng.gen_range(-90.0..90.0);
+    let longitude: f32 = rng.gen_range(-180.0..180.0);
+    BoundingBox {
+        min_latitude: (latitude - degrees / 2.0).max(-90.0),
+        max_latitude: (latitude + degrees / 2.0).min(90.0),
+        left_longitude: wrap_longitude(longitude - degrees / 2.0),
+        right_longitude: wrap_longitude(longitude + degrees / 2.0),
+    }
+}
@@ -35,6 +35,15 @@ struct Args {
     /// URL of the API server
     #[arg(short, long, default_value = "http://localhost:8000/api/measurement")]
     server_url: String,
+
+    /// Number of measurements to send in each request to the batch endpoint
+    /// zero means send each measurement in its own request to server_url
+    #[arg(short, long, default_value_t = 0)]
+    batch_size: usize,
+
+    /// URL of the API server batch endpoint, used when batch_size is not zero
+    #[arg(long, default_value = "http://localhost:8000/api/measurements")]
+    batch_server_url: String,
 }
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -83,43 +92,59 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // until we get through all the objects.  Then we sleep for the interval and reset the object index.
     while tick < args.tick_count || args.tick_count == 0 {
         // send a batch of measurements using the specified number of futures
+        // in batch mode each future sends batch_size measurements in a single request
         let mut futures = Vec::new();
         for _index in 0..args.future_count {
             if object_index >= args.object_count {
                 break;
             }
 
-            let measurement = Measurement {
-                measurement_uuid: None,
-                recorded_at: None,
-                object_uuid: object_uuids[object_index],
-                sensor_uuid,
-                measured_at: chrono::Utc::now().naive_utc(),
-                latitude: 10.0 + object_index as f32 * 0.2,
-                longitude: 2.0 + tick as f32 * 0.002,
-                altitude: 110.0,
-                x_position: 1.0 + object_index as f32 * 0.1,
-                y_position: 1.0 + tick as f32 * 0.01,
-                z_position: 1.0,
-                x_velocity: 0.01,
-                y_velocity: 0.02,
-                z_velocity: 0.03,
-                flavor: pick_from_list(&mut rng, &flavors),
-                toppings: pick_from_list(&mut rng, &toppings),
-                color: pick_from_list(&mut rng, &colors),
-                texture: pick_from_list(&mut rng, &textures),
-                object_height: Some(33.0),
-                object_width: Some(22.0),
-                object_length: Some(10.0 + object_index as f32 * 0.5),
-            };
+            let mut measurements = Vec::new();
+            while measurements.len() < args.batch_size.max(1) && object_index < args.object_count {
+                measurements.push(Measurement {
+                    measurement_uuid: None,
+                    recorded_at: None,
+                    object_uuid: object_uuids[object_index],
+                    sensor_uuid,
+                    measured_at: chrono::Utc::now().naive_utc(),
+                    latitude: 10.0 + object_index as f32 * 0.2,
+                    longitude: 2.0 + tick as f32 * 0.002,
+                    altitude: 110.0,
+                    x_position: 1.0 + object_index as f32 * 0.1,
+                    y_position: 1.0 + tick as f32 * 0.01,
+                    z_position: 1.0,
+                    x_velocity: 0.01,
+                    y_velocity: 0.02,
+                    z_velocity: 0.03,
+                    flavor: pick_from_list(&mut rng, &flavors),
+                    toppings: pick_from_list(&mut rng, &toppings),
+                    color: pick_from_list(&mut rng, &colors),
+                    texture: pick_from_list(&mut rng, &textures),
+                    object_height: Some(33.0),
+                    object_width: Some(22.0),
+                    object_length: Some(10.0 + object_index as f32 * 0.5),
+                });
+                object_index += 1;
+            }
 
             let client = client.clone();
+            let batch_mode = args.batch_size > 0;
             let server_url = args.server_url.clone();
+            let batch_server_url = args.batch_server_url.clone();
             let future = async move {
-                let _result = client.post(server_url).json(&measurement).send().await;
+                if batch_mode {
+                    let _result = client
+                        .post(batch_server_url)
+                        .json(&measurements)
+                        .send()
+                        .await;
+                } else {
+                    for measurement in measurements {
+                        let _result = client.post(&server_url).json(&measurement).send().await;
+                    }
+                }
             };
             futures.push(future);
-            object_index += 1;
         }
 
         futures::future::join_all(futures).await;
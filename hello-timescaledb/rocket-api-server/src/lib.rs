@@ -1,4 +1,4 @@
-use chrono::{NaiveDateTime, ParseResult};
+use chrono::{NaiveDateTime, ParseResult, Timelike};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::{Display, Formatter};
@@ -80,12 +80,212 @@ pub struct Diagnostics {
     pub average_measurement_size_bytes: f64,
 }
 
+/// MeasurementPage is one page of measurements
+/// next_cursor is passed back as the cursor to get the next page and
+/// is None when there are no more pages
+/// The cursor is keyed on object_uuid order, the next page starts after the last object_uuid
+/// of this one, and page_index is ignored when a cursor is given
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MeasurementPage {
+    pub measurements: Vec<Measurement>,
+    pub next_cursor: Option<String>,
+}
+
+/// MeasurementBucket summarizes the measurements in one time bucket
+/// flavor and color are only set when the buckets are grouped by them
+/// object_count is None when the bucket comes from the continuous aggregate,
+/// distinct counts can't be rolled up from the per minute buckets
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MeasurementBucket {
+    pub bucket: NaiveDateTime,
+    pub flavor: Option<String>,
+    pub color: Option<String>,
+    pub measurement_count: usize,
+    pub average_velocity: Option<f64>,
+    pub object_count: Option<usize>,
+}
+
+/// BoundingBox is an area between two latitudes and two longitudes
+/// left_longitude is the western edge and right_longitude the eastern edge,
+/// when left_longitude is greater than right_longitude the box crosses the antimeridian
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct BoundingBox {
+    pub min_latitude: f32,
+    pub max_latitude: f32,
+    pub left_longitude: f32,
+    pub right_longitude: f32,
+}
+
+impl BoundingBox {
+    /// from_params builds a bounding box from optional query parameters, there is no box when
+    /// none of them are given and it is an error to give only some of them
+    pub fn from_params(
+        min_latitude: Option<f32>,
+        max_latitude: Option<f32>,
+        left_longitude: Option<f32>,
+        right_longitude: Option<f32>,
+    ) -> Result<Option<BoundingBox>, String> {
+        match (min_latitude, max_latitude, left_longitude, right_longitude) {
+            (Some(min_latitude), Some(max_latitude), Some(left_longitude), Some(right_longitude)) => {
+                Ok(Some(BoundingBox {
+                    min_latitude,
+                    max_latitude,
+                    left_longitude,
+                    right_longitude,
+                }))
+            }
+            (None, None, None, None) => Ok(None),
+            _ => Err(
+                "a bounding box needs min_latitude, max_latitude, left_longitude and right_longitude"
+                    .to_string(),
+            ),
+        }
+    }
+
+    pub fn contains(&self, latitude: f32, longitude: f32) -> bool {
+        latitude >= self.min_latitude
+            && latitude <= self.max_latitude
+            && is_in_bounds(self.left_longitude, self.right_longitude, longitude)
+    }
+}
+
+/// Circle is the area within radius_meters of a point on the surface of the earth
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Circle {
+    pub latitude: f32,
+    pub longitude: f32,
+    pub radius_meters: f64,
+}
+
+impl Circle {
+    /// from_params builds a circle from optional query parameters, there is no circle when
+    /// none of them are given and it is an error to give only some of them
+    pub fn from_params(
+        latitude: Option<f32>,
+        longitude: Option<f32>,
+        radius_meters: Option<f64>,
+    ) -> Result<Option<Circle>, String> {
+        match (latitude, longitude, radius_meters) {
+            (Some(latitude), Some(longitude), Some(radius_meters)) => Ok(Some(Circle {
+                latitude,
+                longitude,
+                radius_meters,
+            })),
+            (None, None, None) => Ok(None),
+            _ => Err(
+                "a radius query needs center_latitude, center_longitude and radius_meters"
+                    .to_string(),
+            ),
+        }
+    }
+
+    pub fn contains(&self, latitude: f32, longitude: f32) -> bool {
+        distance_meters(self.latitude, self.longitude, latitude, longitude) <= self.radius_meters
+    }
+}
+
+/// mean radius of the earth, the same value is used by the radius query in find_measurements
+pub const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+/// is_in_bounds checks a longitude against the western and eastern edges of an area
+/// the area crosses the antimeridian when the left edge is greater than the right edge
+pub fn is_in_bounds(left_longitude: f32, right_longitude: f32, value: f32) -> bool {
+    let min = left_longitude.min(right_longitude);
+    let max = left_longitude.max(right_longitude);
+    let in_range = value >= min && value <= max;
+    if left_longitude > right_longitude {
+        // the bounds themselves are still in the area
+        !in_range || value == min || value == max
+    } else {
+        in_range
+    }
+}
+
+/// wrap_longitude brings a longitude into the range -180 to 180
+pub fn wrap_longitude(longitude: f32) -> f32 {
+    (longitude + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// distance_meters is the great circle distance between two points using the haversine formula
+pub fn distance_meters(
+    from_latitude: f32,
+    from_longitude: f32,
+    to_latitude: f32,
+    to_longitude: f32,
+) -> f64 {
+    let from_latitude = (from_latitude as f64).to_radians();
+    let to_latitude = (to_latitude as f64).to_radians();
+    let latitude_delta = to_latitude - from_latitude;
+    let longitude_delta = (to_longitude as f64 - from_longitude as f64).to_radians();
+
+    let haversine = (latitude_delta / 2.0).sin().powi(2)
+        + from_latitude.cos() * to_latitude.cos() * (longitude_delta / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * haversine.sqrt().min(1.0).asin()
+}
+
 pub const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
 
 pub fn parse_datetime(datetime_str: &&str) -> ParseResult<NaiveDateTime> {
     chrono::NaiveDateTime::parse_from_str(datetime_str, TIME_FORMAT)
 }
 
+/// parse_bucket_width checks a bucket width such as "5 minutes" before it is used as an interval
+/// and returns its length in seconds, the width is a positive whole number of seconds, minutes,
+/// hours, days or weeks
+pub fn parse_bucket_width(bucket_width: &str) -> Result<i64, String> {
+    let invalid = || {
+        format!(
+            "invalid bucket_width {:?}, expected e.g. \"5 minutes\"",
+            bucket_width
+        )
+    };
+    let (count, unit) = bucket_width.trim().split_once(' ').ok_or_else(invalid)?;
+    let count: i64 = count.parse().map_err(|_| invalid())?;
+    let unit = unit.trim();
+    let unit_seconds = match unit.strip_suffix('s').unwrap_or(unit) {
+        "second" => 1,
+        "minute" => 60,
+        "hour" => 60 * 60,
+        "day" => 24 * 60 * 60,
+        "week" => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    if count <= 0 {
+        return Err(invalid());
+    }
+    count.checked_mul(unit_seconds).ok_or_else(invalid)
+}
+
+/// is_whole_minute is true when the time has no seconds, which is where the buckets of the
+/// measurements_by_minute continuous aggregate start
+pub fn is_whole_minute(datetime: &NaiveDateTime) -> bool {
+    datetime.second() == 0 && datetime.nanosecond() == 0
+}
+
+/// parse_measurements reads a batch of measurements from either a JSON array
+/// or newline delimited JSON with one measurement per line
+pub fn parse_measurements(body: &str) -> Result<Vec<Measurement>, serde_json::Error> {
+    let body = body.trim_start();
+    if body.starts_with('[') {
+        return serde_json::from_str(body);
+    }
+
+    body.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect()
+}
+
+/// encode_cursor makes the cursor for the page after the given object
+/// clients should treat the cursor as opaque
+pub fn encode_cursor(object_uuid: &uuid::Uuid) -> String {
+    object_uuid.to_simple().to_string()
+}
+
+pub fn decode_cursor(cursor: &str) -> Result<uuid::Uuid, uuid::Error> {
+    uuid::Uuid::parse_str(cursor)
+}
+
 pub fn convert_to_sqlx_uuid(
     uuid: &uuid::Uuid,
 ) -> Result<sqlx::types::Uuid, sqlx::types::uuid::Error> {
@@ -95,3 +295,227 @@ pub fn convert_to_sqlx_uuid(
 pub fn convert_to_uuid(uuid: &sqlx::types::Uuid) -> Result<uuid::Uuid, uuid::Error> {
     uuid::Uuid::parse_str(&uuid.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement_json(latitude: f32) -> String {
+        serde_json::json!({
+            "object_uuid": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "sensor_uuid": "9c5b94b1-35ad-49bb-b118-8e8fc24abf80",
+            "measured_at": "2024-06-01T12:00:00",
+            "latitude": latitude,
+            "longitude": 10.0,
+            "altitude": 0.0,
+            "x_position": 1.0,
+            "y_position": 2.0,
+            "z_position": 3.0,
+            "x_velocity": 0.5,
+            "y_velocity": 0.0,
+            "z_velocity": 0.0,
+            "flavor": "vanilla"
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn parse_single_measurement() {
+        let measurements = parse_measurements(&measurement_json(45.0)).unwrap();
+        assert_eq!(measurements.len(), 1);
+        assert_eq!(measurements[0].latitude, 45.0);
+        assert_eq!(measurements[0].flavor.as_deref(), Some("vanilla"));
+        assert!(measurements[0].measurement_uuid.is_none());
+    }
+
+    #[test]
+    fn parse_json_array() {
+        let body = format!("  [{}, {}]", measurement_json(45.0), measurement_json(46.0));
+        let measurements = parse_measurements(&body).unwrap();
+        assert_eq!(measurements.len(), 2);
+        assert_eq!(measurements[1].latitude, 46.0);
+        assert!(parse_measurements("[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_newline_delimited_json() {
+        let body = format!(
+            "{}\n\n{}\r\n{}\n",
+            measurement_json(45.0),
+            measurement_json(46.0),
+            measurement_json(47.0)
+        );
+        let measurements = parse_measurements(&body).unwrap();
+        assert_eq!(measurements.len(), 3);
+        assert_eq!(measurements[2].latitude, 47.0);
+    }
+
+    #[test]
+    fn parse_bad_json() {
+        assert!(parse_measurements("[{\"latitude\": 45.0}]").is_err());
+        assert!(parse_measurements(&format!("{}\nnot json", measurement_json(45.0))).is_err());
+        assert!(parse_measurements(&format!("[{}", measurement_json(45.0))).is_err());
+    }
+
+    #[test]
+    fn normal_in_bounds() {
+        assert!(is_in_bounds(50.0, 60.0, 55.0));
+        assert!(is_in_bounds(50.0, 60.0, 50.0));
+        assert!(is_in_bounds(50.0, 60.0, 60.0));
+    }
+
+    #[test]
+    fn normal_out_of_bounds() {
+        assert!(!is_in_bounds(50.0, 60.0, 45.0));
+        assert!(!is_in_bounds(50.0, 60.0, 65.0));
+    }
+
+    #[test]
+    fn straddle_in_bounds() {
+        assert!(is_in_bounds(170.0, 10.0, 175.0));
+        assert!(is_in_bounds(170.0, 10.0, 5.0));
+        assert!(is_in_bounds(-100.0, -80.0, -90.0));
+        assert!(is_in_bounds(-10.0, 10.0, 1.0));
+        assert!(is_in_bounds(151.0, -177.0, 179.9));
+        assert!(is_in_bounds(151.0, -177.0, -178.0));
+        assert!(is_in_bounds(151.0, -177.0, -180.0));
+        assert!(is_in_bounds(151.0, -177.0, 180.0));
+    }
+
+    #[test]
+    fn straddle_out_of_bounds() {
+        assert!(!is_in_bounds(170.0, 10.0, 15.0));
+        assert!(!is_in_bounds(170.0, 10.0, 165.0));
+        assert!(!is_in_bounds(-100.0, -80.0, -110.0));
+        assert!(!is_in_bounds(-100.0, -80.0, -70.0));
+        assert!(!is_in_bounds(-10.0, 10.0, 11.0));
+        assert!(!is_in_bounds(151.0, -177.0, -176.0));
+        assert!(!is_in_bounds(151.0, -177.0, 150.9));
+    }
+
+    #[test]
+    fn straddle_edges_in_bounds() {
+        assert!(is_in_bounds(170.0, 10.0, 170.0));
+        assert!(is_in_bounds(170.0, 10.0, 10.0));
+        assert!(is_in_bounds(151.0, -177.0, 151.0));
+        assert!(is_in_bounds(151.0, -177.0, -177.0));
+    }
+
+    #[test]
+    fn bounding_box_contains() {
+        let bounding_box = BoundingBox {
+            min_latitude: -10.0,
+            max_latitude: 10.0,
+            left_longitude: 170.0,
+            right_longitude: -170.0,
+        };
+        assert!(bounding_box.contains(0.0, 180.0));
+        assert!(bounding_box.contains(10.0, -170.0));
+        assert!(!bounding_box.contains(0.0, 0.0));
+        assert!(!bounding_box.contains(11.0, 175.0));
+    }
+
+    #[test]
+    fn area_params() {
+        let bounding_box =
+            BoundingBox::from_params(Some(-10.0), Some(10.0), Some(170.0), Some(-170.0))
+                .unwrap()
+                .unwrap();
+        assert_eq!(bounding_box.left_longitude, 170.0);
+        assert!(BoundingBox::from_params(None, None, None, None)
+            .unwrap()
+            .is_none());
+        assert!(BoundingBox::from_params(Some(-10.0), Some(10.0), Some(170.0), None).is_err());
+        assert!(BoundingBox::from_params(None, None, None, Some(-170.0)).is_err());
+
+        let circle = Circle::from_params(Some(0.0), Some(179.5), Some(100_000.0))
+            .unwrap()
+            .unwrap();
+        assert_eq!(circle.radius_meters, 100_000.0);
+        assert!(Circle::from_params(None, None, None).unwrap().is_none());
+        assert!(Circle::from_params(Some(0.0), Some(179.5), None).is_err());
+        assert!(Circle::from_params(None, None, Some(100_000.0)).is_err());
+    }
+
+    #[test]
+    fn whole_minutes() {
+        assert!(is_whole_minute(
+            &parse_datetime(&"2024-06-01T12:05:00").unwrap()
+        ));
+        assert!(!is_whole_minute(
+            &parse_datetime(&"2024-06-01T12:05:30").unwrap()
+        ));
+        let almost =
+            parse_datetime(&"2024-06-01T12:05:00").unwrap() + chrono::Duration::milliseconds(1);
+        assert!(!is_whole_minute(&almost));
+    }
+
+    #[test]
+    fn wrap_longitudes() {
+        assert_eq!(wrap_longitude(0.0), 0.0);
+        assert_eq!(wrap_longitude(-179.0), -179.0);
+        assert_eq!(wrap_longitude(190.0), -170.0);
+        assert_eq!(wrap_longitude(-190.0), 170.0);
+        assert_eq!(wrap_longitude(540.0), -180.0);
+        assert_eq!(wrap_longitude(180.0), -180.0);
+    }
+
+    #[test]
+    fn known_distances() {
+        let half_circumference = std::f64::consts::PI * EARTH_RADIUS_METERS;
+        assert_eq!(distance_meters(45.0, 10.0, 45.0, 10.0), 0.0);
+        assert!((distance_meters(0.0, 0.0, 1.0, 0.0) - half_circumference / 180.0).abs() < 1.0);
+        assert!((distance_meters(0.0, 0.0, 0.0, 180.0) - half_circumference).abs() < 1.0);
+        assert!((distance_meters(90.0, 0.0, -90.0, 0.0) - half_circumference).abs() < 1.0);
+        // the short way round across the antimeridian
+        assert!((distance_meters(0.0, 179.0, 0.0, -179.0) - half_circumference / 90.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn circle_contains() {
+        let circle = Circle {
+            latitude: 0.0,
+            longitude: 179.5,
+            radius_meters: 100_000.0,
+        };
+        assert!(circle.contains(0.0, -179.9));
+        assert!(!circle.contains(0.0, 178.0));
+    }
+
+    #[test]
+    fn parse_bucket_widths() {
+        assert_eq!(parse_bucket_width("5 minutes"), Ok(300));
+        assert_eq!(parse_bucket_width("1 minute"), Ok(60));
+        assert_eq!(parse_bucket_width("30 seconds"), Ok(30));
+        assert_eq!(parse_bucket_width("2 hours"), Ok(7200));
+        assert_eq!(parse_bucket_width("1 week"), Ok(604800));
+        assert!(parse_bucket_width("5").is_err());
+        assert!(parse_bucket_width("0 minutes").is_err());
+        assert!(parse_bucket_width("-5 minutes").is_err());
+        assert!(parse_bucket_width("five minutes").is_err());
+        assert!(parse_bucket_width("5 fortnights").is_err());
+        assert!(parse_bucket_width("5 minutess").is_err());
+        assert!(parse_bucket_width("5 minutes'; DROP TABLE measurements").is_err());
+        assert!(parse_bucket_width("9223372036854775807 weeks").is_err());
+    }
+
+    #[test]
+    fn cursor_round_trip() {
+        let object_uuid = uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        let cursor = encode_cursor(&object_uuid);
+        assert_eq!(cursor, "67e5504410b1426f9247bb680e5fe0c8");
+        assert_eq!(decode_cursor(&cursor).unwrap(), object_uuid);
+        assert_eq!(
+            decode_cursor(&encode_cursor(&uuid::Uuid::nil())).unwrap(),
+            uuid::Uuid::nil()
+        );
+    }
+
+    #[test]
+    fn decode_bad_cursor() {
+        assert!(decode_cursor("").is_err());
+        assert!(decode_cursor("not a cursor").is_err());
+        assert!(decode_cursor("67e5504410b1426f9247bb680e5fe0c").is_err());
+        assert!(decode_cursor("67e5504410b1426f9247bb680e5fe0cz").is_err());
+    }
+}